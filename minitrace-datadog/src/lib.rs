@@ -6,6 +6,7 @@ use std::collections::HashMap;
 use std::net::SocketAddr;
 
 use minitrace::collector::Reporter;
+use minitrace::collector::SpanStatus;
 use minitrace::prelude::*;
 use rmp_serde::Serializer;
 use serde::Serialize;
@@ -53,7 +54,7 @@ impl DatadogReporter {
                             .collect(),
                     )
                 },
-                error_code: 0,
+                error_code: (s.status == SpanStatus::Error) as i32,
                 span_id: s.span_id.0,
                 trace_id: s.trace_id.0 as u64,
                 parent_id: s.parent_id.0,
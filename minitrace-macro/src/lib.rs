@@ -12,83 +12,818 @@ extern crate proc_macro;
 #[macro_use]
 extern crate proc_macro_error;
 
+mod util;
+
 use std::collections::HashSet;
 
+use quote::quote;
 use quote::quote_spanned;
+use syn::parse::Parser;
 use syn::spanned::Spanned;
 use syn::*;
 
+use crate::util::parse_duration;
+use crate::util::token_stream_with_error;
+
 struct Args {
     name: Name,
     enter_on_poll: bool,
+    lazy: bool,
+    err: bool,
+    ok_code: Option<Expr>,
+    err_code: Option<Expr>,
+    min_duration: Option<std::time::Duration>,
+    sample: Option<f64>,
+    parent_id: Option<Expr>,
+    /// The name of the enclosing span to use as the parent, given by `parent = "..."`.
+    parent: Option<LitStr>,
+    timestamp: bool,
+    buffer: Option<usize>,
+    root: bool,
+    guard: Option<Ident>,
+    recurse: bool,
+    recurse_only: Option<Ident>,
+    child_count: bool,
+    coalesce: bool,
+    scope: Option<Scope>,
+    async_trait: Option<bool>,
+    catch_panic: bool,
+    record_return: bool,
+    passthrough: bool,
+    display: Vec<Ident>,
+    variables: Vec<Ident>,
+    skip: Vec<Ident>,
+    /// The recorded parameters whose value is masked, each with the function redacting its
+    /// formatted value, or none to record `***`.
+    redact: Vec<(Ident, Option<Expr>)>,
+    type_params: Vec<Ident>,
+    krate: Path,
+    deprecated: Vec<(Ident, &'static str)>,
+    /// The keys of the given arguments, with deprecated keys renamed, so that an error about an
+    /// argument can point at it.
+    keys: Vec<Ident>,
 }
 
+#[allow(clippy::enum_variant_names)]
 enum Name {
     Plain(String),
+    /// A format string interpolating parameters, e.g. `"handle_user({user_id})"`.
+    Format(LitStr),
+    /// A parameter whose value, formatted with `Display`, is the name.
+    From(Ident),
+    /// A path to a `&'static str` constant, e.g. `Self::SPAN_NAME`.
+    Const(ExprPath),
     FullName,
 }
 
+/// Where the span of a non-async function can be used, given by `scope = Scope::...`, mirroring
+/// `minitrace::Scope`.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Scope {
+    /// A `LocalSpan`, only usable on the current thread.
+    Local,
+    /// A `Span`, set as the local parent, which can be used on other threads.
+    Threads,
+}
+
+/// The arguments of the attribute, e.g. `name = "foo", display = [a, b]`.
+///
+/// Unlike `syn::AttributeArgs`, the value of an argument can be any expression.
+type AttributeArgs = punctuated::Punctuated<Expr, Token![,]>;
+
+/// The deprecated spellings of arguments and the spellings replacing them. A deprecated spelling
+/// is accepted with a warning.
+const DEPRECATED_ARGUMENTS: &[(&str, &str)] = &[("catch_err", "err")];
+
+/// The alternative spellings of arguments and the spellings they stand for.
+const ALIASED_ARGUMENTS: &[(&str, &str)] = &[("recorder", "guard")];
+
+/// The prefix of the environment variable holding the default arguments of `#[trace]`, e.g.
+/// `short_name = true`, followed by the upper-cased name of the crate they apply to, since the
+/// environment of the compiler is shared by the crate being compiled and its dependencies.
+const DEFAULTS_ENV_PREFIX: &str = "MINITRACE_TRACE_DEFAULTS_";
+
+/// Groups of arguments of which at most one can be given, so that a default argument is
+/// overridden by any argument of its group.
+const EXCLUSIVE_ARGUMENTS: &[&[&str]] = &[&["name", "short_name", "name_const", "name_from"], &[
+    "guard", "recorder",
+]];
+
+/// Stores the value of an argument, returning `false` if the value is not of the expected kind.
+type ArgumentParser = fn(&mut GivenArgs, &Signature, &Expr) -> bool;
+
+/// The arguments accepted by `#[trace]` and their parsers. The error of an unknown argument lists
+/// them, along with [`ALIASED_ARGUMENTS`].
+const ARGUMENTS: &[(&str, ArgumentParser)] = &[
+    ("name", |args, _, value| {
+        set(&mut args.name, lit_str(value).cloned().map(Some))
+    }),
+    ("short_name", |args, _, value| {
+        set(&mut args.short_name, lit_bool(value))
+    }),
+    ("name_const", |args, _, value| match value {
+        Expr::Path(path) => set(&mut args.name_const, Some(Some(path.clone()))),
+        _ => false,
+    }),
+    ("name_from", |args, sig, value| match plain_ident(value) {
+        Some(ident) => {
+            if !param_names(sig).any(|param| param == *ident) || ident == "self" {
+                abort!(ident, "`{}` is not a parameter of the function", ident);
+            }
+            set(&mut args.name_from, Some(Some(ident.clone())))
+        }
+        None => false,
+    }),
+    ("crate", |args, _, value| {
+        set(&mut args.krate, plain_path(value).cloned().map(Some))
+    }),
+    ("enter_on_poll", |args, _, value| {
+        set(&mut args.enter_on_poll, lit_bool(value))
+    }),
+    ("lazy", |args, _, value| {
+        set(&mut args.lazy, lit_bool(value))
+    }),
+    ("err", |args, _, value| set(&mut args.err, lit_bool(value))),
+    ("display", |args, sig, value| {
+        set(
+            &mut args.display,
+            array(value).map(|elems| parse_param_names(sig, elems)),
+        )
+    }),
+    ("type_params", |args, _, value| {
+        set(&mut args.type_params, lit_bool(value))
+    }),
+    ("min_duration", |args, _, value| match lit_str(value) {
+        Some(s) => match parse_duration(&s.value()) {
+            Some(duration) => set(&mut args.min_duration, Some(Some(duration))),
+            None => abort!(s, "invalid duration, expected e.g. \"100us\" or \"1ms\""),
+        },
+        None => false,
+    }),
+    ("sample", |args, _, value| match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Float(f), ..
+        }) => match f.base10_parse::<f64>() {
+            Ok(ratio) if (0.0..=1.0).contains(&ratio) => set(&mut args.sample, Some(Some(ratio))),
+            _ => abort!(
+                f,
+                "invalid sample ratio, expected a number between 0.0 and 1.0"
+            ),
+        },
+        _ => false,
+    }),
+    ("parent_id", |args, _, value| {
+        set(&mut args.parent_id, Some(Some(value.clone())))
+    }),
+    ("parent", |args, _, value| {
+        set(&mut args.parent, lit_str(value).cloned().map(Some))
+    }),
+    ("timestamp", |args, _, value| {
+        set(&mut args.timestamp, lit_bool(value))
+    }),
+    ("buffer", |args, _, value| match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Int(n), ..
+        }) => match n.base10_parse::<usize>() {
+            Ok(capacity) => set(&mut args.buffer, Some(Some(capacity))),
+            Err(_) => abort!(n, "invalid buffer size, expected a non-negative integer"),
+        },
+        _ => false,
+    }),
+    ("test", |args, _, value| {
+        set(&mut args.test, lit_bool(value).map(Some))
+    }),
+    ("root", |args, _, value| {
+        set(&mut args.root, lit_bool(value))
+    }),
+    ("guard", |args, _, value| {
+        set(&mut args.guard, plain_ident(value).cloned().map(Some))
+    }),
+    ("variables", |args, sig, value| {
+        set(
+            &mut args.variables,
+            array(value).map(|elems| parse_param_names(sig, elems)),
+        )
+    }),
+    ("skip", |args, sig, value| {
+        set(
+            &mut args.skip,
+            array(value).map(|elems| parse_param_names(sig, elems)),
+        )
+    }),
+    ("redact", |args, sig, value| {
+        set(
+            &mut args.redact,
+            array(value).map(|elems| parse_redacted_params(sig, elems)),
+        )
+    }),
+    ("recurse", |args, _, value| {
+        set(&mut args.recurse, lit_bool(value))
+    }),
+    ("recurse_only", |args, _, value| {
+        set(
+            &mut args.recurse_only,
+            plain_ident(value).cloned().map(Some),
+        )
+    }),
+    ("child_count", |args, _, value| {
+        set(&mut args.child_count, lit_bool(value))
+    }),
+    ("coalesce", |args, _, value| {
+        set(&mut args.coalesce, lit_bool(value))
+    }),
+    ("scope", |args, _, value| match plain_path(value) {
+        Some(path) => {
+            // Only the variant is checked, so that `minitrace::Scope::Threads` or an imported
+            // `Threads` are accepted as well.
+            let variant = path
+                .segments
+                .last()
+                .map(|segment| segment.ident.to_string());
+            let scope = match variant.as_deref() {
+                Some("Local") => Scope::Local,
+                Some("Threads") => Scope::Threads,
+                _ => abort!(
+                    path,
+                    "invalid scope, expected `Scope::Local` or `Scope::Threads`"
+                ),
+            };
+            set(&mut args.scope, Some(Some(scope)))
+        }
+        None => false,
+    }),
+    ("ok_code", |args, _, value| {
+        set(&mut args.ok_code, Some(Some(value.clone())))
+    }),
+    ("err_code", |args, _, value| {
+        set(&mut args.err_code, Some(Some(value.clone())))
+    }),
+    ("async_trait", |args, _, value| {
+        set(&mut args.async_trait, lit_bool(value).map(Some))
+    }),
+    // Handled before the other arguments, as it can not be used together with them.
+    ("conventional", |_, _, _| false),
+    ("catch_panic", |args, _, value| {
+        set(&mut args.catch_panic, lit_bool(value))
+    }),
+    ("record_return", |args, _, value| {
+        set(&mut args.record_return, lit_bool(value))
+    }),
+    ("passthrough", |args, _, value| {
+        set(&mut args.passthrough, lit_bool(value))
+    }),
+];
+
+/// The values of the arguments given to `#[trace]`, before they are checked against each other.
+#[derive(Default)]
+struct GivenArgs {
+    name: Option<LitStr>,
+    short_name: bool,
+    name_const: Option<ExprPath>,
+    name_from: Option<Ident>,
+    krate: Option<Path>,
+    enter_on_poll: bool,
+    lazy: bool,
+    err: bool,
+    ok_code: Option<Expr>,
+    err_code: Option<Expr>,
+    min_duration: Option<std::time::Duration>,
+    sample: Option<f64>,
+    parent_id: Option<Expr>,
+    parent: Option<LitStr>,
+    timestamp: bool,
+    buffer: Option<usize>,
+    test: Option<bool>,
+    root: bool,
+    guard: Option<Ident>,
+    recurse: bool,
+    recurse_only: Option<Ident>,
+    child_count: bool,
+    coalesce: bool,
+    scope: Option<Scope>,
+    async_trait: Option<bool>,
+    catch_panic: bool,
+    record_return: bool,
+    passthrough: bool,
+    display: Vec<Ident>,
+    variables: Vec<Ident>,
+    skip: Vec<Ident>,
+    redact: Vec<(Ident, Option<Expr>)>,
+    type_params: bool,
+}
+
+/// Stores the value of an argument if it is of the expected kind.
+fn set<T>(field: &mut T, value: Option<T>) -> bool {
+    match value {
+        Some(value) => {
+            *field = value;
+            true
+        }
+        None => false,
+    }
+}
+
+fn lit_bool(value: &Expr) -> Option<bool> {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Bool(b), ..
+        }) => Some(b.value),
+        _ => None,
+    }
+}
+
+fn lit_str(value: &Expr) -> Option<&LitStr> {
+    match value {
+        Expr::Lit(ExprLit {
+            lit: Lit::Str(s), ..
+        }) => Some(s),
+        _ => None,
+    }
+}
+
+fn plain_path(value: &Expr) -> Option<&Path> {
+    match value {
+        Expr::Path(ExprPath {
+            path, qself: None, ..
+        }) => Some(path),
+        _ => None,
+    }
+}
+
+fn plain_ident(value: &Expr) -> Option<&Ident> {
+    plain_path(value).and_then(Path::get_ident)
+}
+
+fn array(value: &Expr) -> Option<&punctuated::Punctuated<Expr, Token![,]>> {
+    match value {
+        Expr::Array(ExprArray { elems, .. }) => Some(elems),
+        _ => None,
+    }
+}
+
 impl Args {
-    fn parse(func_name: String, input: AttributeArgs) -> Args {
-        if input.len() > 2 {
-            abort_call_site!("too many arguments");
+    fn parse(sig: &Signature, attrs: &[Attribute], input: AttributeArgs) -> Args {
+        let is_conventional = |arg: &Expr| parse_name_value(arg).0 == "conventional";
+        if let Some(conventional) = input.iter().find(|arg| is_conventional(arg)) {
+            match lit_bool(parse_name_value(conventional).1) {
+                Some(true) if input.len() > 1 => abort_call_site!(
+                    "`conventional = true` can not be used together with other arguments"
+                ),
+                Some(true) => return Args::conventional(sig),
+                // A no-op, so the other arguments are parsed as usual.
+                Some(false) => {
+                    let input = input.into_iter().filter(|arg| !is_conventional(arg));
+                    return Args::parse(sig, attrs, input.collect());
+                }
+                None => abort_call_site!("invalid argument"),
+            }
         }
 
-        let mut args = HashSet::new();
-        let mut func_name = func_name;
-        let mut short_name = false;
-        let mut enter_on_poll = false;
+        let mut given = GivenArgs::default();
+        let mut parsed = HashSet::new();
+        let mut deprecated = Vec::new();
+        let mut keys = Vec::new();
 
         for arg in &input {
-            match arg {
-                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                    path,
-                    lit: Lit::Str(s),
-                    ..
-                })) if path.is_ident("name") => {
-                    func_name = s.value();
-                    args.insert("name");
+            let (key, value) = parse_name_value(arg);
+            let key = match DEPRECATED_ARGUMENTS.iter().find(|(old, _)| key == old) {
+                Some((_, new)) => {
+                    deprecated.push((key.clone(), *new));
+                    Ident::new(new, key.span())
                 }
-                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                    path,
-                    lit: Lit::Bool(b),
-                    ..
-                })) if path.is_ident("short_name") => {
-                    short_name = b.value;
-                    args.insert("short_name");
+                None => key.clone(),
+            };
+            let name = ALIASED_ARGUMENTS
+                .iter()
+                .find(|(alias, _)| key == alias)
+                .map_or_else(|| key.to_string(), |(_, name)| name.to_string());
+            keys.push(key.clone());
+            match ARGUMENTS.iter().find(|(supported, _)| name == *supported) {
+                Some((name, parse)) => {
+                    if !parse(&mut given, sig, value) {
+                        abort_call_site!("invalid argument");
+                    }
+                    parsed.insert(*name);
                 }
-                NestedMeta::Meta(Meta::NameValue(MetaNameValue {
-                    path,
-                    lit: Lit::Bool(b),
-                    ..
-                })) if path.is_ident("enter_on_poll") => {
-                    enter_on_poll = b.value;
-                    args.insert("enter_on_poll");
+                None => {
+                    let supported = ARGUMENTS
+                        .iter()
+                        .map(|(name, _)| *name)
+                        .chain(ALIASED_ARGUMENTS.iter().map(|(alias, _)| *alias));
+                    abort!(
+                        key,
+                        "unknown option `{}`; supported options are: {}",
+                        key,
+                        supported.collect::<Vec<_>>().join(", ")
+                    )
                 }
-                _ => abort_call_site!("invalid argument"),
             }
         }
 
-        let name = if args.contains("name") {
+        let GivenArgs {
+            name: name_lit,
+            short_name,
+            name_const,
+            name_from,
+            krate,
+            enter_on_poll,
+            lazy,
+            err,
+            ok_code,
+            err_code,
+            min_duration,
+            sample,
+            parent_id,
+            parent,
+            timestamp,
+            buffer,
+            test,
+            root,
+            guard,
+            recurse,
+            recurse_only,
+            child_count,
+            coalesce,
+            scope,
+            async_trait,
+            catch_panic,
+            record_return,
+            passthrough,
+            display,
+            variables,
+            skip,
+            redact,
+            type_params,
+        } = given;
+        let krate = krate.unwrap_or_else(|| parse_quote!(minitrace));
+
+        let name = if let Some(ident) = name_from {
+            if name_lit.is_some() || name_const.is_some() || short_name {
+                abort_call_site!(
+                    "`name_from` can not be used together with `name`, `name_const` or `short_name`"
+                );
+            }
+            Name::From(ident)
+        } else if let Some(path) = name_const {
+            if name_lit.is_some() || short_name {
+                abort_call_site!(
+                    "`name_const` can not be used together with `name` or `short_name`"
+                );
+            }
+            Name::Const(path)
+        } else if let Some(name_lit) = name_lit {
             if short_name {
                 abort_call_site!("`name` and `short_name` can not be used together");
             }
-            Name::Plain(func_name)
+            if has_placeholders(sig, &name_lit) {
+                Name::Format(name_lit)
+            } else {
+                Name::Plain(name_lit.value().replace("{{", "{").replace("}}", "}"))
+            }
         } else if short_name {
-            Name::Plain(func_name)
+            Name::Plain(sig.ident.to_string())
         } else {
             Name::FullName
         };
 
-        if args.len() != input.len() {
+        if parsed.len() != input.len() {
             abort_call_site!("duplicated arguments");
         }
 
+        if enter_on_poll && lazy {
+            abort_call_site!("`enter_on_poll` and `lazy` can not be used together");
+        }
+
+        if timestamp && !cfg!(feature = "timestamp") {
+            abort_call_site!("`timestamp` requires the `timestamp` feature of `minitrace`");
+        }
+
+        if parent_id.is_some() && (enter_on_poll || lazy || min_duration.is_some()) {
+            abort_call_site!(
+                "`parent_id` can not be used together with `enter_on_poll`, `lazy` or `min_duration`"
+            );
+        }
+
+        // A test function has no caller to provide a local parent, so it gets a root span.
+        let test = test.unwrap_or_else(|| attrs.iter().any(is_test_attr));
+        if test && (parent_id.is_some() || enter_on_poll || lazy || min_duration.is_some()) {
+            abort_call_site!(
+                "`test`, which is implied by `#[test]`, can not be used together with `parent_id`, `enter_on_poll`, `lazy` or `min_duration`; use `test = false` to opt out"
+            );
+        }
+
+        if root && (parent_id.is_some() || enter_on_poll || lazy || min_duration.is_some()) {
+            abort_call_site!(
+                "`root` can not be used together with `parent_id`, `enter_on_poll`, `lazy` or `min_duration`"
+            );
+        }
+
+        if child_count && (parent_id.is_some() || test || root) {
+            abort_call_site!(
+                "`child_count` can not be used together with `parent_id`, `test` or `root`"
+            );
+        }
+
+        if coalesce && (parent_id.is_some() || test || root || min_duration.is_some()) {
+            abort_call_site!(
+                "`coalesce` can not be used together with `parent_id`, `test`, `root` or `min_duration`"
+            );
+        }
+
+        if scope == Some(Scope::Threads) && (child_count || coalesce || min_duration.is_some()) {
+            abort_call_site!(
+                "`scope = Scope::Threads` can not be used together with `child_count`, `coalesce` or `min_duration`"
+            );
+        }
+
+        if parent.is_some()
+            && (parent_id.is_some()
+                || test
+                || root
+                || enter_on_poll
+                || lazy
+                || min_duration.is_some()
+                || child_count
+                || coalesce
+                || scope == Some(Scope::Local))
+        {
+            abort!(
+                parent,
+                "`parent` can not be used together with `parent_id`, `test`, `root`, `enter_on_poll`, `lazy`, `min_duration`, `child_count`, `coalesce` or `scope = Scope::Local`"
+            );
+        }
+
+        if let Some(ident) = skip
+            .iter()
+            .find(|ident| display.contains(ident) || variables.contains(ident))
+        {
+            abort!(
+                ident,
+                "`{}` can not be both skipped and listed in `display` or `variables`",
+                ident
+            );
+        }
+
+        if let Some((ident, _)) = redact
+            .iter()
+            .find(|(ident, _)| !display.contains(ident) && !variables.contains(ident))
+        {
+            abort!(
+                ident,
+                "`{}` is redacted but not recorded; list it in `display` or `variables`",
+                ident
+            );
+        }
+
+        // Recording `()` is pointless, so `record_return` is ignored rather than rejected for a
+        // function returning `()`, e.g. when given in the default arguments.
+        let record_return = record_return && !returns_unit(sig);
+
+        if passthrough
+            && (enter_on_poll
+                || lazy
+                || err
+                || ok_code.is_some()
+                || err_code.is_some()
+                || record_return)
+        {
+            abort_call_site!(
+                "`passthrough` can not be used together with `enter_on_poll`, `lazy`, `err`, `ok_code`, `err_code` or `record_return`"
+            );
+        }
+
+        if recurse_only.is_some() && !recurse {
+            abort_call_site!("`recurse_only` can only be used together with `recurse = true`");
+        }
+
+        if buffer.is_some() && (enter_on_poll || lazy) {
+            abort_call_site!("`buffer` can not be used together with `enter_on_poll` or `lazy`");
+        }
+
         Args {
             name,
             enter_on_poll,
+            lazy,
+            err,
+            ok_code,
+            err_code,
+            min_duration,
+            sample,
+            parent_id,
+            parent,
+            timestamp,
+            buffer,
+            root: test || root,
+            guard,
+            recurse,
+            recurse_only,
+            child_count,
+            coalesce,
+            scope,
+            async_trait,
+            catch_panic,
+            record_return,
+            passthrough,
+            display,
+            variables,
+            skip,
+            redact,
+            type_params: if type_params {
+                sig.generics
+                    .type_params()
+                    .map(|param| param.ident.clone())
+                    .collect()
+            } else {
+                Vec::new()
+            },
+            krate,
+            deprecated,
+            keys,
+        }
+    }
+
+    /// The arguments of `conventional = true`, which are fixed rather than parsed: the span is
+    /// named after the function, an `async fn` is entered on poll, and the guard of the span of a
+    /// non-async function is bound to `span`.
+    fn conventional(sig: &Signature) -> Args {
+        let is_async = sig.asyncness.is_some();
+        Args {
+            name: Name::Plain(sig.ident.to_string()),
+            enter_on_poll: is_async,
+            lazy: false,
+            err: false,
+            ok_code: None,
+            err_code: None,
+            min_duration: None,
+            sample: None,
+            parent_id: None,
+            parent: None,
+            timestamp: false,
+            buffer: None,
+            root: false,
+            guard: (!is_async).then(|| Ident::new("span", proc_macro2::Span::call_site())),
+            recurse: false,
+            recurse_only: None,
+            child_count: false,
+            coalesce: false,
+            scope: None,
+            async_trait: None,
+            catch_panic: false,
+            record_return: false,
+            passthrough: false,
+            display: Vec::new(),
+            variables: Vec::new(),
+            skip: Vec::new(),
+            redact: Vec::new(),
+            type_params: Vec::new(),
+            krate: parse_quote!(minitrace),
+            deprecated: Vec::new(),
+            keys: Vec::new(),
+        }
+    }
+
+    /// The span of the key of the argument named `key`, or the call site if it is not given.
+    fn key_span(&self, key: &str) -> proc_macro2::Span {
+        self.keys
+            .iter()
+            .find(|ident| *ident == key)
+            .map_or_else(proc_macro2::Span::call_site, Ident::span)
+    }
+}
+
+/// Parses a list of parameter names, e.g. `[a, b]`, aborting on an element which is not the name
+/// of a parameter of the function.
+fn parse_param_names(
+    sig: &Signature,
+    elems: &punctuated::Punctuated<Expr, Token![,]>,
+) -> Vec<Ident> {
+    elems
+        .iter()
+        .map(|elem| parse_param_name(sig, elem))
+        .collect()
+}
+
+fn parse_param_name(sig: &Signature, elem: &Expr) -> Ident {
+    let ident = match elem {
+        Expr::Path(ExprPath {
+            path, qself: None, ..
+        }) if path.get_ident().is_some() => path.get_ident().unwrap(),
+        _ => abort!(elem, "expected a parameter name"),
+    };
+    if !param_names(sig).any(|param| param == *ident) {
+        abort!(ident, "`{}` is not a parameter of the function", ident);
+    }
+    ident.clone()
+}
+
+/// Parses the parameters listed in `redact`, e.g. `[password, email = mask_email]`, each with the
+/// function redacting its formatted value if given.
+fn parse_redacted_params(
+    sig: &Signature,
+    elems: &punctuated::Punctuated<Expr, Token![,]>,
+) -> Vec<(Ident, Option<Expr>)> {
+    elems
+        .iter()
+        .map(|elem| match elem {
+            Expr::Assign(ExprAssign { left, right, .. }) => {
+                (parse_param_name(sig, left), Some((**right).clone()))
+            }
+            elem => (parse_param_name(sig, elem), None),
+        })
+        .collect()
+}
+
+/// Returns whether a span name contains placeholders, e.g. `{user_id}` or `{user_id:?}`, aborting
+/// on a placeholder which does not name a parameter of the function, or on an unmatched brace, as
+/// `format!` does. `{{` and `}}` are escaped braces.
+fn has_placeholders(sig: &Signature, name: &LitStr) -> bool {
+    let value = name.value();
+    let mut chars = value.chars().peekable();
+    let mut found = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '{' if chars.peek() == Some(&'{') => {
+                chars.next();
+            }
+            '{' => {
+                let mut placeholder = String::new();
+                loop {
+                    match chars.next() {
+                        Some('}') => break,
+                        Some(c) => placeholder.push(c),
+                        None => abort!(
+                            name,
+                            "invalid span name: unmatched `{`; use `{{` to escape it"
+                        ),
+                    }
+                }
+                let param = placeholder.split(':').next().unwrap_or_default().trim();
+                if !param_names(sig).any(|name| name == param) || param == "self" {
+                    abort!(
+                        name,
+                        "`{{{}}}` does not name a parameter of the function",
+                        placeholder
+                    );
+                }
+                found = true;
+            }
+            '}' if chars.peek() == Some(&'}') => {
+                chars.next();
+            }
+            '}' => abort!(
+                name,
+                "invalid span name: unmatched `}`; use `}}` to escape it"
+            ),
+            _ => {}
         }
     }
+    found
+}
+
+/// Splits an argument of the form `key = value`.
+fn parse_name_value(arg: &Expr) -> (&Ident, &Expr) {
+    if let Expr::Assign(ExprAssign {
+        attrs, left, right, ..
+    }) = arg
+    {
+        if let Expr::Path(ExprPath {
+            path, qself: None, ..
+        }) = &**left
+        {
+            if let (true, Some(key)) = (attrs.is_empty(), path.get_ident()) {
+                return (key, right);
+            }
+        }
+    }
+
+    // The list form, e.g. `name("foo")`, is emitted by some tools but is not supported.
+    if let Expr::Call(ExprCall { func, .. }) = arg {
+        if let Expr::Path(ExprPath {
+            path, qself: None, ..
+        }) = &**func
+        {
+            if let Some(key) = path.get_ident() {
+                abort!(
+                    arg,
+                    "invalid argument, use the `key = value` form, e.g. `{} = ...`",
+                    key
+                );
+            }
+        }
+    }
+
+    abort_call_site!("invalid argument")
+}
+
+/// The names of the parameters of the function, including `self`.
+fn param_names(sig: &Signature) -> impl Iterator<Item = Ident> + '_ {
+    sig.inputs.iter().filter_map(|input| match input {
+        FnArg::Receiver(receiver) => Some(Ident::new("self", receiver.self_token.span)),
+        FnArg::Typed(PatType { pat, .. }) => match &**pat {
+            Pat::Ident(PatIdent { ident, .. }) => Some(ident.clone()),
+            _ => None,
+        },
+    })
 }
 
 /// An attribute macro designed to eliminate boilerplate code.
@@ -102,10 +837,220 @@ impl Args {
 ///
 /// ## Arguments
 ///
-/// * `name` - The name of the span. Defaults to the full path of the function.
+/// * `name` - The name of the span. Defaults to the full path of the function. The name can
+///    interpolate parameters of the function, e.g. `name = "handle_user({user_id})"` or
+///    `name = "get({key:?})"`, in which case it is formatted with `format!` when the span is
+///    recorded. `{{` and `}}` are escaped braces.
+/// * `crate` - The path to the `minitrace` crate, e.g. `crate = my_minitrace`, for the case that
+///    the dependency is renamed. Defaults to `minitrace`.
+/// * `name_const` - A path to a `&'static str` constant to use as the name of the span, e.g.
+///    `Self::SPAN_NAME` for an associated constant of a trait. Can not be used together with
+///    `name` or `short_name`.
+/// * `name_from` - A parameter, e.g. `name_from = state`, whose value formatted with `Display` is
+///    the name of the span, e.g. the variant of an enum for a state machine. Use `name` with a
+///    placeholder, e.g. `name = "{state:?}"`, to format it with `Debug` instead. Can not be used
+///    together with `name`, `name_const` or `short_name`.
 /// * `short_name` - Whether to use the function name without path as the span name. Defaults to `false`.
 /// * `enter_on_poll` - Whether to enter the span on poll. If set to `false`, `in_span` will be used.
-///    Only available for `async fn`. Defaults to `false`.
+///    Only available for `async fn`, and for a function returning `impl Future`, whose returned
+///    future is entered on poll rather than its body on call. Defaults to `false`.
+/// * `lazy` - Whether to defer the creation of the span until the future is polled for the first
+///    time. A future that is dropped without being polled records no span. Only available for
+///    `async fn` and for a function returning `impl Future`, and can not be used together with
+///    `enter_on_poll`. Defaults to `false`.
+/// * `err` - Whether to record the error as the `error` property of the span and set the status
+///    of the span to `SpanStatus::Error` if the function returns `Err`, including errors
+///    propagated by `?`. The function must return a `Result` whose error type implements
+///    `Display`. Defaults to `false`. The spelling `catch_err` is deprecated.
+/// * `ok_code` - An expression, e.g. `ok_code = 0`, evaluated if the function returns `Ok` and
+///    recorded as the `status_code` property of the span using its `Display` implementation. The
+///    function must return a `Result`. Defaults to none.
+/// * `err_code` - The same as `ok_code`, e.g. `err_code = 2`, but evaluated if the function
+///    returns `Err`, including errors propagated by `?`. Defaults to none.
+/// * `record_return` - Whether to record the return value of the function as the `return` property
+///    of the span using its `Debug` implementation, including values returned early by `return` or
+///    `?`. For an `async fn`, the output of the future is recorded once it completes. The return
+///    type must implement `Debug`. Ignored for a function returning `()`. Defaults to `false`.
+/// * `passthrough` - Whether to instrument the awaited future directly, rather than wrapping the
+///    body in another async block, for an `async fn` whose body is a single `.await` expression,
+///    e.g. `{ inner(a).await }`, saving a layer of polling. The expression creating the future is
+///    evaluated before the span is entered, and the `tokio.task_id` property is not recorded. Only
+///    available for async functions, and can not be used together with `enter_on_poll`, `lazy`,
+///    `err`, `ok_code`, `err_code` or `record_return`. Defaults to `false`.
+/// * `display` - A list of parameters, e.g. `[a, b]`, to record as properties of the span using
+///    their `Display` implementation. The property key is the name of the parameter.
+/// * `variables` - A list of parameters, e.g. `[a, b]`, to record as properties of the span using
+///    their `Debug` implementation. The property key is the name of the parameter.
+/// * `skip` - A list of parameters, e.g. `[password, raw_bytes]`, never to record as properties of
+///    the span, e.g. because they are sensitive or large. A parameter can not be both skipped and
+///    listed in `display` or `variables`.
+/// * `redact` - A list of parameters listed in `display` or `variables`, e.g.
+///    `[password, email = mask_email]`, whose recorded value is masked, e.g. because it holds
+///    personal data. A parameter is recorded as `***`, or, if a function or closure is given, as
+///    the result of calling it with the formatted value as a `&str`, which returns a `String`.
+/// * `type_params` - Whether to record the concrete type of each generic type parameter, as given
+///    by `std::any::type_name`, as properties of the span. The property key is the name of the
+///    type parameter. Defaults to `false`.
+/// * `min_duration` - A duration such as `"100us"` or `"1ms"`. If the span lasts shorter than
+///    it, the span is discarded along with its child spans. Supported units are `ns`, `us`, `ms`
+///    and `s`. Only available for non-async functions. Defaults to none.
+/// * `sample` - A ratio between `0.0` and `1.0`, e.g. `0.1`, of the calls to record. Whether a call
+///    is recorded is decided when the function is called. Defaults to recording every call.
+/// * `parent_id` - A `u64` expression, e.g. `parent_id = header.span_id`, to use as the parent id
+///    of the span, for correlation with an id assigned by an external system. The span starts a new
+///    trace sharing the trace id of the local parent, if any, and is reported on its own. Can not
+///    be used together with `enter_on_poll`, `lazy` or `min_duration`. Defaults to none.
+/// * `parent` - The name of an enclosing span, e.g. `parent = "request"`, to record the span as a
+///    child of, rather than of the local parent, e.g. to attach it to a known ancestor when the
+///    caller is not its natural parent. The nearest unfinished [`LocalSpan`] of that name on the
+///    current thread is used, or else the [`Span`] set as the local parent if it has that name
+///    as a static string, e.g. an enclosing `#[trace]` function without a formatted `name`. If
+///    there is none, the span is not recorded. The span is a [`Span`], set as the local parent
+///    of the function body. Can not be used together with `parent_id`, `test`, `root`,
+///    `enter_on_poll`, `lazy`, `min_duration`, `child_count`, `coalesce` or
+///    `scope = Scope::Local`. Defaults to none.
+/// * `timestamp` - Whether to record the wall-clock time at which the span starts as the
+///    `started_at` property, formatted as RFC 3339, e.g. `2024-01-01T00:00:00Z`. Requires the
+///    `timestamp` feature of `minitrace`. Defaults to `false`.
+/// * `buffer` - The number of properties, e.g. `buffer = 8`, to reserve capacity for when the span
+///    is created, to avoid reallocations when many properties are added to the span. Can not be
+///    used together with `enter_on_poll` or `lazy`. Defaults to none.
+/// * `test` - Whether to record the span as the root of a new trace, which is set as the local
+///    parent of the function body, so that the spans of a test function are collected. Implied by
+///    `#[test]` or an attribute ending with `test`, e.g. `#[tokio::test]`, placed below `#[trace]`.
+///    Can not be used together with `parent_id`, `enter_on_poll`, `lazy` or `min_duration`.
+///    Defaults to `false`.
+/// * `root` - Whether to record the span as the root of a new trace rather than as a child of the
+///    local parent, e.g. for the handler of a request. The span is set as the local parent of the
+///    function body and is submitted to the reporter set by `minitrace::set_reporter` once the
+///    function returns, or once the future completes or is dropped for an `async fn`. Can not be
+///    used together with `parent_id`, `enter_on_poll`, `lazy` or `min_duration`. Defaults to
+///    `false`.
+/// * `guard` - The name of the variable, e.g. `guard = span`, holding the guard of the span in the
+///    function body, so that the body can refer to it, e.g. to end the span early by dropping it.
+///    The guard is a [`LocalSpan`], a [`Span`] with `scope = Scope::Threads` or `parent`, or a
+///    [`LocalParentGuard`] of the root span if `parent_id`, `test` or `root` is set, and `()` with
+///    the `disable` feature. Only available for non-async
+///    functions. Defaults to a hidden name.
+/// * `recorder` - The same as `guard`, e.g. `recorder = span`. Only one of them can be given.
+/// * `recurse` - Whether to also trace the functions defined in the function body, at any depth,
+///    each in a span named after the nested function. Functions which are `const` or already
+///    annotated with `#[trace]` are left untouched. Defaults to `false`.
+/// * `recurse_only` - The name of an attribute, e.g. `recurse_only = traced`, marking the nested
+///    functions to trace with `recurse = true`, so that the others are left untouched. The marker,
+///    e.g. `#[traced]`, takes no arguments and is removed from the nested functions, so it needs
+///    no definition. Can only be used together with `recurse = true`. Defaults to none, tracing
+///    all the nested functions.
+/// * `child_count` - Whether to record the number of the child spans of the span, e.g. the calls
+///    to other functions annotated with `#[trace]`, as the `child_count` property. Only the child
+///    spans recorded on the current thread are counted. Only available for non-async functions,
+///    and can not be used together with `parent_id`, `test` or `root`. Defaults to `false`.
+/// * `coalesce` - Whether to merge the spans of consecutive calls into a single span, e.g. when
+///    the function is called in a tight loop, recording the number of calls as the `count`
+///    property. The spans are merged only if they have the same parent and no child spans, and
+///    nothing else is recorded between them. Only available for non-async functions, and can not
+///    be used together with `parent_id`, `test`, `root` or `min_duration`. Defaults to `false`.
+/// * `scope` - Where the span of a non-async function can be used, either `Scope::Local` or
+///    `Scope::Threads` of [`minitrace::Scope`], by any path to the variant. With `Scope::Local`, the span is a [`LocalSpan`], only usable on the
+///    current thread. With `Scope::Threads`, the span is a [`Span`], set as the local parent of the
+///    function body, which can be passed to other threads through `guard`, e.g. to record the work
+///    of spawned threads as its children. The span of an `async fn` is always a [`Span`], so only
+///    `Scope::Threads` is accepted for it. `Scope::Threads` can not be used together with
+///    `child_count`, `coalesce` or `min_duration`. Defaults to `Scope::Local`.
+/// * `async_trait` - Whether the function returns a boxed future, as the methods generated by
+///    `async-trait` do. If `true`, the async block of the last `Box::pin(async move { ... })` in
+///    the function body is instrumented, rather than the function itself, and other statements are
+///    kept. If `false`, the function itself is instrumented. Only available for non-async functions.
+///    Defaults to detecting the pattern generated by `async-trait`, and a function returning
+///    `impl Future` whose body ends with an `async move` block, which is instrumented instead.
+/// * `catch_panic` - Whether to record a panic in the function as the `panic.message` property of
+///    the span, e.g. `"index out of bounds"`, and set the status of the span to
+///    `SpanStatus::Error`. The panic is resumed once recorded. The location of the panic, e.g.
+///    `src/main.rs:10:5`, is also recorded as the `panic.location` property if the process-wide
+///    panic hook of `minitrace::install_panic_hook()` is installed; `#[trace]` never installs it.
+///    Only available for non-async functions. Defaults to `false`.
+/// * `conventional` - Whether to apply fixed arguments rather than parsing them, to save expansion
+///    time when many functions are annotated: the span is named after the function as with
+///    `short_name = true`, an `async fn` is entered on poll as with `enter_on_poll = true`, and the
+///    guard of the span of a non-async function is bound to `span` as with `guard = span`. If
+///    `true`, can not be used together with other arguments. Defaults to `false`.
+///
+/// [`LocalSpan`]: https://docs.rs/minitrace/latest/minitrace/local/struct.LocalSpan.html
+/// [`Span`]: https://docs.rs/minitrace/latest/minitrace/struct.Span.html
+/// [`minitrace::Scope`]: https://docs.rs/minitrace/latest/minitrace/enum.Scope.html
+/// [`LocalParentGuard`]: https://docs.rs/minitrace/latest/minitrace/local/struct.LocalParentGuard.html
+///
+/// ## Thread Safety
+///
+/// No `Send` bound is added to an `async fn`: the instrumented future is `Send` exactly when the
+/// original one is. A function holding `!Send` data such as `Rc` across an `.await` still
+/// compiles, and fails only where its future is spawned on a multi-threaded executor, e.g. by
+/// `tokio::spawn`. Run such functions with `tokio::task::spawn_local` or a current-thread executor.
+///
+/// Likewise, an `async fn` stays an `async fn`, so a future dropped without being awaited is still
+/// reported by the `unused_must_use` lint, and so is an unused output of an `async fn` annotated
+/// with `#[must_use]`.
+///
+/// ## Detached Tasks
+///
+/// The span ends when the function returns, so a task spawned by the function that outlives it
+/// is not covered by the span. To trace such a task, start a new root span for it from
+/// [`SpanContext::current_local_parent()`], which is collected and reported independently
+/// while still being linked to the span of the function as its parent:
+///
+/// ```no_run
+/// use minitrace::prelude::*;
+///
+/// #[trace]
+/// async fn foo() {
+///     let span = Span::root(
+///         "detached",
+///         SpanContext::current_local_parent().unwrap_or_default(),
+///     );
+///     tokio::spawn(async { /* ... */ }.in_span(span));
+/// }
+/// ```
+///
+/// [`SpanContext::current_local_parent()`]: https://docs.rs/minitrace/latest/minitrace/collector/struct.SpanContext.html#method.current_local_parent
+///
+/// ## Qualifiers
+///
+/// The qualifiers of the function, e.g. `unsafe` and `extern "C"`, are kept for both non-async
+/// functions and `async fn`. A `const fn` is left untouched, since a span can not be recorded in
+/// a const context, though the arguments of `#[trace]` are still checked.
+///
+/// ## Defaults
+///
+/// Default arguments for every `#[trace]` of a crate can be set at compile time in the
+/// `MINITRACE_TRACE_DEFAULTS_<CRATE>` environment variable, where `<CRATE>` is the upper-cased
+/// name of the crate as seen by the compiler, i.e. with `-` replaced by `_`. For example, in the
+/// `[env]` table of `.cargo/config.toml` for the crate `my-app`:
+///
+/// ```toml
+/// [env]
+/// MINITRACE_TRACE_DEFAULTS_MY_APP = "short_name = true, sample = 0.1"
+/// ```
+///
+/// The variable only applies to the named crate, so the `#[trace]` of dependencies built in the
+/// same environment is unaffected. The expanded functions refer to the variable by `option_env!`,
+/// so that changing it rebuilds the crate.
+///
+/// The arguments given to `#[trace]` override the default arguments of the same key, and a
+/// default name, e.g. `short_name = true`, is overridden by any other way of naming the span, e.g.
+/// `name = "..."`. Likewise for `guard` and `recorder`, and `conventional = true` overrides all the
+/// default arguments. The default arguments apply to every function annotated with `#[trace]`, so
+/// they must be valid for all of them, e.g. `enter_on_poll` is only valid if no non-async function
+/// is traced. The defaults can not be given as an attribute at the crate root, e.g.
+/// `#![trace(...)]`, since custom inner attributes are unstable.
+///
+/// ## Features
+///
+/// * `disable` - Expands `#[trace]` to the untouched function. Unlike building `minitrace`
+///    without the `enable` feature, no instrumentation code is emitted at all. The arguments are
+///    still checked, so that a typo is caught in a build with the feature as well.
+/// * `tokio` - Records the id of the tokio task polling the function as the `tokio.task_id`
+///    property of the span, when called inside a tokio task. Enable it through the `tokio` feature
+///    of `minitrace`.
 ///
 /// # Examples
 ///
@@ -160,72 +1105,585 @@ pub fn trace(
     args: proc_macro::TokenStream,
     item: proc_macro::TokenStream,
 ) -> proc_macro::TokenStream {
-    let input = syn::parse_macro_input!(item as ItemFn);
-    let args = Args::parse(
-        input.sig.ident.to_string(),
-        syn::parse_macro_input!(args as AttributeArgs),
-    );
+    let env = std::env::var("CARGO_CRATE_NAME")
+        .ok()
+        .map(|krate| format!("{}{}", DEFAULTS_ENV_PREFIX, krate.to_uppercase()));
+    let defaults = env.as_deref().and_then(|env| std::env::var(env).ok());
+    let args = with_defaults(args.into(), env.as_deref().zip(defaults.as_deref()));
+
+    let output = if cfg!(feature = "disable") {
+        gen_disabled(args, item.into())
+    } else {
+        expand_trace(args, item.into())
+    };
+
+    match &env {
+        Some(env) => track_env(output, env).into(),
+        None => output.into(),
+    }
+}
+
+/// Refers to the environment variable `env` by `option_env!` in the body of the expanded function,
+/// so that the compiler records it as read by the crate, and a change of it rebuilds the crate.
+fn track_env(output: proc_macro2::TokenStream, env: &str) -> proc_macro2::TokenStream {
+    match syn::parse2::<ItemFn>(output.clone()) {
+        Ok(mut item) => {
+            item.block.stmts.insert(
+                0,
+                parse_quote!(
+                    const _: ::core::option::Option<&str> = ::core::option_env!(#env);
+                ),
+            );
+            quote!(#item)
+        }
+        // A crate failing to compile is rebuilt anyway.
+        Err(_) => output,
+    }
+}
+
+/// Prepends the default arguments, given with the name of the environment variable holding them,
+/// to the arguments of `#[trace]`, except those overridden by an argument of the same key or of
+/// the same group in [`EXCLUSIVE_ARGUMENTS`]. `conventional = true` overrides all of them.
+fn with_defaults(
+    args: proc_macro2::TokenStream,
+    defaults: Option<(&str, &str)>,
+) -> proc_macro2::TokenStream {
+    let (env, defaults) = match defaults.filter(|(_, defaults)| !defaults.trim().is_empty()) {
+        Some(defaults) => defaults,
+        None => return args,
+    };
+    let defaults = match defaults
+        .parse()
+        .ok()
+        .and_then(|defaults| AttributeArgs::parse_terminated.parse2(defaults).ok())
+    {
+        Some(defaults) => defaults,
+        None => abort_call_site!("invalid `{}`: `{}`", env, defaults),
+    };
+    // Invalid arguments are reported by the expansion.
+    let given = match AttributeArgs::parse_terminated.parse2(args.clone()) {
+        Ok(given) => given,
+        Err(_) => return args,
+    };
+
+    // The key of an argument, with a deprecated key renamed.
+    let key = |arg: &Expr| match arg {
+        Expr::Assign(ExprAssign { left, .. }) => match &**left {
+            Expr::Path(ExprPath { path, .. }) => path.get_ident().map(|key| {
+                let key = key.to_string();
+                DEPRECATED_ARGUMENTS
+                    .iter()
+                    .find(|(old, _)| key == *old)
+                    .map_or(key, |(_, new)| new.to_string())
+            }),
+            _ => None,
+        },
+        _ => None,
+    };
+    let given_keys = given.iter().filter_map(key).collect::<Vec<_>>();
+    let conventional = given.iter().any(|arg| match arg {
+        Expr::Assign(ExprAssign { left, right, .. }) => {
+            matches!(&**left, Expr::Path(ExprPath { path, .. }) if path.is_ident("conventional"))
+                && lit_bool(right) == Some(true)
+        }
+        _ => false,
+    });
+    let overridden = |key: &str| {
+        conventional
+            || given_keys.iter().any(|given| {
+                given == key
+                    || EXCLUSIVE_ARGUMENTS
+                        .iter()
+                        .any(|group| group.contains(&key) && group.contains(&given.as_str()))
+            })
+    };
+    let defaults = defaults.iter().filter(|arg| match key(arg) {
+        Some(key) => !overridden(&key),
+        None => true,
+    });
+    let given = given.iter();
+
+    quote!(#(#defaults,)* #(#given),*)
+}
+
+/// Expands `#[trace]` on a function. The output only depends on the tokens of the input, and the
+/// generated tokens take the span of the input they derive from, or the call site otherwise, so
+/// that identical input expands to identical output.
+fn expand_trace(
+    args: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mut input = match syn::parse2::<ItemFn>(item.clone()) {
+        Ok(input) => input,
+        Err(err) => return token_stream_with_error(item, err),
+    };
+    let args = match AttributeArgs::parse_terminated.parse2(args) {
+        Ok(args) => Args::parse(&input.sig, &input.attrs, args),
+        Err(_) => abort_call_site!("invalid argument"),
+    };
+    let deprecated = gen_deprecated(&args.deprecated);
+
+    if args.recurse {
+        visit_mut::VisitMut::visit_block_mut(
+            &mut TraceNestedFns {
+                krate: &args.krate,
+                marker: args.recurse_only.as_ref(),
+                annotate: input.sig.constness.is_none(),
+            },
+            &mut input.block,
+        );
+    }
+
+    // A span can not be recorded in a const context, so a `const fn` is left as is.
+    if input.sig.constness.is_some() {
+        if !deprecated.is_empty() {
+            let block = &input.block;
+            input.block = parse_quote!({ #deprecated #block });
+        }
+        return quote!(#input);
+    }
+
+    // A non-async function returning `impl Future` whose body builds the future otherwise than
+    // with a trailing async block, e.g. by calling another function, is entered on poll by
+    // awaiting the future in an async block, which is instrumented below. The body is evaluated
+    // in a closure, so that a `return` in it still returns the future.
+    if (args.enter_on_poll || args.lazy)
+        && args.async_trait != Some(false)
+        && returns_impl_future(&input.sig)
+        && get_returned_async_block(&input).is_none()
+    {
+        let block = &input.block;
+        input.block = parse_quote!({
+            #[allow(clippy::redundant_closure_call)]
+            let __future = (|| #block)();
+            async move { __future.await }
+        });
+    }
 
     // check for async_trait-like patterns in the block, and instrument
     // the future instead of the wrapper
-    let func_body = if let Some(internal_fun) =
+    let func_body = if args.async_trait == Some(true) {
+        if input.sig.asyncness.is_some() {
+            abort_call_site!("`async_trait = true` can not be applied on async function");
+        }
+
+        let mut instrument = InstrumentBoxedAsync {
+            target: 0,
+            visited: 0,
+            args: None,
+        };
+        visit_mut::VisitMut::visit_block_mut(&mut instrument, &mut input.block);
+        if instrument.visited == 0 {
+            abort_call_site!(
+                "`async_trait = true` requires a `Box::pin(async move { ... })` in the function body"
+            );
+        }
+        instrument = InstrumentBoxedAsync {
+            target: instrument.visited,
+            visited: 0,
+            args: Some(args),
+        };
+        visit_mut::VisitMut::visit_block_mut(&mut instrument, &mut input.block);
+
+        let block = &input.block;
+        quote!(#block)
+    } else if let Some(internal_fun) =
         get_async_trait_info(&input.block, input.sig.asyncness.is_some())
+            .filter(|_| args.async_trait != Some(false))
     {
         // let's rewrite some statements!
         match internal_fun.kind {
             // async-trait <= 0.1.43
-            AsyncTraitKind::Function(_) => {
-                unimplemented!(
-                    "Please upgrade the crate `async-trait` to a version higher than 0.1.44"
-                )
+            AsyncTraitKind::Function(fun) => {
+                // Instrument the body of the inner async fn, keeping the other statements, such as
+                // the call to `Box::pin`, untouched.
+                let instrumented_block =
+                    gen_block(&fun.block, true, true, args, &[], match &fun.sig.output {
+                        ReturnType::Type(_, ty) if !contains_impl_trait(quote!(#ty)) => Some(&**ty),
+                        _ => None,
+                    });
+                let ItemFn {
+                    attrs, vis, sig, ..
+                } = fun;
+                let stmts = input.block.stmts.iter().map(|stmt| {
+                    if std::ptr::eq(stmt, internal_fun.source_stmt) {
+                        quote!(#(#attrs)* #vis #sig { #instrumented_block })
+                    } else {
+                        quote!(#stmt)
+                    }
+                });
+                quote!(#(#stmts)*)
             }
             // async-trait >= 0.1.44
             AsyncTraitKind::Async(async_expr) => {
                 // fallback if we couldn't find the '__async_trait' binding, might be
                 // useful for crates exhibiting the same behaviors as async-trait
-                let instrumented_block = gen_block(&async_expr.block, true, false, args);
-                let async_attrs = &async_expr.attrs;
+                // The attributes of the async block stay on the instrumented async block, so that
+                // they keep applying to the user code.
+                let instrumented_block = gen_block(
+                    &async_expr.block,
+                    true,
+                    false,
+                    args,
+                    &async_expr.attrs,
+                    None,
+                );
                 quote! {
-                    Box::pin(#(#async_attrs) * #instrumented_block)
+                    Box::pin(#instrumented_block)
                 }
             }
         }
+    } else if let Some(async_expr) =
+        get_returned_async_block(&input).filter(|_| args.async_trait != Some(false))
+    {
+        // A non-async function returning `impl Future` written as an async block, e.g.
+        // `fn f() -> impl Future<Output = T> + Send { async move { ... } }`. The async block is
+        // instrumented like the body of an `async fn`, and the statements before it are kept.
+        let stmts = &input.block.stmts[..input.block.stmts.len() - 1];
+        let instrumented_block = gen_block(
+            &async_expr.block,
+            true,
+            false,
+            args,
+            &async_expr.attrs,
+            None,
+        );
+        quote!(#(#stmts)* #instrumented_block)
     } else {
         gen_block(
             &input.block,
             input.sig.asyncness.is_some(),
             input.sig.asyncness.is_some(),
             args,
+            &[],
+            match &input.sig.output {
+                ReturnType::Type(_, ty) if !contains_impl_trait(quote!(#ty)) => Some(&**ty),
+                _ => None,
+            },
         )
     };
 
     let ItemFn {
-        attrs, vis, sig, ..
+        attrs, vis, sig, ..
+    } = input;
+
+    let Signature {
+        output: return_type,
+        inputs: params,
+        unsafety,
+        constness,
+        abi,
+        ident,
+        asyncness,
+        generics:
+            Generics {
+                params: gen_params,
+                where_clause,
+                ..
+            },
+        ..
+    } = sig;
+
+    let output = quote::quote!(
+        #(#attrs) *
+        #vis #constness #asyncness #unsafety #abi fn #ident<#gen_params>(#params) #return_type
+        #where_clause
+        {
+            #deprecated
+            #func_body
+        }
+    );
+
+    // Catch a function reassembled wrongly, e.g. with the qualifiers out of order, while developing
+    // the macro, rather than leaving the user with a confusing syntax error.
+    if cfg!(debug_assertions) {
+        if let Err(err) = reparse_fn(&output) {
+            let message = format!(
+                "`#[trace]` emitted a malformed function ({}), which is a bug: {}",
+                err, output
+            );
+            return syn::Error::new(err.span(), message).to_compile_error();
+        }
+    }
+
+    output
+}
+
+/// Parses the tokens emitted for an instrumented function back into a function.
+fn reparse_fn(tokens: &proc_macro2::TokenStream) -> syn::Result<ItemFn> {
+    syn::parse2(tokens.clone())
+}
+
+/// Annotates the functions defined in a function body, at any depth, with `#[trace]` for
+/// `recurse = true`. Functions which are `const` or already annotated with `#[trace]` are left
+/// untouched, and so are those without the marker attribute given by `recurse_only`, if any.
+struct TraceNestedFns<'a> {
+    krate: &'a Path,
+    marker: Option<&'a Ident>,
+    /// Whether to annotate the functions, rather than only removing the marker attribute, e.g.
+    /// with the `disable` feature.
+    annotate: bool,
+}
+
+impl visit_mut::VisitMut for TraceNestedFns<'_> {
+    fn visit_item_fn_mut(&mut self, item: &mut ItemFn) {
+        visit_mut::visit_item_fn_mut(self, item);
+
+        let mut marked = false;
+        if let Some(marker) = self.marker {
+            item.attrs.retain(|attr| {
+                if !attr.path.is_ident(marker) {
+                    return true;
+                }
+                if !attr.tokens.is_empty() {
+                    abort!(attr.tokens, "`#[{}]` takes no arguments", marker);
+                }
+                marked = true;
+                false
+            });
+        }
+        if !self.annotate || (self.marker.is_some() && !marked) {
+            return;
+        }
+
+        let traced = item.attrs.iter().any(
+            |attr| matches!(attr.path.segments.last(), Some(segment) if segment.ident == "trace"),
+        );
+        if !traced && item.sig.constness.is_none() {
+            let krate = self.krate;
+            item.attrs
+                .push(parse_quote!(#[#krate::trace(short_name = true, crate = #krate)]));
+        }
+    }
+}
+
+/// Instruments the async block of the last call to `Box::pin(async move { ... })` in a function
+/// body, outside of nested items, for `async_trait = true`. Visiting the body without `args`
+/// counts the calls, and visiting it again with `args` instruments the call numbered `target`.
+struct InstrumentBoxedAsync {
+    target: usize,
+    visited: usize,
+    args: Option<Args>,
+}
+
+impl visit_mut::VisitMut for InstrumentBoxedAsync {
+    fn visit_item_mut(&mut self, _: &mut Item) {}
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr) {
+        visit_mut::visit_expr_mut(self, expr);
+
+        let Expr::Call(ExprCall { func, args, .. }) = expr else {
+            return;
+        };
+        let is_box_pin = matches!(
+            &**func,
+            Expr::Path(path) if path_to_string(&path.path).ends_with("Box::pin")
+        );
+        let async_expr = match args.first() {
+            Some(Expr::Async(async_expr)) if is_box_pin && async_expr.capture.is_some() => {
+                async_expr
+            }
+            _ => return,
+        };
+
+        self.visited += 1;
+        if self.visited == self.target {
+            let trace_args = self.args.take().unwrap();
+            let instrumented_block = gen_block(
+                &async_expr.block,
+                true,
+                false,
+                trace_args,
+                &async_expr.attrs,
+                None,
+            );
+            args[0] = Expr::Verbatim(instrumented_block);
+        }
+    }
+}
+
+/// Returns the function unchanged, except that the variable named by `guard`, or `span` for a
+/// non-async function with `conventional = true`, is still bound, to `()`, so that the body
+/// referring to it compiles, and that the marker attribute given by `recurse_only` is removed from
+/// the nested functions. The arguments are parsed as usual, so that an invalid argument is
+/// reported even though it has no effect.
+fn gen_disabled(
+    args: proc_macro2::TokenStream,
+    item: proc_macro2::TokenStream,
+) -> proc_macro2::TokenStream {
+    let mut input = match syn::parse2::<ItemFn>(item.clone()) {
+        Ok(input) => input,
+        Err(err) => return token_stream_with_error(item, err),
+    };
+    let args = match AttributeArgs::parse_terminated.parse2(args) {
+        Ok(args) => Args::parse(&input.sig, &input.attrs, args),
+        Err(_) => abort_call_site!("invalid argument"),
+    };
+
+    if args.recurse_only.is_some() {
+        visit_mut::VisitMut::visit_block_mut(
+            &mut TraceNestedFns {
+                krate: &args.krate,
+                marker: args.recurse_only.as_ref(),
+                annotate: false,
+            },
+            &mut input.block,
+        );
+    }
+
+    match args.guard {
+        Some(guard) => {
+            input.block.stmts.insert(
+                0,
+                parse_quote!(
+                    #[allow(unused_variables, clippy::let_unit_value)]
+                    let #guard = ();
+                ),
+            );
+            quote::quote!(#input)
+        }
+        None if args.recurse_only.is_some() => quote::quote!(#input),
+        None => item,
+    }
+}
+
+/// Emits a warning for each deprecated argument by referring to a deprecated constant named after
+/// it, since custom warnings can not be emitted by procedural macros on stable Rust.
+fn gen_deprecated(deprecated: &[(Ident, &str)]) -> proc_macro2::TokenStream {
+    deprecated
+        .iter()
+        .map(|(old, new)| {
+            let note = format!("`{}` is deprecated, use `{}` instead", old, new);
+            quote_spanned!(old.span()=>
+                {
+                    #[deprecated(note = #note)]
+                    #[allow(non_upper_case_globals)]
+                    const #old: () = ();
+                    let _ = #old;
+                }
+            )
+        })
+        .collect()
+}
+
+/// An attribute macro to trace the `main` function of an executable.
+///
+/// This macro sets up a reporter, then runs the function in a root span named after the function
+/// which is set as the local parent, so that the functions annotated with `#[trace]` are recorded
+/// as its children. The spans are flushed before the function returns.
+///
+/// The spans are reported to the reporter given by `reporter`, e.g.
+/// `#[minitrace::main(reporter = MyReporter::new())]`, which replaces any reporter already set.
+/// Without `reporter`, the spans are reported to the console by `ConsoleReporter`, unless a
+/// reporter is already set, which is kept.
+///
+/// The macro is intended for examples and demos. Applications reporting to a tracing backend should
+/// set up the reporter and the root span manually.
+///
+/// For `async fn main`, apply it below the attribute of the async runtime, e.g. `#[tokio::main]`.
+///
+/// If the `minitrace` dependency is renamed, pass the path to it with `crate`, e.g.
+/// `#[my_minitrace::main(crate = my_minitrace)]`.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::prelude::*;
+///
+/// #[trace]
+/// fn foo() {
+///     // ...
+/// }
+///
+/// #[minitrace::main]
+/// fn main() {
+///     foo();
+/// }
+/// ```
+#[proc_macro_attribute]
+#[proc_macro_error]
+pub fn main(
+    args: proc_macro::TokenStream,
+    item: proc_macro::TokenStream,
+) -> proc_macro::TokenStream {
+    if cfg!(feature = "disable") {
+        return item;
+    }
+
+    let input = match syn::parse::<ItemFn>(item.clone()) {
+        Ok(input) => input,
+        Err(err) => return token_stream_with_error(item.into(), err).into(),
+    };
+
+    let mut krate = parse_quote!(minitrace);
+    let mut reporter = None;
+    match AttributeArgs::parse_terminated.parse(args) {
+        Ok(args) => {
+            let mut keys = HashSet::new();
+            for arg in &args {
+                match parse_name_value(arg) {
+                    (
+                        key,
+                        Expr::Path(ExprPath {
+                            path, qself: None, ..
+                        }),
+                    ) if key == "crate" && keys.insert("crate") => krate = path.clone(),
+                    (key, value) if key == "reporter" && keys.insert("reporter") => {
+                        reporter = Some(value.clone())
+                    }
+                    _ => {
+                        abort_call_site!("`main` only accepts the `crate` and `reporter` arguments")
+                    }
+                }
+            }
+        }
+        Err(_) => abort_call_site!("invalid argument"),
+    }
+
+    if let Some(asyncness) = input.sig.asyncness {
+        abort!(
+            asyncness,
+            "`main` can not be applied on async function, apply it below the attribute of the async runtime, e.g. `#[tokio::main]`"
+        );
+    }
+
+    let ItemFn {
+        attrs,
+        vis,
+        sig,
+        block,
     } = input;
 
-    let Signature {
-        output: return_type,
-        inputs: params,
-        unsafety,
-        constness,
-        abi,
-        ident,
-        asyncness,
-        generics:
-            Generics {
-                params: gen_params,
-                where_clause,
-                ..
-            },
-        ..
-    } = sig;
+    let name = sig.ident.to_string();
+    let return_type = match &sig.output {
+        ReturnType::Default => quote!(()),
+        ReturnType::Type(_, ty) => quote!(#ty),
+    };
+
+    let set_reporter = match reporter {
+        Some(reporter) => quote_spanned!(reporter.span()=>
+            #krate::set_reporter(#reporter, #krate::collector::Config::default());
+        ),
+        None => quote!(#krate::util::set_default_reporter();),
+    };
 
     quote::quote!(
         #(#attrs) *
-        #vis #constness #unsafety #asyncness #abi fn #ident<#gen_params>(#params) #return_type
-        #where_clause
+        #vis #sig
         {
-            #func_body
+            #set_reporter
+
+            let __ret = {
+                let __root = #krate::Span::root(
+                    #name,
+                    #krate::collector::SpanContext::random(),
+                );
+                let __guard = __root.set_local_parent();
+                (|| -> #return_type #block)()
+            };
+
+            #krate::flush();
+            __ret
         }
     )
     .into()
@@ -237,26 +1695,368 @@ fn gen_block(
     async_context: bool,
     async_keyword: bool,
     args: Args,
+    async_attrs: &[Attribute],
+    return_type: Option<&Type>,
 ) -> proc_macro2::TokenStream {
-    let name = gen_name(block.span(), args.name);
+    // Checked before the arguments are consumed, pointing at the offending argument.
+    let passthrough = args.passthrough.then(|| {
+        if !async_context {
+            abort!(
+                args.key_span("passthrough"),
+                "`passthrough` can not be applied on non-async function"
+            );
+        }
+        match get_tail_await(block) {
+            Some(future) => future.clone(),
+            None => abort!(
+                args.key_span("passthrough"),
+                "`passthrough` requires the function body to be a single `.await` expression"
+            ),
+        }
+    });
+    if async_context && args.scope == Some(Scope::Local) {
+        abort!(
+            args.key_span("scope"),
+            "`scope = Scope::Local` can not be applied on async function"
+        );
+    }
+    if !async_context {
+        if args.enter_on_poll {
+            abort!(
+                args.key_span("enter_on_poll"),
+                "`enter_on_poll` can not be applied on non-async function"
+            );
+        }
+
+        if args.lazy {
+            abort!(
+                args.key_span("lazy"),
+                "`lazy` can not be applied on non-async function"
+            );
+        }
+    }
+
+    let krate = &args.krate;
+    let is_computed_name = matches!(args.name, Name::Format(_) | Name::From(_));
+    let name = gen_name(block.span(), args.name, krate);
+
+    // The body loses the return type of the function as its expected type when it is wrapped in a
+    // closure or an async block, which breaks coercions such as `&String` to `&str` in the tail
+    // expression. An unreachable `return` of the return type restores it.
+    let return_hint = return_type.map(|return_type| {
+        quote_spanned!(return_type.span()=>
+            #[allow(
+                unreachable_code,
+                clippy::diverging_sub_expression,
+                clippy::empty_loop,
+                clippy::let_unit_value,
+                clippy::needless_return,
+                clippy::unreachable
+            )]
+            if false {
+                let __ret: #return_type = loop {};
+                return __ret;
+            }
+        )
+    });
+
+    // Whether the call is recorded is decided once on entry when `sample` is given, and the
+    // properties are only added to the span of the function if it is recorded.
+    let sample = args
+        .sample
+        .map(|ratio| quote!(let __sampled = #krate::util::sample(#ratio);));
+    let if_sampled = |tokens: proc_macro2::TokenStream| {
+        if sample.is_some() {
+            quote!(if __sampled { #tokens })
+        } else {
+            tokens
+        }
+    };
+
+    // Evaluate the body in a nested closure or async block, so that returns and `?` inside the
+    // body are observed before the span ends.
+    let block = if args.err
+        || args.ok_code.is_some()
+        || args.err_code.is_some()
+        || args.record_return
+    {
+        // A function rather than inline code, so that a return type other than a `Result` whose
+        // error implements `Display` is reported as such.
+        let record_err_fn = args.err.then(|| {
+            quote!(
+                fn __minitrace_record_err<T, E: ::std::fmt::Display>(
+                    ret: &::core::result::Result<T, E>,
+                ) {
+                    if let ::core::result::Result::Err(err) = ret {
+                        #krate::local::LocalSpan::add_property(|| ("error", err.to_string()));
+                        #krate::local::LocalSpan::set_status(
+                            #krate::collector::SpanStatus::Error,
+                        );
+                    }
+                }
+            )
+        });
+        let record_err = args.err.then(|| quote!(__minitrace_record_err(&__ret);));
+        // Likewise, so that a return type not implementing `Debug` is reported at the type.
+        let record_return_fn = args.record_return.then(|| {
+            let span = return_type.map_or_else(|| block.span(), |ty| ty.span());
+            quote_spanned!(span=>
+                fn __minitrace_record_return<T: ::std::fmt::Debug>(ret: &T) {
+                    #krate::local::LocalSpan::add_property(|| ("return", ::std::format!("{:?}", ret)));
+                }
+            )
+        });
+        let record_return = args
+            .record_return
+            .then(|| quote!(__minitrace_record_return(&__ret);));
+        let record_code = |code: &Option<Expr>| {
+            code.as_ref().map(|code| {
+                quote_spanned!(code.span()=>
+                    #krate::local::LocalSpan::add_property(|| {
+                        ("status_code", ::std::string::ToString::to_string(&(#code)))
+                    });
+                )
+            })
+        };
+        let record_ok_code = record_code(&args.ok_code);
+        let record_err_code = record_code(&args.err_code);
+        let record_code = (args.ok_code.is_some() || args.err_code.is_some()).then(|| {
+            quote!(
+                match &__ret {
+                    ::core::result::Result::Ok(_) => { #record_ok_code }
+                    ::core::result::Result::Err(_) => { #record_err_code }
+                }
+            )
+        });
+        let record_result = if_sampled(quote!(#record_err #record_code #record_return));
+        let eval = if async_context {
+            quote_spanned!(block.span()=>
+                async move { #return_hint #block }.await
+            )
+        } else {
+            quote_spanned!(block.span()=>
+                (|| { #return_hint #block })()
+            )
+        };
+
+        quote_spanned!(block.span()=>
+            {
+                #record_err_fn
+                #record_return_fn
+
+                #[allow(clippy::redundant_closure_call)]
+                let __ret = #eval;
+                #record_result
+                __ret
+            }
+        )
+    } else if async_context {
+        quote!({ #return_hint #block })
+    } else {
+        quote!(#block)
+    };
+
+    // A panic in the body is recorded while the span is still the local parent, and resumed.
+    let block = if args.catch_panic && !async_context {
+        quote_spanned!(block.span()=>
+            #krate::util::catch_panic(|| { #return_hint #block })
+        )
+    } else {
+        block
+    };
+
+    // Properties recording the arguments listed in `display` and `variables`, except the skipped
+    // ones, the generic type parameters and the start time.
+    let recorded = |ident: &&Ident| !args.skip.contains(ident);
+    let properties = if args.display.is_empty()
+        && args.variables.is_empty()
+        && args.type_params.is_empty()
+        && !args.timestamp
+    {
+        None
+    } else {
+        // A redacted argument is recorded as `***`, or as its formatted value passed through the
+        // given function.
+        let format = |ident: &Ident, format: &str| {
+            let redact = args.redact.iter().find(|(param, _)| param == ident);
+            match redact {
+                Some((_, None)) => quote!(::std::string::String::from("***")),
+                Some((_, Some(redact))) => {
+                    quote_spanned!(redact.span()=> (#redact)(&::std::format!(#format, #ident)))
+                }
+                None => quote!(::std::format!(#format, #ident)),
+            }
+        };
+        let display = args.display.iter().filter(recorded).map(|ident| {
+            let key = ident.to_string();
+            let value = format(ident, "{}");
+            quote_spanned!(ident.span()=>
+                (#key, #value)
+            )
+        });
+        let variables = args.variables.iter().filter(recorded).map(|ident| {
+            let key = ident.to_string();
+            let value = format(ident, "{:?}");
+            quote_spanned!(ident.span()=>
+                (#key, #value)
+            )
+        });
+        let type_params = args.type_params.iter().map(|ident| {
+            let key = ident.to_string();
+            quote_spanned!(ident.span()=>
+                (#key, ::std::string::ToString::to_string(::std::any::type_name::<#ident>()))
+            )
+        });
+        let timestamp = args
+            .timestamp
+            .then(|| quote!(("started_at", #krate::util::rfc3339_now())));
+        Some(quote!(|| [#(#display,)* #(#variables,)* #(#type_params,)* #timestamp]))
+    };
+
+    // A root span under the given parent id, sharing the trace id of the local parent if any, or a
+    // root span of a new trace for `root` or a test function.
+    let root = match &args.parent_id {
+        Some(parent_id) => Some(quote_spanned!(parent_id.span()=>
+            #krate::Span::root(
+                #name,
+                #krate::collector::SpanContext::new(
+                    #krate::collector::SpanContext::current_local_parent()
+                        .unwrap_or_else(#krate::collector::SpanContext::random)
+                        .trace_id,
+                    #krate::collector::SpanId(#parent_id),
+                ),
+            )
+        )),
+        None if args.root => Some(quote!(
+            #krate::Span::root(#name, #krate::collector::SpanContext::random())
+        )),
+        None => None,
+    };
+
+    // A `Span` entered as a child of the local parent, or of the enclosing span named by `parent`.
+    let enter_span = match &args.parent {
+        Some(parent) => quote_spanned!(parent.span()=>
+            #krate::Span::enter_with_local_ancestor( #name, #parent )
+        ),
+        None => quote!(#krate::Span::enter_with_local_parent( #name )),
+    };
+
+    // The id of the tokio task polling the function, recorded once the span is entered.
+    let task_id = if cfg!(feature = "tokio") {
+        Some(if_sampled(quote!(
+            if let ::core::option::Option::Some(id) = #krate::util::tokio_task_id() {
+                #krate::local::LocalSpan::add_property(|| ("tokio.task_id", id));
+            }
+        )))
+    } else {
+        None
+    };
+
+    let with_capacity = args
+        .buffer
+        .map(|capacity| quote!(.with_properties_capacity(#capacity)));
 
     // Generate the instrumented function body.
     // If the function is an `async fn`, this will wrap it in an async block.
     // Otherwise, this will enter the span and then perform the rest of the body.
     if async_context {
-        let block = if args.enter_on_poll {
-            quote_spanned!(block.span()=>
-                minitrace::future::FutureExt::enter_on_poll(
-                    async move { #block },
-                    #name
+        if args.min_duration.is_some() {
+            abort_call_site!("`min_duration` can not be applied on async function");
+        }
+
+        if args.guard.is_some() {
+            abort_call_site!("`guard` can not be applied on async function");
+        }
+
+        if args.child_count {
+            abort_call_site!("`child_count` can not be applied on async function");
+        }
+
+        if args.coalesce {
+            abort_call_site!("`coalesce` can not be applied on async function");
+        }
+
+        if args.catch_panic {
+            abort_call_site!("`catch_panic` can not be applied on async function");
+        }
+
+        let block = if args.enter_on_poll || args.lazy {
+            // The span does not exist yet, so the properties are added once the future is polled.
+            let add_properties = properties.map(|properties| {
+                if_sampled(quote!(#krate::local::LocalSpan::add_properties(#properties);))
+            });
+            let adapter = if args.enter_on_poll {
+                quote!(enter_on_poll)
+            } else {
+                quote!(in_lazy_span)
+            };
+            // A name computed from the arguments is computed before they are moved into the async
+            // block.
+            let (compute_name, name) = if is_computed_name {
+                (Some(quote!(let __name = #name;)), quote!(__name))
+            } else {
+                (None, name)
+            };
+            if sample.is_some() {
+                quote_spanned!(block.span()=>
+                    {
+                        #sample
+                        #compute_name
+                        let __future = ::core::convert::identity(
+                            #(#async_attrs)* async move { #add_properties #task_id #block }
+                        );
+                        async move {
+                            if __sampled {
+                                #krate::future::FutureExt::#adapter(__future, #name).await
+                            } else {
+                                __future.await
+                            }
+                        }
+                    }
                 )
-            )
+            } else {
+                quote_spanned!(block.span()=>
+                    {
+                        #compute_name
+                        #krate::future::FutureExt::#adapter(
+                            #(#async_attrs)* async move { #add_properties #task_id #block },
+                            #name
+                        )
+                    }
+                )
+            }
         } else {
+            // The span is created before the arguments are moved into the async block.
+            let with_properties =
+                properties.map(|properties| quote!(.with_properties(#properties)));
+            let with_properties = quote!(#with_capacity #with_properties);
+            let span = match root {
+                Some(root) if sample.is_some() => {
+                    quote!(if __sampled { #root #with_properties } else { #krate::Span::noop() })
+                }
+                Some(root) => quote!(#root #with_properties),
+                None => {
+                    let recorded = gen_recorded(sample.is_some(), krate);
+                    quote!(
+                        if #recorded {
+                            #enter_span #with_properties
+                        } else {
+                            #krate::Span::noop()
+                        }
+                    )
+                }
+            };
+            // With `passthrough`, the awaited future is instrumented in place of the body.
+            let future = match passthrough {
+                Some(future) => quote!(#future),
+                None => quote!(#(#async_attrs)* async move { #task_id #block }),
+            };
             quote_spanned!(block.span()=>
-                minitrace::future::FutureExt::in_span(
-                    async move { #block },
-                    minitrace::Span::enter_with_local_parent( #name )
-                )
+                {
+                    #sample
+                    let __span = #span;
+                    #krate::future::FutureExt::in_span(#future, __span)
+                }
             )
         };
 
@@ -268,24 +2068,183 @@ fn gen_block(
             block
         }
     } else {
-        if args.enter_on_poll {
-            abort_call_site!("`enter_on_poll` can not be applied on non-async function");
+        let min_duration = args.min_duration.map(|min_duration| {
+            let nanos = min_duration.as_nanos() as u64;
+            quote_spanned!(block.span()=>
+                .with_min_duration(::std::time::Duration::from_nanos(#nanos))
+            )
+        });
+
+        let with_properties = properties.map(|properties| quote!(.with_properties(#properties)));
+        let with_properties = quote!(#with_capacity #with_properties);
+
+        let guard = args
+            .guard
+            .unwrap_or_else(|| Ident::new("__guard", proc_macro2::Span::call_site()));
+
+        if let Some(root) = root {
+            // The root span is entered as the local parent of the function body.
+            let span = if sample.is_some() {
+                quote!(if __sampled { #root #with_properties } else { #krate::Span::noop() })
+            } else {
+                quote!(#root #with_properties)
+            };
+
+            return quote_spanned!(block.span()=>
+                #sample
+                let __span = #span;
+                let #guard = __span.set_local_parent();
+                #task_id
+                #block
+            );
+        }
+
+        let recorded = gen_recorded(sample.is_some(), krate);
+
+        if args.scope == Some(Scope::Threads) || args.parent.is_some() {
+            // The guard is the span itself, so that the body can pass it to other threads, and the
+            // span is the local parent of the function body.
+            let span = quote!(
+                if #recorded {
+                    #enter_span #with_properties
+                } else {
+                    #krate::Span::noop()
+                }
+            );
+
+            return quote_spanned!(block.span()=>
+                #sample
+                #[allow(unused_variables)]
+                let #guard = #span;
+                let __local_parent = #guard.set_local_parent();
+                #task_id
+                #block
+            );
         }
 
+        let coalesce = args.coalesce.then(|| quote!(.with_coalescing()));
+
+        let span = quote!(
+            if #recorded {
+                #krate::local::LocalSpan::enter_with_local_parent( #name )
+                    #min_duration #coalesce #with_properties
+            } else {
+                #krate::local::LocalSpan::default()
+            }
+        );
+
+        // Dropped before the guard, while the span is still the local parent.
+        let child_count = args
+            .child_count
+            .then(|| quote!(let __child_count = #krate::util::ChildCountGuard(&#guard);));
+
         quote_spanned!(block.span()=>
-            let __guard = minitrace::local::LocalSpan::enter_with_local_parent( #name );
+            #sample
+            #[allow(unused_variables)]
+            let #guard = #span;
+            #child_count
+            #task_id
             #block
         )
     }
 }
 
-fn gen_name(span: proc_macro2::Span, name: Name) -> proc_macro2::TokenStream {
+/// The condition under which the span entered with the local parent is recorded, checked before
+/// the name of the span, which may be costly to compute, is evaluated.
+fn gen_recorded(sampled: bool, krate: &Path) -> proc_macro2::TokenStream {
+    if sampled {
+        quote!(__sampled && #krate::util::has_local_parent())
+    } else {
+        quote!(#krate::util::has_local_parent())
+    }
+}
+
+/// Whether the type contains `impl Trait`, which can not be the type of a `let` binding.
+fn contains_impl_trait(tokens: proc_macro2::TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        proc_macro2::TokenTree::Ident(ident) => ident == "impl",
+        proc_macro2::TokenTree::Group(group) => contains_impl_trait(group.stream()),
+        _ => false,
+    })
+}
+
+/// Returns the awaited future of a block consisting of a single `.await` expression, e.g. `inner(a)`
+/// for `{ inner(a).await }`.
+fn get_tail_await(block: &Block) -> Option<&Expr> {
+    match block.stmts.as_slice() {
+        [Stmt::Expr(Expr::Await(ExprAwait { attrs, base, .. }))] if attrs.is_empty() => Some(base),
+        _ => None,
+    }
+}
+
+/// Returns the async block returned by a non-async function returning `impl Future`, if the
+/// function body ends with an `async move` block.
+fn get_returned_async_block(input: &ItemFn) -> Option<&ExprAsync> {
+    if !returns_impl_future(&input.sig) {
+        return None;
+    }
+
+    match input.block.stmts.last() {
+        Some(Stmt::Expr(Expr::Async(async_expr))) if async_expr.capture.is_some() => {
+            Some(async_expr)
+        }
+        _ => None,
+    }
+}
+
+/// Whether the function returns `()`, either implicitly or as `-> ()`.
+fn returns_unit(sig: &Signature) -> bool {
+    match &sig.output {
+        ReturnType::Type(_, ty) => {
+            matches!(&**ty, Type::Tuple(TypeTuple { elems, .. }) if elems.is_empty())
+        }
+        ReturnType::Default => true,
+    }
+}
+
+/// Whether the function is a non-async function returning `impl Future`, e.g.
+/// `fn f() -> impl Future<Output = T> + Send`.
+fn returns_impl_future(sig: &Signature) -> bool {
+    if sig.asyncness.is_some() {
+        return false;
+    }
+
+    match &sig.output {
+        ReturnType::Type(_, ty) => match &**ty {
+            Type::ImplTrait(TypeImplTrait { bounds, .. }) => bounds.iter().any(|bound| {
+                matches!(
+                    bound,
+                    TypeParamBound::Trait(TraitBound { path, .. })
+                        if matches!(path.segments.last(), Some(segment) if segment.ident == "Future")
+                )
+            }),
+            _ => false,
+        },
+        ReturnType::Default => false,
+    }
+}
+
+/// Whether the attribute marks a test function, e.g. `#[test]` or `#[tokio::test]`.
+fn is_test_attr(attr: &Attribute) -> bool {
+    matches!(attr.path.segments.last(), Some(segment) if segment.ident == "test")
+}
+
+fn gen_name(span: proc_macro2::Span, name: Name, krate: &Path) -> proc_macro2::TokenStream {
     match name {
         Name::Plain(name) => quote_spanned!(span=>
             #name
         ),
+        Name::Const(path) => quote_spanned!(span=>
+            #path
+        ),
+        Name::Format(name) => quote_spanned!(span=>
+            ::std::format!(#name)
+        ),
+        Name::From(ident) => quote_spanned!(span=>
+            ::std::string::ToString::to_string(&#ident)
+        ),
         Name::FullName => quote_spanned!(span=>
-            minitrace::full_name!()
+            #krate::full_name!()
         ),
     }
 }
@@ -299,7 +2258,7 @@ enum AsyncTraitKind<'a> {
 
 struct AsyncTraitInfo<'a> {
     // statement that must be patched
-    _source_stmt: &'a Stmt,
+    source_stmt: &'a Stmt,
     kind: AsyncTraitKind<'a>,
 }
 
@@ -377,7 +2336,7 @@ fn get_async_trait_info(block: &Block, block_is_async: bool) -> Option<AsyncTrai
         async_expr.capture?;
 
         return Some(AsyncTraitInfo {
-            _source_stmt: last_expr_stmt,
+            source_stmt: last_expr_stmt,
             kind: AsyncTraitKind::Async(async_expr),
         });
     }
@@ -401,7 +2360,7 @@ fn get_async_trait_info(block: &Block, block_is_async: bool) -> Option<AsyncTrai
         .find(|(_, fun)| fun.sig.ident == func_name)?;
 
     Some(AsyncTraitInfo {
-        _source_stmt: stmt_func_declaration,
+        source_stmt: stmt_func_declaration,
         kind: AsyncTraitKind::Function(func),
     })
 }
@@ -419,3 +2378,418 @@ fn path_to_string(path: &Path) -> String {
     }
     res
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn deterministic_expansion() {
+        let expand = || {
+            expand_trace(
+                quote!(display = [a], recurse = true),
+                quote!(
+                    async fn f(a: u32) -> Result<u32, ()> {
+                        fn nested() {}
+                        Ok(a)
+                    }
+                ),
+            )
+            .to_string()
+        };
+        assert_eq!(expand(), expand());
+    }
+
+    #[test]
+    fn record_return_expansion() {
+        for item in [
+            quote!(
+                fn f(a: u32) -> u32 {
+                    a
+                }
+            ),
+            quote!(
+                async fn f(a: u32) -> u32 {
+                    a
+                }
+            ),
+        ] {
+            let expanded = expand_trace(quote!(record_return = true), item).to_string();
+            let bound = expanded.find("let __ret =").unwrap();
+            let recorded = expanded
+                .find("__minitrace_record_return (& __ret)")
+                .unwrap();
+            assert!(bound < recorded, "{expanded}");
+            assert!(expanded.contains("\"return\""), "{expanded}");
+        }
+    }
+
+    #[test]
+    fn impl_future_enter_on_poll() {
+        let expanded = expand_trace(
+            quote!(enter_on_poll = true),
+            quote!(
+                fn f(a: u32) -> impl Future<Output = u32> {
+                    g(a)
+                }
+            ),
+        )
+        .to_string();
+        // The returned future is awaited in an async block entering the span on poll.
+        let future = expanded.find("let __future").unwrap();
+        let poll = expanded.find("enter_on_poll").unwrap();
+        assert!(future < poll, "{expanded}");
+        assert!(!expanded.contains("LocalSpan"), "{expanded}");
+    }
+
+    #[test]
+    fn where_clause_preserved() {
+        let where_clause = quote!(
+            where
+                Self: 'a,
+                F: for<'b> Fn(&'b str) -> bool,
+        );
+        for asyncness in [quote!(), quote!(async)] {
+            let expanded = expand_trace(
+                quote!(),
+                quote!(#asyncness fn f<'a, F>(&'a self, f: F) -> bool #where_clause { f("") }),
+            );
+            let input = syn::parse2::<ItemFn>(expanded).unwrap();
+            let expanded_where_clause = input.sig.generics.where_clause;
+            assert_eq!(
+                quote!(#expanded_where_clause).to_string(),
+                where_clause.to_string()
+            );
+        }
+    }
+
+    #[test]
+    fn scope_expansion() {
+        let expand = |args| {
+            expand_trace(
+                args,
+                quote!(
+                    fn f(a: u32) -> u32 {
+                        a
+                    }
+                ),
+            )
+            .to_string()
+        };
+
+        let local = expand(quote!(scope = Scope::Local));
+        assert_eq!(local, expand(quote!()));
+        assert!(
+            local.contains("LocalSpan :: enter_with_local_parent"),
+            "{local}"
+        );
+
+        // The span can cross threads and is the local parent of the body.
+        let threads = expand(quote!(scope = Scope::Threads, guard = span));
+        assert!(!threads.contains("LocalSpan"), "{threads}");
+        assert!(
+            threads.contains("minitrace :: Span :: enter_with_local_parent"),
+            "{threads}"
+        );
+        assert!(threads.contains("let span ="), "{threads}");
+        assert!(threads.contains("span . set_local_parent ()"), "{threads}");
+    }
+
+    #[test]
+    fn default_arguments() {
+        let parse = |args: proc_macro2::TokenStream, defaults: Option<&str>| {
+            let sig: Signature = parse_quote!(async fn f(a: u32));
+            let args = with_defaults(
+                args,
+                defaults.map(|defaults| (DEFAULTS_ENV_PREFIX, defaults)),
+            );
+            Args::parse(
+                &sig,
+                &[],
+                AttributeArgs::parse_terminated.parse2(args).unwrap(),
+            )
+        };
+
+        // Inherited.
+        let args = parse(quote!(), Some("short_name = true, enter_on_poll = true"));
+        assert!(matches!(&args.name, Name::Plain(name) if name == "f"));
+        assert!(args.enter_on_poll);
+
+        // Overridden by the same key, or by another argument naming the span.
+        let args = parse(
+            quote!(enter_on_poll = false, name = "g"),
+            Some("short_name = true, enter_on_poll = true, err = true"),
+        );
+        assert!(matches!(&args.name, Name::Plain(name) if name == "g"));
+        assert!(!args.enter_on_poll);
+        assert!(args.err);
+
+        let args = parse(
+            quote!(conventional = true),
+            Some("short_name = true, err = true"),
+        );
+        assert!(args.enter_on_poll && !args.err);
+
+        // Only `conventional = true` overrides all of them.
+        let args = parse(
+            quote!(conventional = false),
+            Some("short_name = true, err = true, conventional = true"),
+        );
+        assert!(!args.enter_on_poll && args.err);
+
+        // Overridden by a deprecated key.
+        let args = parse(quote!(catch_err = false), Some("err = true"));
+        assert!(!args.err);
+
+        assert_eq!(
+            with_defaults(quote!(name = "g"), None).to_string(),
+            quote!(name = "g").to_string()
+        );
+    }
+
+    #[test]
+    fn track_defaults_env() {
+        let tracked = track_env(quote! { fn f() { g(); } }, "MINITRACE_TRACE_DEFAULTS_APP");
+        let item: ItemFn = syn::parse2(tracked).unwrap();
+        assert_eq!(item.block.stmts.len(), 2);
+        let tracking = &item.block.stmts[0];
+        assert_eq!(
+            quote!(#tracking).to_string(),
+            quote! {
+                const _: ::core::option::Option<&str> =
+                    ::core::option_env!("MINITRACE_TRACE_DEFAULTS_APP");
+            }
+            .to_string()
+        );
+
+        // An error is left as is.
+        let error = quote! { fn f() {} ::core::compile_error! { "error" } };
+        assert_eq!(
+            track_env(error.clone(), "MINITRACE_TRACE_DEFAULTS_APP").to_string(),
+            error.to_string()
+        );
+    }
+
+    #[test]
+    fn scope_args() {
+        let sig: Signature = parse_quote!(fn f(a: u32));
+        let scope = |args: proc_macro2::TokenStream, defaults: Option<&str>| {
+            let args = with_defaults(
+                args,
+                defaults.map(|defaults| (DEFAULTS_ENV_PREFIX, defaults)),
+            );
+            Args::parse(
+                &sig,
+                &[],
+                AttributeArgs::parse_terminated.parse2(args).unwrap(),
+            )
+            .scope
+        };
+
+        assert!(scope(quote!(scope = Scope::Threads), None) == Some(Scope::Threads));
+        assert!(scope(quote!(scope = Scope::Local), None) == Some(Scope::Local));
+        assert!(scope(quote!(scope = minitrace::Scope::Threads), None) == Some(Scope::Threads));
+        assert!(scope(quote!(), None).is_none());
+        assert!(scope(quote!(), Some("scope = Scope::Threads")) == Some(Scope::Threads));
+        assert!(
+            scope(quote!(scope = Scope::Local), Some("scope = Scope::Threads"))
+                == Some(Scope::Local)
+        );
+    }
+
+    #[test]
+    fn conventional_args() {
+        let sig: Signature = parse_quote!(fn f(a: u32));
+        let args = Args::parse(&sig, &[], parse_quote!(conventional = true));
+        assert!(matches!(&args.name, Name::Plain(name) if name == "f"));
+        assert!(!args.enter_on_poll);
+        assert_eq!(args.guard.unwrap(), "span");
+
+        let sig: Signature = parse_quote!(async fn g(a: u32));
+        let args = Args::parse(&sig, &[], parse_quote!(conventional = true));
+        assert!(matches!(&args.name, Name::Plain(name) if name == "g"));
+        assert!(args.enter_on_poll);
+        assert!(args.guard.is_none());
+
+        // `conventional = false` is a no-op.
+        let args = Args::parse(
+            &sig,
+            &[],
+            parse_quote!(conventional = false, name = "h", enter_on_poll = false),
+        );
+        assert!(matches!(&args.name, Name::Plain(name) if name == "h"));
+        assert!(!args.enter_on_poll);
+
+        for args in [
+            Args::conventional(&parse_quote!(fn f())),
+            Args::conventional(&parse_quote!(async fn g())),
+        ] {
+            assert!(!args.lazy && !args.err && !args.root && !args.recurse && !args.child_count);
+            assert!(args.min_duration.is_none() && args.sample.is_none());
+            assert!(args.display.is_empty() && args.variables.is_empty());
+            assert!(args.krate.is_ident("minitrace"));
+        }
+    }
+
+    #[test]
+    fn name_placeholders() {
+        let sig: Signature = parse_quote!(fn f(user_id: u32, key: &str));
+        assert!(has_placeholders(&sig, &parse_quote!("f({user_id})")));
+        assert!(has_placeholders(&sig, &parse_quote!("f({key:?}, {{}})")));
+        assert!(!has_placeholders(&sig, &parse_quote!("f")));
+        assert!(!has_placeholders(&sig, &parse_quote!("f({{user_id}})")));
+        assert!(!has_placeholders(&sig, &parse_quote!("f}}")));
+    }
+
+    #[test]
+    fn reparse_qualifiers() {
+        assert!(
+            reparse_fn(&quote!(
+                const unsafe extern "C" fn f() {}
+            ))
+            .is_ok()
+        );
+        assert!(
+            reparse_fn(&quote!(
+                async unsafe fn f() {}
+            ))
+            .is_ok()
+        );
+        assert!(
+            reparse_fn(&quote!(
+                unsafe async fn f() {}
+            ))
+            .is_err()
+        );
+        assert!(
+            reparse_fn(&quote!(
+                extern "C" unsafe fn f() {}
+            ))
+            .is_err()
+        );
+
+        let output = expand_trace(quote!(), quote! {
+            pub(crate) async unsafe fn f<T>(a: T) -> T
+            where
+                T: Send,
+            {
+                a
+            }
+        });
+        assert!(reparse_fn(&output).is_ok());
+
+        // A `const fn` is left untouched.
+        let item = quote!(
+            pub const unsafe extern "C" fn f(a: u32) -> u32 {
+                a
+            }
+        );
+        assert_eq!(
+            expand_trace(quote!(short_name = true), item.clone()).to_string(),
+            item.to_string()
+        );
+    }
+
+    #[test]
+    fn forwards_attributes() {
+        let input: ItemFn = parse_quote!(
+            #[inline(always)]
+            #[must_use]
+            #[allow(clippy::all)]
+            #[doc = "f"]
+            fn f() -> u32 {
+                1
+            }
+        );
+        let output = expand_trace(quote!(), quote!(#input));
+        assert_eq!(reparse_fn(&output).unwrap().attrs, input.attrs);
+    }
+
+    #[test]
+    fn trace_nested_fns() {
+        let mut outer: ItemFn = parse_quote!(
+            fn outer() {
+                fn nested() {
+                    fn deep() {}
+                }
+
+                #[minitrace::trace]
+                fn traced() {}
+
+                const fn constant() {}
+            }
+        );
+        let krate = parse_quote!(minitrace);
+        visit_mut::VisitMut::visit_block_mut(
+            &mut TraceNestedFns {
+                krate: &krate,
+                marker: None,
+                annotate: true,
+            },
+            &mut outer.block,
+        );
+
+        let expected: ItemFn = parse_quote!(
+            fn outer() {
+                #[minitrace::trace(short_name = true, crate = minitrace)]
+                fn nested() {
+                    #[minitrace::trace(short_name = true, crate = minitrace)]
+                    fn deep() {}
+                }
+
+                #[minitrace::trace]
+                fn traced() {}
+
+                const fn constant() {}
+            }
+        );
+        assert_eq!(outer, expected);
+
+        // Only the marked functions are annotated, and the marker is removed.
+        let marked = |annotate| {
+            let mut outer: ItemFn = parse_quote!(
+                fn outer() {
+                    #[traced]
+                    fn nested() {
+                        fn deep() {}
+                    }
+
+                    fn unmarked() {}
+                }
+            );
+            let marker = parse_quote!(traced);
+            visit_mut::VisitMut::visit_block_mut(
+                &mut TraceNestedFns {
+                    krate: &krate,
+                    marker: Some(&marker),
+                    annotate,
+                },
+                &mut outer.block,
+            );
+            outer
+        };
+
+        let expected: ItemFn = parse_quote!(
+            fn outer() {
+                #[minitrace::trace(short_name = true, crate = minitrace)]
+                fn nested() {
+                    fn deep() {}
+                }
+
+                fn unmarked() {}
+            }
+        );
+        assert_eq!(marked(true), expected);
+
+        let expected: ItemFn = parse_quote!(
+            fn outer() {
+                fn nested() {
+                    fn deep() {}
+                }
+
+                fn unmarked() {}
+            }
+        );
+        assert_eq!(marked(false), expected);
+    }
+}
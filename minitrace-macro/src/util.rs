@@ -0,0 +1,131 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::time::Duration;
+
+use proc_macro2::TokenStream;
+
+/// Appends a `compile_error!` invocation carrying `error` to `tokens`.
+///
+/// Unlike aborting the macro, the original tokens are still emitted, so the rest of the crate
+/// keeps seeing the annotated item and only the reported error is added to the diagnostics.
+///
+/// Since this crate is a `proc-macro` crate, it can only export procedural macros, hence this
+/// helper is crate-private.
+pub(crate) fn token_stream_with_error(mut tokens: TokenStream, error: syn::Error) -> TokenStream {
+    tokens.extend(error.into_compile_error());
+    tokens
+}
+
+/// Parses a duration literal consisting of an integer and a unit, e.g. `"100us"` or `"1ms"`.
+///
+/// The supported units are `ns`, `us`, `ms` and `s`.
+pub(crate) fn parse_duration(s: &str) -> Option<Duration> {
+    let s = s.trim();
+    let unit_start = s.find(|c: char| !c.is_ascii_digit())?;
+    let (value, unit) = s.split_at(unit_start);
+    let value: u64 = value.parse().ok()?;
+
+    match unit.trim_start() {
+        "ns" => Some(Duration::from_nanos(value)),
+        "us" => Some(Duration::from_micros(value)),
+        "ms" => Some(Duration::from_millis(value)),
+        "s" => Some(Duration::from_secs(value)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use proc_macro2::Span;
+    use quote::quote;
+
+    use super::*;
+
+    /// Asserts that two token streams are the same items, ignoring spans and the formatting of the
+    /// tokens, by comparing their `syn` syntax trees.
+    #[track_caller]
+    fn assert_tokens_eq(actual: TokenStream, expected: TokenStream) {
+        let parse = |tokens: TokenStream| {
+            syn::parse2::<syn::File>(tokens.clone())
+                .unwrap_or_else(|err| panic!("failed to parse `{}`: {}", tokens, err))
+        };
+        assert_eq!(
+            parse(actual.clone()),
+            parse(expected.clone()),
+            "\n  actual: `{}`\nexpected: `{}`",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn tokens_eq_ignores_formatting() {
+        assert_tokens_eq(quote! { fn f() { 1 } }, "fn   f( )\n{ 1 }".parse().unwrap());
+    }
+
+    #[test]
+    #[should_panic]
+    fn tokens_ne() {
+        assert_tokens_eq(quote! { fn f() { 1 } }, quote! { fn f() { 2 } });
+    }
+
+    #[test]
+    fn appends_compile_error() {
+        let tokens = quote! {
+            struct S;
+        };
+        let error = syn::Error::new(Span::call_site(), "expected `fn`");
+
+        let output = token_stream_with_error(tokens, error);
+        let expected = quote! {
+            struct S;
+            compile_error! { "expected `fn`" }
+        };
+        assert_tokens_eq(output, expected);
+    }
+
+    #[test]
+    fn appends_compile_error_to_empty_tokens() {
+        let error = syn::Error::new(Span::call_site(), "invalid argument");
+
+        let output = token_stream_with_error(TokenStream::new(), error);
+        let expected = quote!(compile_error! { "invalid argument" });
+        assert_tokens_eq(output, expected);
+    }
+
+    #[test]
+    fn appends_all_combined_errors() {
+        let tokens = quote!(
+            fn f() {}
+        );
+        let mut error = syn::Error::new(Span::call_site(), "first");
+        error.combine(syn::Error::new(Span::call_site(), "second"));
+
+        let output = token_stream_with_error(tokens, error);
+        let expected = quote!(
+            fn f() {}
+            compile_error! { "first" }
+            compile_error! { "second" }
+        );
+        assert_tokens_eq(output, expected);
+    }
+
+    #[test]
+    fn parse_valid_durations() {
+        assert_eq!(parse_duration("10ns"), Some(Duration::from_nanos(10)));
+        assert_eq!(parse_duration("100us"), Some(Duration::from_micros(100)));
+        assert_eq!(parse_duration("1ms"), Some(Duration::from_millis(1)));
+        assert_eq!(parse_duration("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(parse_duration(" 5 ms "), Some(Duration::from_millis(5)));
+    }
+
+    #[test]
+    fn parse_invalid_durations() {
+        assert_eq!(parse_duration(""), None);
+        assert_eq!(parse_duration("1"), None);
+        assert_eq!(parse_duration("ms"), None);
+        assert_eq!(parse_duration("1.5ms"), None);
+        assert_eq!(parse_duration("-1ms"), None);
+        assert_eq!(parse_duration("1h"), None);
+    }
+}
@@ -0,0 +1,57 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+
+#[trace]
+fn work_sync(a: u32) -> u32 {
+    a
+}
+
+#[trace(name = "work", guard = span)]
+fn work_with_guard(a: u32) -> u32 {
+    let _ = &span;
+    a
+}
+
+#[trace(enter_on_poll = true)]
+async fn work_async(a: u32) -> u32 {
+    a
+}
+
+#[trace(recurse = true, recurse_only = traced)]
+fn work_recurse(a: u32) -> u32 {
+    #[traced]
+    fn nested(a: u32) -> u32 {
+        a
+    }
+
+    nested(a)
+}
+
+#[tokio::test]
+async fn no_spans() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(work_sync(1), 1);
+        assert_eq!(work_with_guard(2), 2);
+        assert_eq!(work_async(3).await, 3);
+        assert_eq!(work_recurse(4), 4);
+    }
+
+    minitrace::flush();
+
+    // Only the root span, which is not created by `#[trace]`, is recorded.
+    let names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| span.name.clone())
+        .collect::<Vec<_>>();
+    assert_eq!(names, ["root"]);
+}
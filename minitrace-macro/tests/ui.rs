@@ -1,6 +1,15 @@
 #[test]
+#[cfg(not(feature = "disable"))]
 fn ui() {
     let t = trybuild::TestCases::new();
     t.compile_fail("tests/ui/err/*.rs");
     t.pass("tests/ui/ok/*.rs");
 }
+
+#[test]
+#[cfg(feature = "disable")]
+fn ui_disable() {
+    let t = trybuild::TestCases::new();
+    t.compile_fail("tests/ui/disable-err/*.rs");
+    t.pass("tests/ui/disable/*.rs");
+}
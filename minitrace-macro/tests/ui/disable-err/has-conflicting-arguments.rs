@@ -0,0 +1,6 @@
+use minitrace_macro::trace;
+
+#[trace(root = true, parent_id = 1)]
+fn f() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace_macro::trace;
+
+#[trace(nmae = "f")]
+fn f() {}
+
+fn main() {}
@@ -0,0 +1,54 @@
+// The expansion must not refer to `minitrace`, which is shadowed by an empty module here.
+mod minitrace {}
+
+use minitrace_macro::trace;
+
+#[trace]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(name = "b", enter_on_poll = true, display = [a])]
+async fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace(min_duration = "1ms", err = true)]
+fn h(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(guard = span)]
+fn i(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+#[trace(recorder = span)]
+fn j(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+#[trace(conventional = true)]
+fn k(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+#[trace(conventional = true)]
+async fn l(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+#[minitrace_macro::main]
+async fn main() {
+    f(1);
+    g(1).await;
+    h(1).ok();
+    i(1);
+    j(1);
+    k(1);
+    l(1).await;
+}
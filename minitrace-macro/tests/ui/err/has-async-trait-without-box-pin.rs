@@ -0,0 +1,4 @@
+#[minitrace::trace(async_trait = true)]
+fn f() {}
+
+fn main() {}
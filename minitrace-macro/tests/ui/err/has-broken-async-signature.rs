@@ -0,0 +1,13 @@
+use minitrace::trace;
+
+#[trace]
+async fn f(a: UnknownType) -> u32 {
+    a.0
+}
+
+#[trace]
+async fn g() -> u32 {
+    "not a number"
+}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[minitrace::trace(buffer = 4, lazy = true)]
+async fn f() {}
+
+fn main() {}
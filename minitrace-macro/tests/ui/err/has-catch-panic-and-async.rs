@@ -0,0 +1,4 @@
+#[minitrace::trace(catch_panic = true)]
+async fn f() {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[minitrace::trace(child_count = true)]
+async fn f() {}
+
+fn main() {}
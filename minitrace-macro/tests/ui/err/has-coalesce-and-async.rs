@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(coalesce = true)]
+async fn f() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(coalesce = true, min_duration = "1ms")]
+fn f() {}
+
+fn main() {}
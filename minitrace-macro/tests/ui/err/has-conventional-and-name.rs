@@ -0,0 +1,4 @@
+#[minitrace::trace(conventional = true, name = "f")]
+fn f() {}
+
+fn main() {}
@@ -0,0 +1,15 @@
+#![deny(deprecated)]
+
+use minitrace::trace;
+
+#[trace(catch_err = true)]
+fn f(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(err = true, catch_err = true)]
+fn g(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+fn main() {}
@@ -0,0 +1,16 @@
+#![deny(deprecated)]
+
+use minitrace::trace;
+
+#[trace]
+#[deprecated(note = "use `g` instead")]
+fn f() {}
+
+#[deprecated(note = "use `g` instead")]
+#[trace]
+async fn h() {}
+
+fn main() {
+    f();
+    let _ = h();
+}
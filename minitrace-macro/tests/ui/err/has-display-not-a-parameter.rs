@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(display = [a, c])]
+fn f(a: u32, b: u32) {}
+
+fn main() {}
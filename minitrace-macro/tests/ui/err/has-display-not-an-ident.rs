@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(display = [a.0])]
+fn f(a: (u32,)) {}
+
+fn main() {}
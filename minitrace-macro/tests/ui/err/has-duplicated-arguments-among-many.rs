@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+#[trace(short_name = true, err = true, lazy = true, err = false)]
+async fn f() -> Result<(), String> {
+    Ok(())
+}
+
+fn main() {}
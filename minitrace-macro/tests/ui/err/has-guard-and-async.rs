@@ -0,0 +1,4 @@
+#[minitrace::trace(guard = span)]
+async fn f() {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[minitrace::trace(guard = a, recorder = b)]
+fn f() {}
+
+fn main() {}
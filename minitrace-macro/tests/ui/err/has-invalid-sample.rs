@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(sample = 1.5)]
+fn f() {}
+
+fn main() {}
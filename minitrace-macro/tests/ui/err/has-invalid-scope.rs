@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(scope = Scope::Global)]
+fn f() {}
+
+fn main() {}
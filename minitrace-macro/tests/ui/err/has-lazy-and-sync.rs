@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(lazy = true)]
+fn f() {}
+
+fn main() {}
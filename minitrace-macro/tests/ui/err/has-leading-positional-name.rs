@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace("x", enter_on_poll = true)]
+async fn f() {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[minitrace::trace(name("x"))]
+fn f() {}
+
+fn main() {}
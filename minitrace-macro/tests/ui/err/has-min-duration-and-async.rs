@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+#[trace(min_duration = "1ms")]
+async fn f(a: u32) -> u32 {
+    a
+}
+
+fn main() {}
@@ -0,0 +1,21 @@
+#![deny(unused_must_use)]
+
+use minitrace::trace;
+
+#[trace]
+#[inline]
+#[must_use = "the result must be checked"]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[must_use]
+#[trace(short_name = true)]
+fn g(a: u32) -> u32 {
+    a
+}
+
+fn main() {
+    f(1);
+    g(1);
+}
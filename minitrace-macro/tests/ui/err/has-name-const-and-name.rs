@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+const NAME: &str = "f";
+
+#[trace(name_const = NAME, name = "f")]
+fn f() {}
+
+fn main() {}
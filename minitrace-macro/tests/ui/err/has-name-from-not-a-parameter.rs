@@ -0,0 +1,4 @@
+#[minitrace::trace(name_from = state)]
+fn f(s: u32) {}
+
+fn main() {}
@@ -0,0 +1,4 @@
+#[minitrace::trace(name = "handle_user({id})")]
+fn f(user_id: u32) {}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use minitrace::trace;
+
+#[trace(name = "get({key}})")]
+fn f(key: &str) {}
+
+#[trace(name = "get(key}")]
+fn g(key: &str) {}
+
+#[trace(name = "get({key")]
+fn h(key: &str) {}
+
+fn main() {}
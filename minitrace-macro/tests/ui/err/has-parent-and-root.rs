@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(parent = "request", root = true)]
+fn f() {}
+
+fn main() {}
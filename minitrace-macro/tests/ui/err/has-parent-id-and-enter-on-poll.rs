@@ -0,0 +1,4 @@
+#[minitrace::trace(parent_id = 42, enter_on_poll = true)]
+async fn f() {}
+
+fn main() {}
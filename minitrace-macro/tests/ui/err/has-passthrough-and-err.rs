@@ -0,0 +1,12 @@
+use minitrace::trace;
+
+async fn inner(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(passthrough = true, err = true)]
+async fn f(a: u32) -> Result<u32, String> {
+    inner(a).await
+}
+
+fn main() {}
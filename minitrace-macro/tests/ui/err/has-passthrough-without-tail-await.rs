@@ -0,0 +1,13 @@
+use minitrace::trace;
+
+async fn inner(a: u32) -> u32 {
+    a
+}
+
+#[trace(passthrough = true)]
+async fn f(a: u32) -> u32 {
+    let b = inner(a).await;
+    b + 1
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use minitrace::trace;
+
+struct Foo;
+
+#[trace(record_return = true)]
+fn f() -> Foo {
+    Foo
+}
+
+fn main() {
+    f();
+}
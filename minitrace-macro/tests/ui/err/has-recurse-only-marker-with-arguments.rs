@@ -0,0 +1,11 @@
+use minitrace::trace;
+
+#[trace(recurse = true, recurse_only = traced)]
+fn f() {
+    #[traced(name = "g")]
+    fn g() {}
+
+    g()
+}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(recurse_only = traced)]
+fn f() {}
+
+fn main() {}
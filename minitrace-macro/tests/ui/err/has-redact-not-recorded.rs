@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(display = [user], redact = [token])]
+fn f(user: &str, token: &str) {}
+
+fn main() {}
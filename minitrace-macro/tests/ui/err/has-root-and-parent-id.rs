@@ -0,0 +1,4 @@
+#[minitrace::trace(root = true, parent_id = 42)]
+fn f() {}
+
+fn main() {}
@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(scope = Scope::Local)]
+async fn f() {}
+
+fn main() {}
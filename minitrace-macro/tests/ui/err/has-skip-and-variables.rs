@@ -0,0 +1,8 @@
+use minitrace::trace;
+
+#[trace(variables = [user, password], skip = [password])]
+fn login(user: &str, password: &str) -> bool {
+    !user.is_empty() && !password.is_empty()
+}
+
+fn main() {}
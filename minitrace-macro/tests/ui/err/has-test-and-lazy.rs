@@ -0,0 +1,5 @@
+#[minitrace::trace(lazy = true)]
+#[tokio::test]
+async fn f() {}
+
+fn main() {}
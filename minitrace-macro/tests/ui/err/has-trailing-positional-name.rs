@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(enter_on_poll = true, "x")]
+async fn f() {}
+
+fn main() {}
@@ -0,0 +1,17 @@
+#![deny(unused_must_use)]
+
+use minitrace::trace;
+
+#[trace]
+async fn f() {}
+
+#[trace(enter_on_poll = true)]
+#[must_use]
+async fn g() -> u32 {
+    1
+}
+
+fn main() {
+    f();
+    g();
+}
@@ -0,0 +1,4 @@
+#[minitrace::trace]
+fn _() {}
+
+fn main() {}
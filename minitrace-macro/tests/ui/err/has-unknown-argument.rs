@@ -0,0 +1,6 @@
+use minitrace::trace;
+
+#[trace(defaults = [1, 2])]
+async fn f(a: u32, b: u32) {}
+
+fn main() {}
@@ -0,0 +1,2 @@
+#[minitrace::main]
+async fn main() {}
@@ -0,0 +1,2 @@
+#[minitrace::main(name = "main")]
+fn main() {}
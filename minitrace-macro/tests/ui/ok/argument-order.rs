@@ -0,0 +1,29 @@
+use minitrace::trace;
+
+#[trace(name = "x", enter_on_poll = true)]
+async fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(enter_on_poll = true, name = "x")]
+async fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace(short_name = true, err = true)]
+fn h(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(err = true, short_name = true)]
+fn i(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).await;
+    g(1).await;
+    h(1).ok();
+    i(1).ok();
+}
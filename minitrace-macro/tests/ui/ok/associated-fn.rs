@@ -0,0 +1,26 @@
+use minitrace::trace;
+
+struct Foo;
+
+impl Foo {
+    #[trace]
+    fn new() -> Self {
+        Foo
+    }
+
+    #[trace]
+    async fn new_async() -> Self {
+        Self
+    }
+
+    #[trace(short_name = true)]
+    fn with_self(&self) -> &Self {
+        self
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    Foo::new().with_self();
+    Foo::new_async().await;
+}
@@ -0,0 +1,28 @@
+use std::rc::Rc;
+
+async fn yield_now() {}
+
+#[minitrace::trace]
+async fn f(a: Rc<u32>) -> u32 {
+    let b = Rc::clone(&a);
+    yield_now().await;
+    *b
+}
+
+#[minitrace::trace(enter_on_poll = true)]
+async fn g(a: Rc<u32>) -> u32 {
+    let b = Rc::clone(&a);
+    yield_now().await;
+    *b
+}
+
+#[tokio::main(flavor = "current_thread")]
+async fn main() {
+    let local = tokio::task::LocalSet::new();
+    local
+        .run_until(async {
+            tokio::task::spawn_local(f(Rc::new(1))).await.unwrap();
+            tokio::task::spawn_local(g(Rc::new(2))).await.unwrap();
+        })
+        .await;
+}
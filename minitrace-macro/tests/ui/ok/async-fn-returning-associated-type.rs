@@ -0,0 +1,50 @@
+trait Load {
+    type Output;
+    type Error: std::fmt::Display;
+
+    #[allow(async_fn_in_trait)]
+    async fn load(&self) -> Self::Output;
+
+    #[allow(async_fn_in_trait)]
+    async fn try_load(&self) -> Result<Self::Output, Self::Error>;
+}
+
+#[async_trait::async_trait]
+trait LoadBoxed {
+    type Output;
+
+    async fn load(&self) -> Self::Output;
+}
+
+struct Loader;
+
+impl Load for Loader {
+    type Output = u32;
+    type Error = String;
+
+    #[minitrace::trace]
+    async fn load(&self) -> Self::Output {
+        1
+    }
+
+    #[minitrace::trace(err = true)]
+    async fn try_load(&self) -> Result<Self::Output, Self::Error> {
+        Ok(Load::load(self).await)
+    }
+}
+
+#[async_trait::async_trait]
+impl LoadBoxed for Loader {
+    type Output = u32;
+
+    #[minitrace::trace]
+    async fn load(&self) -> Self::Output {
+        1
+    }
+}
+
+fn main() {
+    let _ = Load::load(&Loader);
+    let _ = Loader.try_load();
+    let _ = LoadBoxed::load(&Loader);
+}
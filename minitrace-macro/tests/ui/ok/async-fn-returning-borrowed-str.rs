@@ -0,0 +1,60 @@
+struct Foo {
+    name: String,
+}
+
+impl Foo {
+    #[minitrace::trace]
+    async fn first(&self) -> &str {
+        &self.name
+    }
+
+    #[minitrace::trace(enter_on_poll = true)]
+    async fn first_on_poll(&self) -> &str {
+        &self.name
+    }
+
+    // `&String` is coerced to `&str` by the return type.
+    #[minitrace::trace]
+    async fn non_empty(&self) -> Option<&str> {
+        if self.name.is_empty() {
+            None
+        } else {
+            Some(&self.name)
+        }
+    }
+
+    #[minitrace::trace(err = true)]
+    async fn non_empty_or_err(&self) -> Result<&str, String> {
+        if self.name.is_empty() {
+            Err("empty".to_string())
+        } else {
+            Ok(&self.name)
+        }
+    }
+
+    #[minitrace::trace(err = true)]
+    fn non_empty_or_err_sync(&self) -> Result<&str, String> {
+        if self.name.is_empty() {
+            Err("empty".to_string())
+        } else {
+            Ok(&self.name)
+        }
+    }
+}
+
+#[minitrace::trace(lazy = true)]
+async fn longest<'a>(a: &'a str, b: &'a str) -> &'a str {
+    if a.len() > b.len() { a } else { b }
+}
+
+fn main() {
+    let foo = Foo {
+        name: "foo".to_string(),
+    };
+    let _ = foo.first();
+    let _ = foo.first_on_poll();
+    let _ = foo.non_empty();
+    let _ = foo.non_empty_or_err();
+    let _ = foo.non_empty_or_err_sync();
+    let _ = longest("a", "b");
+}
@@ -0,0 +1,25 @@
+use std::future::Future;
+
+use minitrace::trace;
+
+#[trace]
+async fn f(a: u32) -> Result<impl Future<Output = u32>, String> {
+    Ok(async move { a })
+}
+
+#[trace(err = true)]
+async fn g<'a>(a: &'a u32) -> Result<impl Future<Output = u32> + 'a, String> {
+    Ok(async move { *a })
+}
+
+#[trace(enter_on_poll = true)]
+async fn h(a: u32) -> Result<impl Future<Output = u32> + Send, String> {
+    Ok(async move { a })
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).await.unwrap().await;
+    g(&1).await.unwrap().await;
+    h(1).await.unwrap().await;
+}
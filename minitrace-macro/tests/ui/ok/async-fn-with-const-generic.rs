@@ -0,0 +1,27 @@
+use minitrace::trace;
+
+#[trace]
+async fn f<const N: usize>(a: [u32; N]) -> usize {
+    a.iter().take(N).count()
+}
+
+#[trace(enter_on_poll = true, type_params = true)]
+async fn g<T: Default + Copy, const N: usize>() -> [T; N] {
+    [T::default(); N]
+}
+
+struct Buffer<const N: usize>;
+
+impl<const N: usize> Buffer<N> {
+    #[trace(short_name = true)]
+    async fn len<const M: usize>(&self) -> usize {
+        N + M
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    f([1, 2]).await;
+    g::<u32, 2>().await;
+    Buffer::<1>.len::<2>().await;
+}
@@ -0,0 +1,39 @@
+use minitrace::trace;
+
+struct MyStruct<'a> {
+    prefix: &'a str,
+}
+
+impl<'a> MyStruct<'a> {
+    #[trace]
+    async fn matches<F>(&self, f: F) -> bool
+    where
+        F: for<'b> Fn(&'b str) -> bool,
+    {
+        tokio::task::yield_now().await;
+        f(self.prefix)
+    }
+
+    #[trace(enter_on_poll = true)]
+    async fn matches_all<'s, F, I>(&'s self, items: I, f: F) -> usize
+    where
+        Self: 's,
+        'a: 's,
+        F: for<'b> Fn(&'b str, &'s str) -> bool + Send,
+        I: IntoIterator,
+        I::Item: AsRef<str>,
+        Vec<I::Item>: Send,
+    {
+        let items = items.into_iter().collect::<Vec<_>>();
+        tokio::task::yield_now().await;
+        items.iter().filter(|item| f(item.as_ref(), self.prefix)).count()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let s = MyStruct { prefix: "a" };
+    s.matches(|s| s.is_empty()).await;
+    s.matches_all(["a", "b"], |item, prefix| item.starts_with(prefix))
+        .await;
+}
@@ -0,0 +1,51 @@
+use minitrace::trace;
+
+struct Counter {
+    values: Vec<u32>,
+}
+
+impl Counter {
+    #[trace]
+    async fn push(&mut self, value: u32) -> usize {
+        let values = &mut self.values;
+        tokio::task::yield_now().await;
+        values.push(value);
+        tokio::task::yield_now().await;
+        self.values.len()
+    }
+
+    #[trace(enter_on_poll = true)]
+    async fn push_on_poll(&mut self, value: u32) -> usize {
+        let values = &mut self.values;
+        tokio::task::yield_now().await;
+        values.push(value);
+        values.len()
+    }
+}
+
+#[async_trait::async_trait]
+trait Push {
+    async fn push_boxed(&mut self, value: u32) -> usize;
+}
+
+#[async_trait::async_trait]
+impl Push for Counter {
+    #[trace]
+    async fn push_boxed(&mut self, value: u32) -> usize {
+        let values = &mut self.values;
+        tokio::task::yield_now().await;
+        values.push(value);
+        values.len()
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut counter = Counter { values: Vec::new() };
+    counter.push(1).await;
+    counter.push_on_poll(2).await;
+    counter.push_boxed(3).await;
+
+    // The borrow is `Send`, so the future can be spawned.
+    tokio::spawn(async move { counter.push(4).await }).await.unwrap();
+}
@@ -0,0 +1,65 @@
+#[async_trait::async_trait]
+trait MyTrait {
+    async fn work(&self) -> usize
+    where
+        Self: Sized;
+
+    async fn consume(self) -> usize
+    where
+        Self: Sized + Send;
+}
+
+struct MyStruct;
+
+#[async_trait::async_trait]
+impl MyTrait for MyStruct {
+    #[minitrace::trace]
+    async fn work(&self) -> usize
+    where
+        Self: Sized,
+    {
+        1
+    }
+
+    #[minitrace::trace(short_name = true)]
+    async fn consume(self) -> usize
+    where
+        Self: Sized + Send,
+    {
+        2
+    }
+}
+
+trait NativeTrait {
+    #[allow(async_fn_in_trait)]
+    async fn work(&self) -> usize
+    where
+        Self: Sized;
+}
+
+impl NativeTrait for MyStruct {
+    #[minitrace::trace]
+    async fn work(&self) -> usize
+    where
+        Self: Sized,
+    {
+        3
+    }
+}
+
+impl MyStruct {
+    #[minitrace::trace]
+    async fn inherent(&self) -> usize
+    where
+        Self: Sized,
+    {
+        4
+    }
+}
+
+fn main() {
+    let _ = MyTrait::work(&MyStruct);
+    let _ = MyStruct.consume();
+    let _ = NativeTrait::work(&MyStruct);
+    let _ = MyStruct.inherent();
+}
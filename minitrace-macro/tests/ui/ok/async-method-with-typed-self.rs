@@ -0,0 +1,76 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::Context;
+use std::task::Poll;
+
+use minitrace::trace;
+
+struct MyStruct {
+    value: usize,
+}
+
+impl MyStruct {
+    #[trace]
+    async fn pinned(self: Pin<&mut Self>) -> usize {
+        let this = self.get_mut();
+        this.value += 1;
+        tokio::task::yield_now().await;
+        this.value
+    }
+
+    #[trace(enter_on_poll = true)]
+    async fn shared(self: Arc<Self>) -> usize {
+        tokio::task::yield_now().await;
+        self.value
+    }
+
+    #[trace(short_name = true)]
+    async fn boxed(self: Box<Self>) -> usize {
+        tokio::task::yield_now().await;
+        self.value
+    }
+}
+
+#[async_trait::async_trait]
+trait MyTrait {
+    async fn shared(self: Arc<Self>) -> usize;
+}
+
+#[async_trait::async_trait]
+impl MyTrait for MyStruct {
+    #[trace]
+    async fn shared(self: Arc<Self>) -> usize {
+        tokio::task::yield_now().await;
+        self.value
+    }
+}
+
+struct Ready(usize);
+
+impl Future for Ready {
+    type Output = usize;
+
+    #[trace]
+    fn poll(self: Pin<&mut Self>, _cx: &mut Context<'_>) -> Poll<usize> {
+        Poll::Ready(self.0)
+    }
+}
+
+#[tokio::main]
+async fn main() {
+    let mut s = MyStruct { value: 1 };
+    Pin::new(&mut s).pinned().await;
+    MyStruct::shared(Arc::new(MyStruct { value: 2 })).await;
+    Box::new(MyStruct { value: 3 }).boxed().await;
+    MyTrait::shared(Arc::new(MyStruct { value: 4 })).await;
+    Ready(5).await;
+
+    // The futures are `Send`, so they can be spawned.
+    tokio::spawn(MyStruct::shared(Arc::new(MyStruct { value: 6 })))
+        .await
+        .unwrap();
+    tokio::spawn(Box::new(MyStruct { value: 7 }).boxed())
+        .await
+        .unwrap();
+}
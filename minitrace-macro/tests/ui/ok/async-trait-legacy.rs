@@ -0,0 +1,24 @@
+use std::future::Future;
+use std::pin::Pin;
+
+// The shape generated by async-trait (<=0.1.43), which moves the body into an inner async fn.
+
+trait Work {
+    fn work(&self, value: usize) -> Pin<Box<dyn Future<Output = usize> + Send + '_>>;
+}
+
+struct Worker;
+
+impl Work for Worker {
+    #[minitrace::trace(short_name = true)]
+    fn work(&self, value: usize) -> Pin<Box<dyn Future<Output = usize> + Send + '_>> {
+        async fn __work(_self: &Worker, value: usize) -> usize {
+            value
+        }
+        Box::pin(__work(self, value))
+    }
+}
+
+fn main() {
+    drop(Worker.work(1));
+}
@@ -0,0 +1,41 @@
+#![deny(unused_variables)]
+
+use std::future::Future;
+use std::pin::Pin;
+
+// The shape generated by async-trait (>=0.1.44) when the async block carries attributes.
+
+#[minitrace::trace]
+fn work() -> Pin<Box<dyn Future<Output = usize> + Send>> {
+    Box::pin(
+        #[allow(unused_variables)]
+        async move {
+            let unused = 1;
+            42
+        },
+    )
+}
+
+#[minitrace::trace(enter_on_poll = true)]
+fn work_on_poll() -> Pin<Box<dyn Future<Output = usize> + Send>> {
+    Box::pin(
+        #[allow(unused_variables)]
+        async move {
+            let unused = 1;
+            42
+        },
+    )
+}
+
+#[minitrace::trace(sample = 0.5, lazy = true)]
+fn work_sampled() -> Pin<Box<dyn Future<Output = usize> + Send>> {
+    Box::pin(
+        #[allow(unused_variables)]
+        async move {
+            let unused = 1;
+            42
+        },
+    )
+}
+
+fn main() {}
@@ -0,0 +1,30 @@
+use std::future::Future;
+
+async fn inner(a: u32) -> u32 {
+    a
+}
+
+#[minitrace::trace(enter_on_poll = true)]
+fn f(a: u32) -> impl Future<Output = u32> + Send {
+    inner(a)
+}
+
+#[minitrace::trace(lazy = true, name = "g({a})")]
+fn g(a: u32) -> impl Future<Output = u32> {
+    if a == 0 {
+        return inner(1);
+    }
+    inner(a)
+}
+
+#[minitrace::trace(enter_on_poll = true)]
+fn h(a: &str) -> impl Future<Output = usize> + '_ {
+    std::future::ready(a.len())
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).await;
+    g(1).await;
+    h("a").await;
+}
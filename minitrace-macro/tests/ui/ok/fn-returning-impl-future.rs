@@ -0,0 +1,25 @@
+use std::future::Future;
+
+#[minitrace::trace]
+fn f(a: u32) -> impl Future<Output = u32> + Send {
+    let b = a + 1;
+    async move { b }
+}
+
+#[minitrace::trace(enter_on_poll = true)]
+fn g<'a>(a: &'a str) -> impl std::future::Future<Output = usize> + 'a {
+    async move { a.len() }
+}
+
+// Traces the creation of the future only.
+#[minitrace::trace(async_trait = false)]
+fn h() -> impl Future<Output = ()> {
+    async move {}
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).await;
+    g("a").await;
+    h().await;
+}
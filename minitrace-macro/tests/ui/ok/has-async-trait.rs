@@ -0,0 +1,41 @@
+use std::future::Future;
+use std::pin::Pin;
+
+trait Work {
+    fn work(&self) -> Pin<Box<dyn Future<Output = usize> + Send + '_>>;
+}
+
+struct Cached(usize);
+
+impl Work for Cached {
+    // Desugared by hand, with a statement before the future and a cast, which the detection of
+    // the shape generated by `async-trait` does not handle.
+    #[minitrace::trace(async_trait = true)]
+    fn work(&self) -> Pin<Box<dyn Future<Output = usize> + Send + '_>> {
+        let value = self.0;
+        Box::pin(async move { value }) as Pin<Box<dyn Future<Output = usize> + Send + '_>>
+    }
+}
+
+struct Boxed;
+
+impl Work for Boxed {
+    // Traces the creation of the future only.
+    #[minitrace::trace(async_trait = false)]
+    fn work(&self) -> Pin<Box<dyn Future<Output = usize> + Send + '_>> {
+        Box::pin(async move { 42 })
+    }
+}
+
+#[minitrace::trace(short_name = true, async_trait = true)]
+fn spawn() -> Pin<Box<dyn Future<Output = ()> + Send>> {
+    let unused = || Box::pin(async move {});
+    drop(unused);
+    Box::pin(async move {})
+}
+
+fn main() {
+    drop(Cached(1).work());
+    drop(Boxed.work());
+    drop(spawn());
+}
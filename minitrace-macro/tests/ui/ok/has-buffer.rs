@@ -0,0 +1,22 @@
+use minitrace::trace;
+
+#[trace(buffer = 4)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(buffer = 2, display = [a])]
+async fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace(parent_id = 42, buffer = 8)]
+fn h(a: u32) -> u32 {
+    a
+}
+
+fn main() {
+    f(1);
+    let _ = g(1);
+    h(1);
+}
@@ -0,0 +1,19 @@
+use minitrace::trace;
+
+#[trace(catch_panic = true)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(catch_panic = true, err = true)]
+fn g(a: &str) -> Result<&str, String> {
+    if a.is_empty() {
+        return Err("empty".to_string());
+    }
+    Ok(a)
+}
+
+fn main() {
+    f(1);
+    g("a").ok();
+}
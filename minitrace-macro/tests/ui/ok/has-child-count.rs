@@ -0,0 +1,18 @@
+use minitrace::trace;
+
+#[trace(child_count = true)]
+fn f(a: u32) -> u32 {
+    if a == 0 {
+        return a;
+    }
+    a
+}
+
+#[trace(child_count = true, sample = 0.5)]
+fn g(a: u32) -> u32 {
+    f(a)
+}
+
+fn main() {
+    g(1);
+}
@@ -0,0 +1,19 @@
+use minitrace::trace;
+
+#[trace(coalesce = true)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(coalesce = true, sample = 0.5, guard = span)]
+fn g(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+fn main() {
+    for i in 0..10 {
+        f(i);
+        g(i);
+    }
+}
@@ -0,0 +1,36 @@
+#![deny(unused_variables)]
+
+use minitrace::trace;
+
+#[trace(conventional = true)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(conventional = true)]
+fn g(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+#[trace(conventional = true)]
+async fn h(a: u32) -> u32 {
+    a
+}
+
+#[trace(conventional = false)]
+fn i() {}
+
+#[trace(conventional = false, name = "j", guard = span)]
+fn j() {
+    drop(span);
+}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    g(1);
+    h(1).await;
+    i();
+    j();
+}
@@ -0,0 +1,41 @@
+extern crate minitrace as tracing_lib;
+
+// Shadows the `minitrace` crate, so the expansion must only refer to `tracing_lib`.
+mod minitrace {}
+
+use tracing_lib::trace;
+
+#[trace(crate = tracing_lib)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(crate = tracing_lib, err = true)]
+fn g(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(crate = tracing_lib, display = [a])]
+async fn h(a: u32) -> u32 {
+    a
+}
+
+#[trace(crate = ::tracing_lib, sample = 0.5)]
+async fn i(a: u32) -> u32 {
+    a
+}
+
+#[trace(crate = tracing_lib, enter_on_poll = true)]
+async fn j(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+#[tracing_lib::main(crate = tracing_lib)]
+async fn main() {
+    f(1);
+    g(1).ok();
+    h(1).await;
+    i(1).await;
+    j(1).await;
+}
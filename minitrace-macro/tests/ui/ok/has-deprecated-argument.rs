@@ -0,0 +1,17 @@
+use minitrace::trace;
+
+#[trace(catch_err = true)]
+fn f(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(short_name = true, catch_err = true)]
+async fn g(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).ok();
+    g(1).await.ok();
+}
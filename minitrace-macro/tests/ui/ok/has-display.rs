@@ -0,0 +1,39 @@
+use minitrace::trace;
+
+struct Foo(u32);
+
+impl Foo {
+    #[trace(display = [self, a])]
+    fn f(&self, a: u32, b: Vec<u8>) -> usize {
+        b.len()
+    }
+}
+
+impl std::fmt::Display for Foo {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Foo({})", self.0)
+    }
+}
+
+#[trace(display = [a, b])]
+async fn g(a: String, b: u32) -> String {
+    a
+}
+
+#[trace(enter_on_poll = true, display = [a])]
+async fn h(a: String) -> String {
+    a
+}
+
+#[trace(lazy = true, display = [a])]
+async fn i(a: String) -> String {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    Foo(1).f(1, vec![]);
+    g("a".to_string(), 1).await;
+    h("a".to_string()).await;
+    i("a".to_string()).await;
+}
@@ -0,0 +1,36 @@
+use minitrace::trace;
+
+#[derive(Debug)]
+struct Error;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error")
+    }
+}
+
+fn parse(a: u32) -> Result<u32, Error> {
+    if a > 0 {
+        Ok(a)
+    } else {
+        Err(Error)
+    }
+}
+
+#[trace(err = true)]
+fn f(a: u32) -> Result<u32, Error> {
+    let a = parse(a)?;
+    Ok(a)
+}
+
+#[trace(err = true)]
+async fn g(a: u32) -> Result<u32, Error> {
+    let a = parse(a)?;
+    Ok(a)
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).ok();
+    g(1).await.ok();
+}
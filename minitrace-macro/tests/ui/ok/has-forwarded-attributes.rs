@@ -0,0 +1,42 @@
+#![deny(unused_variables, dead_code)]
+
+use minitrace::trace;
+
+#[inline(always)]
+#[trace]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace]
+#[inline]
+#[cold]
+#[must_use]
+fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace]
+#[allow(unused_variables)]
+fn h(a: u32) {
+    let b = a;
+}
+
+#[trace]
+#[cfg_attr(not(test), allow(dead_code))]
+fn never_called() {}
+
+#[trace]
+#[doc = "Documented."]
+#[inline]
+async fn i(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    let _ = g(1);
+    h(1);
+    i(1).await;
+}
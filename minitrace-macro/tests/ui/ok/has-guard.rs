@@ -0,0 +1,18 @@
+use minitrace::trace;
+
+#[trace(guard = span)]
+fn f(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+#[trace(parent_id = 42, guard = parent)]
+fn g(a: u32) -> u32 {
+    drop(parent);
+    a
+}
+
+fn main() {
+    f(1);
+    g(1);
+}
@@ -0,0 +1,31 @@
+use minitrace::trace;
+
+#[trace]
+fn f<'a>(a: impl AsRef<str> + 'a) -> usize {
+    a.as_ref().len()
+}
+
+#[trace]
+async fn g<'a>(a: impl AsRef<str> + 'a) -> usize {
+    a.as_ref().len()
+}
+
+#[trace]
+async fn h<'a, 'b>(a: &'a str, b: impl AsRef<str> + Send + 'b) -> &'a str {
+    let _ = b.as_ref();
+    a
+}
+
+#[trace(err = true)]
+async fn i<'a>(a: impl AsRef<str> + 'a) -> Result<usize, String> {
+    Ok(a.as_ref().len())
+}
+
+#[tokio::main]
+async fn main() {
+    let s = String::from("test");
+    f(&s);
+    g(&s).await;
+    h("test", &s).await;
+    i(s.as_str()).await.ok();
+}
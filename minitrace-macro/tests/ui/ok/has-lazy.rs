@@ -0,0 +1,11 @@
+use minitrace::trace;
+
+#[trace(lazy = true)]
+async fn f(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).await;
+}
@@ -0,0 +1,30 @@
+use minitrace::trace;
+
+#[trace(
+    short_name = true,
+    display = [a],
+    variables = [b],
+    err = true,
+    sample = 0.5,
+    buffer = 4,
+    guard = span,
+    catch_panic = true
+)]
+fn f(a: u32, b: &str) -> Result<u32, String> {
+    drop(span);
+    if b.is_empty() {
+        return Err("empty".to_string());
+    }
+    Ok(a)
+}
+
+#[trace(name = "g", enter_on_poll = true, display = [a], err = true, type_params = true)]
+async fn g<T: std::fmt::Debug>(a: u32, _t: T) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[tokio::main]
+async fn main() {
+    f(1, "b").ok();
+    g(1, ()).await.ok();
+}
@@ -0,0 +1,16 @@
+use minitrace::trace;
+
+#[trace(min_duration = "1ms")]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(short_name = true, min_duration = "100us")]
+fn g(a: u32) -> u32 {
+    a
+}
+
+fn main() {
+    f(1);
+    g(1);
+}
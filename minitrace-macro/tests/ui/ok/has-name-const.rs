@@ -0,0 +1,37 @@
+use minitrace::trace;
+
+const NAME: &str = "f";
+
+#[trace(name_const = NAME)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+trait Operation {
+    const SPAN_NAME: &'static str;
+
+    #[trace(name_const = Self::SPAN_NAME)]
+    fn run(&self) {}
+}
+
+struct Get;
+
+impl Operation for Get {
+    const SPAN_NAME: &'static str = "get";
+}
+
+struct Put;
+
+impl Put {
+    const SPAN_NAME: &'static str = "put";
+
+    #[trace(name_const = Self::SPAN_NAME, enter_on_poll = true)]
+    async fn run(&self) {}
+}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    Get.run();
+    Put.run().await;
+}
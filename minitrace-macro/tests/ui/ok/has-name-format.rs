@@ -0,0 +1,29 @@
+#![deny(unused_variables)]
+
+use minitrace::trace;
+
+#[trace(name = "f({a}, {b:?})")]
+fn f(a: u32, b: &str) {}
+
+#[trace(name = "g({a})")]
+async fn g(a: String) -> String {
+    a
+}
+
+#[trace(name = "h({{a}})")]
+fn h(a: u32) -> u32 {
+    a
+}
+
+#[trace(name = "i({a}}}")]
+fn i(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f(1, "b");
+    g("a".to_string()).await;
+    h(1);
+    i(1);
+}
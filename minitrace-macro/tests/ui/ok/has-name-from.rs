@@ -0,0 +1,15 @@
+use minitrace::trace;
+
+#[trace(name_from = state)]
+fn f(state: &str) {}
+
+#[trace(name_from = state)]
+async fn g(state: String) -> String {
+    state
+}
+
+#[tokio::main]
+async fn main() {
+    f("a");
+    g("a".to_string()).await;
+}
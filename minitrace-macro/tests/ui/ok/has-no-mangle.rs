@@ -0,0 +1,13 @@
+#[minitrace::trace]
+#[no_mangle]
+pub extern "C" fn f(a: u32) -> u32 {
+    a
+}
+
+#[no_mangle]
+#[minitrace::trace]
+pub extern "C" fn g(a: u32) -> u32 {
+    a
+}
+
+fn main() {}
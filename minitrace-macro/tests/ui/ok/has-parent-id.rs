@@ -0,0 +1,14 @@
+struct Header {
+    span_id: u64,
+}
+
+#[minitrace::trace(parent_id = header.span_id)]
+fn f(header: &Header) {}
+
+#[minitrace::trace(parent_id = 42, sample = 0.5)]
+async fn g() {}
+
+fn main() {
+    f(&Header { span_id: 42 });
+    let _ = g();
+}
@@ -0,0 +1,29 @@
+use minitrace::prelude::*;
+
+#[trace(parent = "request")]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(parent = "request", guard = span, display = [a])]
+fn g(a: u32) -> u32 {
+    drop(span);
+    a
+}
+
+#[trace(parent = "request")]
+async fn h(a: u32) -> u32 {
+    a
+}
+
+fn main() {
+    // Without a local parent, no span is recorded.
+    f(1);
+
+    let root = Span::root("request", SpanContext::random());
+    let _g = root.set_local_parent();
+    let _local = LocalSpan::enter_with_local_parent("handler");
+    f(1);
+    g(1);
+    let _ = h(1);
+}
@@ -0,0 +1,21 @@
+use minitrace::trace;
+
+async fn inner(a: u32) -> u32 {
+    a
+}
+
+#[trace(passthrough = true)]
+async fn f(a: u32) -> u32 {
+    inner(a).await
+}
+
+#[trace(passthrough = true, display = [a], sample = 0.5)]
+async fn g(a: u32) -> u32 {
+    inner(a).await
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).await;
+    g(1).await;
+}
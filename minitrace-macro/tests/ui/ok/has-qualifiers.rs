@@ -0,0 +1,39 @@
+use minitrace::trace;
+
+#[trace]
+async unsafe fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace]
+unsafe extern "C" fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace]
+extern "C" fn h(a: u32) -> u32 {
+    a
+}
+
+#[trace(short_name = true)]
+const fn j(a: u32) -> u32 {
+    a
+}
+
+#[trace(err = true)]
+const unsafe fn k(a: u32) -> u32 {
+    a
+}
+
+const J: u32 = j(1);
+const K: u32 = unsafe { k(1) };
+
+#[tokio::main]
+async fn main() {
+    unsafe {
+        f(1).await;
+        g(1);
+    }
+    h(1);
+    assert_eq!(J + K, 2);
+}
@@ -0,0 +1,35 @@
+use minitrace::trace;
+
+#[trace(record_return = true)]
+fn f(a: u32) -> u32 {
+    if a == 0 {
+        return a;
+    }
+    a
+}
+
+#[trace(record_return = true)]
+fn g(a: &str) -> &str {
+    a
+}
+
+#[trace(record_return = true, err = true)]
+async fn h(a: u32) -> Result<u32, String> {
+    if a == 0 {
+        return Err("zero".to_string());
+    }
+    Ok(a)
+}
+
+#[trace(record_return = true, enter_on_poll = true)]
+async fn i(a: u32) {
+    let _ = a;
+}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    g("a");
+    h(1).await.ok();
+    i(1).await;
+}
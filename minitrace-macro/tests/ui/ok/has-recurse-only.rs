@@ -0,0 +1,35 @@
+use minitrace::trace;
+
+#[trace(recurse = true, recurse_only = traced)]
+fn f(a: u32) -> u32 {
+    #[traced]
+    fn g(a: u32) -> u32 {
+        a
+    }
+
+    fn h(a: u32) -> u32 {
+        a
+    }
+
+    #[traced]
+    const fn i(a: u32) -> u32 {
+        a
+    }
+
+    g(h(i(a)))
+}
+
+#[trace(recurse = true, recurse_only = traced)]
+const fn j(a: u32) -> u32 {
+    #[traced]
+    const fn k(a: u32) -> u32 {
+        a
+    }
+
+    k(a)
+}
+
+fn main() {
+    f(1);
+    j(1);
+}
@@ -0,0 +1,29 @@
+use minitrace::trace;
+
+#[trace(recurse = true)]
+fn f(a: u32) -> u32 {
+    fn g(a: u32) -> u32 {
+        a
+    }
+
+    async fn h(a: u32) -> u32 {
+        a
+    }
+
+    let _ = h(a);
+    g(a)
+}
+
+#[trace(recurse = true)]
+async fn i(a: u32) -> u32 {
+    fn j(a: u32) -> u32 {
+        a
+    }
+
+    j(a)
+}
+
+fn main() {
+    f(1);
+    let _ = i(1);
+}
@@ -0,0 +1,18 @@
+use minitrace::trace;
+
+fn mask(value: &str) -> String {
+    format!("{}***", &value[..1])
+}
+
+#[trace(display = [user, token], variables = [card], redact = [token, card = mask])]
+fn f(user: &str, token: &str, card: u64) -> usize {
+    token.len()
+}
+
+#[trace(display = [token], redact = [token = |token: &str| token.len().to_string()])]
+async fn g(token: String) {}
+
+fn main() {
+    f("alice", "secret", 4111111111111111);
+    let _ = g("secret".to_string());
+}
@@ -0,0 +1,23 @@
+use minitrace::trace;
+
+#[trace(root = true)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(root = true, guard = span)]
+fn g() {
+    drop(span);
+}
+
+#[trace(short_name = true, root = true)]
+async fn h(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    g();
+    h(1).await;
+}
@@ -0,0 +1,41 @@
+use minitrace::trace;
+
+#[trace(sample = 0.5)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(sample = 0.5, display = [a])]
+async fn g(a: u32) -> u32 {
+    a
+}
+
+#[trace(sample = 0.5, enter_on_poll = true)]
+async fn h(a: u32) -> u32 {
+    a
+}
+
+#[trace(sample = 0.5, lazy = true)]
+async fn i(a: u32) -> u32 {
+    a
+}
+
+#[trace(sample = 0.5, err = true)]
+async fn j(a: u32) -> Result<u32, String> {
+    Ok(a)
+}
+
+#[trace(sample = 0.5, min_duration = "1ms")]
+fn k(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f(1);
+    g(1).await;
+    h(1).await;
+    i(1).await;
+    j(1).await.ok();
+    k(1);
+}
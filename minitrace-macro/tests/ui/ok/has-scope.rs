@@ -0,0 +1,27 @@
+use minitrace::prelude::*;
+
+#[trace(scope = Scope::Local)]
+fn f(a: u32) -> u32 {
+    a
+}
+
+#[trace(scope = Scope::Threads, sample = 0.5, guard = span)]
+fn g(a: u32) -> u32 {
+    std::thread::scope(|scope| {
+        scope.spawn(|| {
+            let _child = Span::enter_with_parent("child", &span);
+        });
+    });
+    a
+}
+
+#[trace(scope = minitrace::Scope::Threads)]
+async fn h(a: u32) -> u32 {
+    a
+}
+
+fn main() {
+    f(1);
+    g(1);
+    let _ = h(1);
+}
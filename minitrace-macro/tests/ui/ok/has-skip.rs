@@ -0,0 +1,17 @@
+use minitrace::trace;
+
+#[trace(variables = [user], skip = [password, raw_bytes])]
+fn login(user: &str, password: &str, raw_bytes: &[u8]) -> bool {
+    !user.is_empty() && !password.is_empty() && !raw_bytes.is_empty()
+}
+
+#[trace(skip = [password])]
+async fn login_async(user: &str, password: &str) -> bool {
+    !user.is_empty() && !password.is_empty()
+}
+
+#[tokio::main]
+async fn main() {
+    login("user", "secret", b"bytes");
+    login_async("user", "secret").await;
+}
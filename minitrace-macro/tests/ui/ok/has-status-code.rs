@@ -0,0 +1,44 @@
+use minitrace::trace;
+
+#[derive(Debug)]
+struct Error;
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "error")
+    }
+}
+
+const INVALID_ARGUMENT: u32 = 3;
+
+fn parse(a: u32) -> Result<u32, Error> {
+    if a > 0 {
+        Ok(a)
+    } else {
+        Err(Error)
+    }
+}
+
+#[trace(ok_code = 0)]
+fn f(a: u32) -> Result<u32, ()> {
+    parse(a).map_err(|_| ())
+}
+
+#[trace(err = true, err_code = INVALID_ARGUMENT)]
+fn g(a: u32) -> Result<u32, Error> {
+    let a = parse(a)?;
+    Ok(a)
+}
+
+#[trace(ok_code = "OK", err_code = "INVALID_ARGUMENT")]
+async fn h(a: u32) -> Result<u32, Error> {
+    let a = parse(a)?;
+    Ok(a)
+}
+
+#[tokio::main]
+async fn main() {
+    f(1).ok();
+    g(1).ok();
+    h(1).await.ok();
+}
@@ -0,0 +1,16 @@
+use minitrace::trace;
+
+#[trace]
+#[test]
+fn f() {}
+
+#[trace(short_name = true)]
+#[tokio::test]
+async fn g() {}
+
+#[trace(test = true)]
+fn h() {}
+
+fn main() {
+    h();
+}
@@ -0,0 +1,17 @@
+use minitrace::trace;
+
+#[trace(type_params = true)]
+fn f<T, const N: usize>(a: [T; N]) -> [T; N] {
+    a
+}
+
+#[trace(type_params = true, display = [a])]
+async fn g<T: std::fmt::Display + Send>(a: T) -> T {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    f([1u8; 4]);
+    g("a").await;
+}
@@ -0,0 +1,28 @@
+use minitrace::trace;
+
+#[derive(Debug)]
+struct Foo(u32);
+
+impl Foo {
+    #[trace(variables = [self, b])]
+    fn f(&self, a: u32, b: Vec<u8>) -> usize {
+        b.len()
+    }
+}
+
+#[trace(variables = [a, b])]
+async fn g(a: String, b: u32) -> String {
+    a
+}
+
+#[trace(lazy = true, variables = [a])]
+async fn h(a: String) -> String {
+    a
+}
+
+#[tokio::main]
+async fn main() {
+    Foo(1).f(1, vec![]);
+    g("a".to_string(), 1).await;
+    h("a".to_string()).await;
+}
@@ -0,0 +1,12 @@
+use minitrace::trace;
+
+#[trace]
+async fn f(a: u32) -> u32 {
+    a
+}
+
+#[tokio::main]
+#[minitrace::main]
+async fn main() {
+    f(1).await;
+}
@@ -0,0 +1,13 @@
+use minitrace::trace;
+
+#[trace]
+fn f(a: u32) -> Result<u32, std::num::ParseIntError> {
+    Ok(a)
+}
+
+#[minitrace::main]
+fn main() -> Result<(), std::num::ParseIntError> {
+    let a = "1".parse()?;
+    f(a)?;
+    Ok(())
+}
@@ -8,6 +8,7 @@ use std::time::UNIX_EPOCH;
 
 use minitrace::collector::EventRecord;
 use minitrace::collector::Reporter;
+use minitrace::collector::SpanStatus;
 use minitrace::prelude::*;
 use opentelemetry::sdk::export::trace::SpanData;
 use opentelemetry::sdk::export::trace::SpanExporter;
@@ -30,6 +31,9 @@ use opentelemetry::Value;
 ///
 /// `OpenTelemetryReporter` exports trace records to remote agents that OpenTelemetry
 /// supports, which includes Jaeger, Datadog, Zipkin, and OpenTelemetry Collector.
+///
+/// The attributes set by [`minitrace::set_resource()`] are added to the resource of the exported
+/// spans. The attributes of the resource given to the reporter take priority.
 pub struct OpenTelemetryReporter {
     opentelemetry_exporter: Box<dyn SpanExporter>,
     span_kind: SpanKind,
@@ -53,6 +57,7 @@ impl OpenTelemetryReporter {
     }
 
     fn convert(&self, spans: &[SpanRecord]) -> Vec<SpanData> {
+        let resource = self.resource();
         spans
             .iter()
             .map(move |span| SpanData {
@@ -71,14 +76,34 @@ impl OpenTelemetryReporter {
                 attributes: Self::convert_properties(&span.properties),
                 events: Self::convert_events(&span.events),
                 links: EvictedQueue::new(0),
-                status: Status::default(),
+                status: Self::convert_status(span.status),
                 span_kind: self.span_kind.clone(),
-                resource: self.resource.clone(),
+                resource: resource.clone(),
                 instrumentation_lib: self.instrumentation_lib.clone(),
             })
             .collect()
     }
 
+    fn resource(&self) -> Cow<'static, Resource> {
+        let attributes = minitrace::collector::resource();
+        if attributes.is_empty() {
+            return self.resource.clone();
+        }
+
+        let resource = Resource::new(attributes.iter().map(|(k, v)| {
+            KeyValue::new(cow_to_otel_key(k.clone()), cow_to_otel_value(v.clone()))
+        }));
+        Cow::Owned(resource.merge(self.resource.as_ref()))
+    }
+
+    fn convert_status(status: SpanStatus) -> Status {
+        match status {
+            SpanStatus::Unset => Status::Unset,
+            SpanStatus::Ok => Status::Ok,
+            SpanStatus::Error => Status::error(""),
+        }
+    }
+
     fn convert_properties(properties: &[(Cow<'static, str>, Cow<'static, str>)]) -> EvictedHashMap {
         let mut map = EvictedHashMap::new(u32::MAX, properties.len());
         for (k, v) in properties {
@@ -5,6 +5,7 @@ use criterion::criterion_group;
 use criterion::criterion_main;
 use criterion::Criterion;
 use minitrace::local::LocalCollector;
+use minitrace::local::LocalSpanBatch;
 use minitrace::prelude::*;
 
 fn init_minitrace() {
@@ -68,6 +69,25 @@ fn bench_trace_wide(c: &mut Criterion) {
     minitrace::flush()
 }
 
+fn bench_trace_wide_unsampled(c: &mut Criterion) {
+    init_minitrace();
+
+    let mut group = c.benchmark_group("trace_wide_unsampled");
+
+    for len in &[1, 10, 100, 1000, 10000] {
+        group.bench_function(len.to_string(), |b| {
+            b.iter(|| {
+                let root = Span::noop();
+                let _sg = root.set_local_parent();
+                dummy_iter(*len - 1);
+            })
+        });
+    }
+
+    group.finish();
+    minitrace::flush()
+}
+
 fn bench_trace_deep_raw(c: &mut Criterion) {
     let mut group = c.benchmark_group("trace_deep_raw");
 
@@ -128,12 +148,72 @@ fn bench_trace_future(c: &mut Criterion) {
     minitrace::flush()
 }
 
+fn bench_trace_wide_concurrent(c: &mut Criterion) {
+    init_minitrace();
+
+    let mut group = c.benchmark_group("trace_wide_concurrent");
+
+    for threads in &[1, 2, 4, 8] {
+        group.bench_function(threads.to_string(), |b| {
+            b.iter(|| {
+                std::thread::scope(|scope| {
+                    for _ in 0..*threads {
+                        scope.spawn(|| {
+                            let root =
+                                Span::root("root", SpanContext::new(TraceId(12), SpanId::default()));
+                            let _sg = root.set_local_parent();
+                            dummy_iter(1000);
+                        });
+                    }
+                })
+            })
+        });
+    }
+
+    group.finish();
+    minitrace::flush()
+}
+
+fn bench_trace_batch(c: &mut Criterion) {
+    let mut group = c.benchmark_group("trace_batch");
+
+    for len in &[1, 10, 100, 1000, 10000] {
+        group.bench_function(format!("per_span/{}", len), |b| {
+            b.iter(|| {
+                let local_collector = LocalCollector::start();
+                for _ in 0..*len {
+                    let _g = LocalSpan::enter_with_local_parent("span");
+                }
+                local_collector.collect()
+            })
+        });
+        group.bench_function(format!("batch/{}", len), |b| {
+            b.iter(|| {
+                let local_collector = LocalCollector::start();
+                {
+                    let mut batch = LocalSpanBatch::with_capacity(*len);
+                    for _ in 0..*len {
+                        let _g = batch.enter("span");
+                    }
+                }
+                local_collector.collect()
+            })
+        });
+    }
+
+    group.finish();
+    minitrace::flush();
+}
+
 criterion_group!(
     benches,
     bench_trace_wide_raw,
     bench_trace_wide,
+    bench_trace_wide_unsampled,
     bench_trace_deep_raw,
     bench_trace_deep,
-    bench_trace_future
+    bench_trace_future,
+    bench_trace_wide_concurrent,
+    bench_trace_batch
 );
 criterion_main!(benches);
@@ -0,0 +1,115 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::fmt::Write;
+
+use crate::collector::SpanRecord;
+
+/// Formats span records as a JSON trace in the
+/// [Trace Event Format](https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU),
+/// which can be opened in `chrome://tracing` or [Perfetto](https://ui.perfetto.dev).
+///
+/// Each span becomes a complete event (`"ph": "X"`) with the fields `name`, `ph`, `ts`, `dur`,
+/// `pid`, `tid` and `args`. `ts` and `dur` are in microseconds, with the nanoseconds kept as the
+/// fraction. `tid` is the [`thread_id`] of the span, `pid` is the id of the current process, and
+/// the properties of the span are listed in `args`.
+///
+/// This function is only available with the `chrome-trace` feature.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, records) = TestReporter::new();
+/// minitrace::set_reporter(reporter, Config::default());
+///
+/// drop(Span::root("root", SpanContext::random()));
+/// minitrace::flush();
+///
+/// let trace = minitrace::collector::to_chrome_trace(&records.lock());
+/// assert!(trace.starts_with("[{\"name\":\"root\",\"ph\":\"X\""));
+/// ```
+///
+/// [`thread_id`]: SpanRecord::thread_id
+pub fn to_chrome_trace(records: &[SpanRecord]) -> String {
+    let pid = std::process::id();
+    let mut trace = String::from("[");
+
+    for (i, record) in records.iter().enumerate() {
+        if i > 0 {
+            trace.push(',');
+        }
+
+        write!(
+            trace,
+            "{{\"name\":{},\"ph\":\"X\",\"ts\":{},\"dur\":{},\"pid\":{pid},\"tid\":{},\"args\":{{",
+            escape(&record.name),
+            micros(record.begin_time_unix_ns),
+            micros(record.duration_ns),
+            record.thread_id,
+        )
+        .unwrap();
+        for (j, (k, v)) in record.properties.iter().enumerate() {
+            if j > 0 {
+                trace.push(',');
+            }
+            write!(trace, "{}:{}", escape(k), escape(v)).unwrap();
+        }
+        trace.push_str("}}");
+    }
+
+    trace.push(']');
+    trace
+}
+
+/// Formats nanoseconds as microseconds, keeping the nanoseconds as the fraction.
+fn micros(ns: u64) -> String {
+    match ns % 1000 {
+        0 => format!("{}", ns / 1000),
+        frac => format!("{}.{:03}", ns / 1000, frac)
+            .trim_end_matches('0')
+            .to_string(),
+    }
+}
+
+fn escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len() + 2);
+    escaped.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => write!(escaped, "\\u{:04x}", c as u32).unwrap(),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_string() {
+        assert_eq!(escape("plain"), "\"plain\"");
+        assert_eq!(escape("say \"hi\""), "\"say \\\"hi\\\"\"");
+        assert_eq!(escape("a\\b"), "\"a\\\\b\"");
+        assert_eq!(escape("line\nbreak"), "\"line\\nbreak\"");
+        assert_eq!(escape("\u{1}"), "\"\\u0001\"");
+    }
+
+    #[test]
+    fn micros_fraction() {
+        assert_eq!(micros(0), "0");
+        assert_eq!(micros(2000), "2");
+        assert_eq!(micros(2500), "2.5");
+        assert_eq!(micros(2001), "2.001");
+    }
+}
@@ -1,5 +1,8 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::sync::mpsc::Sender;
+
+use crate::collector::SpanRecord;
 use crate::collector::SpanSet;
 use crate::util::CollectToken;
 
@@ -14,6 +17,8 @@ pub enum CollectCommand {
 #[derive(Debug)]
 pub struct StartCollect {
     pub collect_id: usize,
+    // If set, the records are streamed to it as spans are submitted rather than reported.
+    pub stream: Option<Sender<SpanRecord>>,
 }
 
 #[derive(Debug)]
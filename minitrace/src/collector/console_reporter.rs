@@ -12,4 +12,8 @@ impl Reporter for ConsoleReporter {
             eprintln!("{:#?}", span);
         }
     }
+
+    fn report_dropped(&mut self, count: usize) {
+        eprintln!("{count} spans dropped");
+    }
 }
@@ -0,0 +1,78 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::fmt::Write;
+
+use crate::collector::SpanRecord;
+
+/// Formats span records as CSV, one row per span, for analysis in a spreadsheet.
+///
+/// The columns are `id`, `parent_id`, `event`, `begin_ns`, `duration_ns` and `properties`.
+/// The properties of a span are flattened into a single column as `key=value` pairs separated
+/// by `;`. Fields containing a comma, a double quote or a line break are quoted as described in
+/// [RFC 4180](https://www.rfc-editor.org/rfc/rfc4180).
+///
+/// This function is only available with the `csv` feature.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, records) = TestReporter::new();
+/// minitrace::set_reporter(reporter, Config::default());
+///
+/// drop(Span::root("root", SpanContext::random()));
+/// minitrace::flush();
+///
+/// let csv = minitrace::collector::to_csv(&records.lock());
+/// assert!(csv.starts_with("id,parent_id,event,begin_ns,duration_ns,properties\n"));
+/// ```
+pub fn to_csv(records: &[SpanRecord]) -> String {
+    let mut csv = String::from("id,parent_id,event,begin_ns,duration_ns,properties\n");
+
+    for record in records {
+        let properties = record
+            .properties
+            .iter()
+            .map(|(k, v)| format!("{k}={v}"))
+            .collect::<Vec<_>>()
+            .join(";");
+
+        writeln!(
+            csv,
+            "{},{},{},{},{},{}",
+            record.span_id,
+            record.parent_id,
+            escape(&record.name),
+            record.begin_time_unix_ns,
+            record.duration_ns,
+            escape(&properties),
+        )
+        .unwrap();
+    }
+
+    csv
+}
+
+fn escape(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_field() {
+        assert_eq!(escape("plain"), "plain");
+        assert_eq!(escape("a,b"), "\"a,b\"");
+        assert_eq!(escape("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(escape("line\nbreak"), "\"line\nbreak\"");
+    }
+}
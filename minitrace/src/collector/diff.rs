@@ -0,0 +1,182 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::collector::span_tree::SpanTree;
+use crate::collector::SpanRecord;
+
+/// The differences between two sets of span records, as reported by [`diff()`].
+///
+/// A span is identified by its path, the names of its ancestors and its own name joined by `/`,
+/// e.g. `root/get`.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct TraceDiff {
+    /// The paths of the spans only found in `actual`.
+    pub added: Vec<String>,
+    /// The paths of the spans only found in `expected`.
+    pub removed: Vec<String>,
+    /// The paths of the spans in `expected` and `actual` which are at the same position in the
+    /// trees but have different names.
+    pub renamed: Vec<(String, String)>,
+}
+
+impl TraceDiff {
+    /// Returns `true` if the span records have the same structure and names.
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.renamed.is_empty()
+    }
+}
+
+/// Compares the span trees formed by two sets of span records, ignoring ids, timing,
+/// properties and events. Useful to check the effect of instrumentation changes in tests.
+///
+/// The children of a span are first matched by name. The remaining children of `expected` and
+/// `actual` are paired in order of their begin time as renamed spans, and the rest are reported
+/// as removed or added, along with all of their descendants.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::diff;
+/// use minitrace::collector::TraceDiff;
+/// use minitrace::prelude::*;
+///
+/// let expected = vec![SpanRecord {
+///     span_id: SpanId(1),
+///     name: "root".into(),
+///     ..Default::default()
+/// }];
+/// let actual = vec![SpanRecord {
+///     span_id: SpanId(2),
+///     name: "new_root".into(),
+///     ..Default::default()
+/// }];
+///
+/// assert_eq!(diff(&expected, &actual), TraceDiff {
+///     renamed: vec![("root".to_string(), "new_root".to_string())],
+///     ..Default::default()
+/// });
+/// ```
+pub fn diff(expected: &[SpanRecord], actual: &[SpanRecord]) -> TraceDiff {
+    let expected = SpanTree::new(expected);
+    let actual = SpanTree::new(actual);
+
+    let mut diff = TraceDiff::default();
+    diff_children(
+        &expected,
+        &actual,
+        &expected.roots,
+        &actual.roots,
+        "",
+        "",
+        &mut diff,
+    );
+    diff
+}
+
+impl SpanTree<'_> {
+    fn path(&self, parent_path: &str, i: usize) -> String {
+        if parent_path.is_empty() {
+            self.records[i].name.to_string()
+        } else {
+            format!("{}/{}", parent_path, self.records[i].name)
+        }
+    }
+
+    fn collect_paths(&self, parent_path: &str, i: usize, paths: &mut Vec<String>) {
+        let path = self.path(parent_path, i);
+        for &child in self.children(i) {
+            self.collect_paths(&path, child, paths);
+        }
+        paths.push(path);
+    }
+}
+
+fn diff_children(
+    expected: &SpanTree,
+    actual: &SpanTree,
+    expected_children: &[usize],
+    actual_children: &[usize],
+    expected_path: &str,
+    actual_path: &str,
+    diff: &mut TraceDiff,
+) {
+    let mut unmatched_actual = actual_children.iter().copied().map(Some).collect::<Vec<_>>();
+    let mut unmatched_expected = Vec::new();
+    let mut pairs = Vec::new();
+
+    for &e in expected_children {
+        let name = &expected.records[e].name;
+        let matched = unmatched_actual
+            .iter_mut()
+            .find(|a| matches!(a, Some(a) if actual.records[*a].name == *name))
+            .and_then(Option::take);
+        match matched {
+            Some(a) => pairs.push((e, a)),
+            None => unmatched_expected.push(e),
+        }
+    }
+
+    let mut unmatched_actual = unmatched_actual.into_iter().flatten();
+    for e in unmatched_expected {
+        match unmatched_actual.next() {
+            Some(a) => {
+                diff.renamed.push((
+                    expected.path(expected_path, e),
+                    actual.path(actual_path, a),
+                ));
+                pairs.push((e, a));
+            }
+            None => expected.collect_paths(expected_path, e, &mut diff.removed),
+        }
+    }
+    for a in unmatched_actual {
+        actual.collect_paths(actual_path, a, &mut diff.added);
+    }
+
+    for (e, a) in pairs {
+        diff_children(
+            expected,
+            actual,
+            expected.children(e),
+            actual.children(a),
+            &expected.path(expected_path, e),
+            &actual.path(actual_path, a),
+            diff,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+
+    fn record(name: &'static str, span_id: u64, parent_id: u64) -> SpanRecord {
+        SpanRecord {
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            begin_time_unix_ns: span_id,
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn diff_added_removed() {
+        let expected = [
+            record("root", 1, 0),
+            record("a", 2, 1),
+            record("b", 3, 2),
+        ];
+        let actual = [
+            record("root", 10, 0),
+            record("c", 11, 10),
+            record("d", 12, 10),
+        ];
+
+        assert_eq!(diff(&expected, &actual), TraceDiff {
+            added: vec!["root/d".to_string()],
+            removed: vec!["root/a/b".to_string()],
+            renamed: vec![("root/a".to_string(), "root/c".to_string())],
+        });
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::collector::to_folded_stacks;
+use crate::collector::SpanRecord;
+
+/// Renders span records as a flamegraph SVG with [inferno].
+///
+/// The flamegraph is rendered from the folded stacks of [`to_folded_stacks()`], so the width of
+/// a frame is the time spent in the span, in nanoseconds. If the records have no duration, the
+/// SVG shows an error message instead.
+///
+/// This function is only available with the `flamegraph` feature.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, records) = TestReporter::new();
+/// minitrace::set_reporter(reporter, Config::default());
+///
+/// {
+///     let root = Span::root("root", SpanContext::random());
+///     let _g = root.set_local_parent();
+///     let _span = LocalSpan::enter_with_local_parent("child");
+/// }
+/// minitrace::flush();
+///
+/// let svg = minitrace::collector::to_flamegraph_svg(&records.lock());
+/// std::fs::write("flamegraph.svg", svg).ok();
+/// # std::fs::remove_file("flamegraph.svg").ok();
+/// ```
+///
+/// [inferno]: https://github.com/jonhoo/inferno
+pub fn to_flamegraph_svg(records: &[SpanRecord]) -> String {
+    let folded = to_folded_stacks(records);
+    let mut options = inferno::flamegraph::Options::default();
+    options.count_name = "ns".to_string();
+
+    let mut svg = Vec::new();
+    // Writing into a `Vec` only fails when there is no stack with a positive count, in which case
+    // inferno still writes an SVG carrying the error message.
+    let _ = inferno::flamegraph::from_lines(&mut options, folded.lines(), &mut svg);
+    String::from_utf8(svg).expect("inferno writes UTF-8")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+
+    #[test]
+    fn no_records() {
+        let svg = to_flamegraph_svg(&[]);
+        assert!(svg.contains("<svg"));
+        assert!(svg.contains("ERROR: No valid input provided to flamegraph"));
+    }
+
+    #[test]
+    fn frames() {
+        let records = [
+            SpanRecord {
+                span_id: SpanId(1),
+                duration_ns: 3000,
+                name: "root".into(),
+                ..Default::default()
+            },
+            SpanRecord {
+                span_id: SpanId(2),
+                parent_id: SpanId(1),
+                duration_ns: 1000,
+                name: "child".into(),
+                ..Default::default()
+            },
+        ];
+
+        let svg = to_flamegraph_svg(&records);
+        assert!(svg.contains("root (3,000 ns, 100.00%)"), "{svg}");
+        assert!(svg.contains("child (1,000 ns, 33.33%)"), "{svg}");
+    }
+}
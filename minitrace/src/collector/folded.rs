@@ -0,0 +1,123 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+
+use crate::collector::SpanRecord;
+
+/// Formats span records as folded stacks, the input format of the [inferno] flamegraph tools,
+/// as written by `tracing-flame`.
+///
+/// Each line is a stack of span names from the root span to a span, separated by `;`, followed
+/// by the self time of the span in nanoseconds, i.e. its duration minus the durations of its
+/// child spans. The self times of identical stacks are summed up, and the lines are sorted.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, records) = TestReporter::new();
+/// minitrace::set_reporter(reporter, Config::default());
+///
+/// {
+///     let root = Span::root("root", SpanContext::random());
+///     let _g = root.set_local_parent();
+///     let _span = LocalSpan::enter_with_local_parent("child");
+/// }
+/// minitrace::flush();
+///
+/// // e.g. "root 1000\nroot;child 2000\n", to be piped into `inferno-flamegraph`.
+/// let folded = minitrace::collector::to_folded_stacks(&records.lock());
+/// assert_eq!(folded.lines().count(), 2);
+/// ```
+///
+/// [inferno]: https://github.com/jonhoo/inferno
+pub fn to_folded_stacks(records: &[SpanRecord]) -> String {
+    let index = records
+        .iter()
+        .enumerate()
+        .map(|(i, record)| ((record.trace_id, record.span_id), i))
+        .collect::<HashMap<_, _>>();
+    let parent = |record: &SpanRecord| {
+        if record.parent_id == record.span_id {
+            return None;
+        }
+        index.get(&(record.trace_id, record.parent_id)).copied()
+    };
+
+    let mut self_times = records
+        .iter()
+        .map(|record| record.duration_ns)
+        .collect::<Vec<_>>();
+    for record in records {
+        if let Some(parent) = parent(record) {
+            self_times[parent] = self_times[parent].saturating_sub(record.duration_ns);
+        }
+    }
+
+    let mut stacks = BTreeMap::<String, u64>::new();
+    for (record, self_time) in records.iter().zip(self_times) {
+        let mut names = vec![&*record.name];
+        let mut current = record;
+        while let Some(parent) = parent(current) {
+            // Guard against malformed records forming a cycle.
+            if names.len() > records.len() {
+                break;
+            }
+            current = &records[parent];
+            names.push(&current.name);
+        }
+        names.reverse();
+        *stacks.entry(names.join(";")).or_default() += self_time;
+    }
+
+    stacks
+        .into_iter()
+        .map(|(stack, self_time)| format!("{stack} {self_time}\n"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+
+    fn record(name: &'static str, span_id: u64, parent_id: u64, duration_ns: u64) -> SpanRecord {
+        SpanRecord {
+            span_id: SpanId(span_id),
+            parent_id: SpanId(parent_id),
+            duration_ns,
+            name: name.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn self_time() {
+        let records = [
+            record("root", 1, 0, 100),
+            record("a", 2, 1, 30),
+            record("b", 3, 1, 20),
+            record("c", 4, 3, 5),
+        ];
+
+        assert_eq!(
+            to_folded_stacks(&records),
+            "root 50\nroot;a 30\nroot;b 15\nroot;b;c 5\n"
+        );
+    }
+
+    #[test]
+    fn merge_identical_stacks() {
+        let records = [
+            record("root", 1, 0, 100),
+            record("a", 2, 1, 30),
+            record("a", 3, 1, 20),
+        ];
+
+        assert_eq!(to_folded_stacks(&records), "root 50\nroot;a 50\n");
+    }
+}
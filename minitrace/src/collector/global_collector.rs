@@ -1,10 +1,12 @@
 // Copyright 2021 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::borrow::Cow;
 use std::cell::UnsafeCell;
 use std::collections::HashMap;
 use std::sync::atomic::AtomicBool;
 use std::sync::atomic::AtomicUsize;
 use std::sync::atomic::Ordering;
+use std::sync::mpsc;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -18,12 +20,16 @@ use crate::collector::command::CommitCollect;
 use crate::collector::command::DropCollect;
 use crate::collector::command::StartCollect;
 use crate::collector::command::SubmitSpans;
+use crate::collector::record_latency;
 use crate::collector::Config;
+use crate::collector::DropPolicy;
 use crate::collector::SpanId;
 use crate::collector::SpanRecord;
 use crate::collector::SpanSet;
+use crate::collector::SpanStatus;
 use crate::collector::TraceId;
 use crate::local::local_collector::LocalSpansInner;
+use crate::local::raw_span::RawKind;
 use crate::local::raw_span::RawSpan;
 use crate::util::spsc::Receiver;
 use crate::util::spsc::Sender;
@@ -31,16 +37,19 @@ use crate::util::spsc::{self};
 use crate::util::CollectToken;
 
 const COLLECT_LOOP_INTERVAL: Duration = Duration::from_millis(50);
+const CHANNEL_CAPACITY: usize = 10240;
 
 static NEXT_COLLECT_ID: AtomicUsize = AtomicUsize::new(0);
 static GLOBAL_COLLECTOR: Lazy<Mutex<GlobalCollector>> =
     Lazy::new(|| Mutex::new(GlobalCollector::start()));
 static SPSC_RXS: Lazy<Mutex<Vec<Receiver<CollectCommand>>>> = Lazy::new(|| Mutex::new(Vec::new()));
 static REPORTER_READY: AtomicBool = AtomicBool::new(false);
+static DROP_OLDEST: AtomicBool = AtomicBool::new(false);
+static DROPPED_SPANS: AtomicUsize = AtomicUsize::new(0);
 
 thread_local! {
     static COMMAND_SENDER: UnsafeCell<Sender<CollectCommand>> = {
-        let (tx, rx) = spsc::bounded(10240);
+        let (tx, rx) = spsc::bounded(CHANNEL_CAPACITY);
         register_receiver(rx);
         UnsafeCell::new(tx)
     };
@@ -62,6 +71,45 @@ fn force_send_command(cmd: CollectCommand) {
         .ok();
 }
 
+fn send_spans(cmd: SubmitSpans) {
+    COMMAND_SENDER
+        .try_with(|sender| {
+            let sender = unsafe { &mut *sender.get() };
+            let dropped = send_spans_to(sender, cmd, DROP_OLDEST.load(Ordering::Relaxed));
+            if dropped > 0 {
+                DROPPED_SPANS.fetch_add(dropped, Ordering::Relaxed);
+            }
+        })
+        .ok();
+}
+
+/// Sends the spans to the channel of the current thread, following the drop policy once it is
+/// full. Returns the number of dropped spans.
+fn send_spans_to(
+    sender: &mut Sender<CollectCommand>,
+    cmd: SubmitSpans,
+    drop_oldest: bool,
+) -> usize {
+    if drop_oldest {
+        sender
+            .send_or_evict_oldest(CollectCommand::SubmitSpans(cmd), CHANNEL_CAPACITY, |cmd| {
+                matches!(cmd, CollectCommand::SubmitSpans(_))
+            })
+            .into_iter()
+            .map(|evicted| match evicted {
+                CollectCommand::SubmitSpans(evicted) => evicted.spans.len(),
+                _ => 0,
+            })
+            .sum()
+    } else {
+        let len = cmd.spans.len();
+        match sender.send(CollectCommand::SubmitSpans(cmd)) {
+            Ok(()) => 0,
+            Err(_) => len,
+        }
+    }
+}
+
 /// Sets the reporter and its configuration for the current application.
 ///
 /// # Examples
@@ -76,12 +124,36 @@ pub fn set_reporter(reporter: impl Reporter, config: Config) {
     #[cfg(feature = "enable")]
     {
         let mut global_collector = GLOBAL_COLLECTOR.lock();
-        global_collector.config = config;
-        global_collector.reporter = Some(Box::new(reporter));
-        REPORTER_READY.store(true, Ordering::Relaxed);
+        install_reporter(&mut global_collector, Box::new(reporter), config);
+    }
+}
+
+/// Sets the reporter as [`set_reporter()`] does, unless a reporter is already set.
+pub(crate) fn set_reporter_if_unset(reporter: impl Reporter, config: Config) {
+    #[cfg(feature = "enable")]
+    {
+        let mut global_collector = GLOBAL_COLLECTOR.lock();
+        if global_collector.reporter.is_none() {
+            install_reporter(&mut global_collector, Box::new(reporter), config);
+        }
     }
 }
 
+#[cfg(feature = "enable")]
+fn install_reporter(
+    global_collector: &mut GlobalCollector,
+    reporter: Box<dyn Reporter>,
+    config: Config,
+) {
+    global_collector.config = config;
+    global_collector.reporter = Some(reporter);
+    DROP_OLDEST.store(
+        config.drop_policy == DropPolicy::DropOldest,
+        Ordering::Relaxed,
+    );
+    REPORTER_READY.store(true, Ordering::Relaxed);
+}
+
 pub(crate) fn reporter_ready() -> bool {
     REPORTER_READY.load(Ordering::Relaxed)
 }
@@ -106,9 +178,29 @@ pub fn flush() {
 /// A trait defining the behavior of a reporter. A reporter is responsible for
 /// handling span records, typically by sending them to a remote service for
 /// further processing and analysis.
+///
+/// Spans dropped because threads submitted them faster than they were collected never reach
+/// [`report()`](Reporter::report). Their number is only passed to
+/// [`report_dropped()`](Reporter::report_dropped), which a reporter must override to learn
+/// about them.
 pub trait Reporter: Send + 'static {
     /// Reports a batch of spans to a remote service.
     fn report(&mut self, spans: &[SpanRecord]);
+
+    /// Reports the number of spans dropped since the previous batch because threads submitted
+    /// spans faster than the global collector consumes them, see [`DropPolicy`]. Called before
+    /// [`report()`](Reporter::report) if any span was dropped.
+    ///
+    /// The default implementation discards the count, so a reporter that wants to monitor or
+    /// log dropped spans must override this method. [`ConsoleReporter`] and [`TreeReporter`]
+    /// print the count.
+    ///
+    /// [`ConsoleReporter`]: crate::collector::ConsoleReporter
+    /// [`TreeReporter`]: crate::collector::TreeReporter
+    /// [`DropPolicy`]: crate::collector::DropPolicy
+    fn report_dropped(&mut self, count: usize) {
+        let _ = count;
+    }
 }
 
 #[derive(Default, Clone)]
@@ -118,7 +210,19 @@ pub(crate) struct GlobalCollect;
 impl GlobalCollect {
     pub fn start_collect(&self) -> usize {
         let collect_id = NEXT_COLLECT_ID.fetch_add(1, Ordering::Relaxed);
-        send_command(CollectCommand::StartCollect(StartCollect { collect_id }));
+        send_command(CollectCommand::StartCollect(StartCollect {
+            collect_id,
+            stream: None,
+        }));
+        collect_id
+    }
+
+    pub fn start_streaming_collect(&self, stream: mpsc::Sender<SpanRecord>) -> usize {
+        let collect_id = NEXT_COLLECT_ID.fetch_add(1, Ordering::Relaxed);
+        send_command(CollectCommand::StartCollect(StartCollect {
+            collect_id,
+            stream: Some(stream),
+        }));
         collect_id
     }
 
@@ -126,6 +230,42 @@ impl GlobalCollect {
         force_send_command(CollectCommand::CommitCollect(CommitCollect { collect_id }));
     }
 
+    pub fn commit_collect_into(&self, collect_id: usize, records: &mut Vec<SpanRecord>) {
+        GLOBAL_COLLECTOR
+            .lock()
+            .redirects
+            .insert(collect_id, Vec::new());
+        force_send_command(CollectCommand::CommitCollect(CommitCollect { collect_id }));
+
+        // Spawns a new thread to ensure the reporter operates outside the tokio runtime to prevent panic.
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let mut global_collector = GLOBAL_COLLECTOR.lock();
+                    global_collector.handle_commands(false);
+                    if let Some(redirected) = global_collector.redirects.remove(&collect_id) {
+                        records.extend(redirected);
+                    }
+                })
+                .join()
+                .unwrap()
+        });
+    }
+
+    pub fn snapshot(&self, collect_id: usize) -> Vec<SpanRecord> {
+        // Spawns a new thread to ensure the reporter operates outside the tokio runtime to prevent panic.
+        std::thread::scope(|scope| {
+            scope
+                .spawn(|| {
+                    let mut global_collector = GLOBAL_COLLECTOR.lock();
+                    global_collector.handle_commands(false);
+                    global_collector.snapshot(collect_id)
+                })
+                .join()
+                .unwrap()
+        })
+    }
+
     pub fn drop_collect(&self, collect_id: usize) {
         force_send_command(CollectCommand::DropCollect(DropCollect { collect_id }));
     }
@@ -157,10 +297,10 @@ impl GlobalCollect {
     //   `CollectTokenItem`
     // * Amend `raw_span.parent_id` of root spans in `SpanSet` to `parent_ids` of `CollectTokenItem`
     pub fn submit_spans(&self, spans: SpanSet, collect_token: CollectToken) {
-        send_command(CollectCommand::SubmitSpans(SubmitSpans {
+        send_spans(SubmitSpans {
             spans,
             collect_token,
-        }));
+        });
     }
 }
 
@@ -177,12 +317,33 @@ enum SpanCollection {
     },
 }
 
+/// A collect whose records are sent to a channel as soon as spans are submitted.
+struct Stream {
+    sender: mpsc::Sender<SpanRecord>,
+    // Events and properties whose parent span has not been submitted yet.
+    dangling_items: HashMap<SpanId, DanglingItems>,
+}
+
+/// Events, properties and status waiting to be attached to the span they belong to.
+#[derive(Default)]
+struct DanglingItems {
+    events: Vec<EventRecord>,
+    properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
+    status: Option<SpanStatus>,
+}
+
 pub(crate) struct GlobalCollector {
     config: Config,
     reporter: Option<Box<dyn Reporter>>,
 
     active_collectors: HashMap<usize, (Vec<SpanCollection>, usize)>,
     committed_records: Vec<SpanRecord>,
+    // Collects whose records are taken by `Span::record_into()` rather than reported.
+    redirects: HashMap<usize, Vec<SpanRecord>>,
+    streams: HashMap<usize, Stream>,
+    // Events, properties and status left over by `Span::snapshot()`, whose parent span has not been
+    // submitted yet. They are mounted when the collect is committed.
+    snapshot_dangling_items: HashMap<usize, HashMap<SpanId, DanglingItems>>,
     last_report: std::time::Instant,
 
     // Vectors to be reused by collection loops. They must be empty outside of the `handle_commands` loop.
@@ -190,7 +351,7 @@ pub(crate) struct GlobalCollector {
     drop_collects: Vec<DropCollect>,
     commit_collects: Vec<CommitCollect>,
     submit_spans: Vec<SubmitSpans>,
-    dangling_events: HashMap<SpanId, Vec<EventRecord>>,
+    dangling_items: HashMap<SpanId, DanglingItems>,
 }
 
 impl GlobalCollector {
@@ -222,13 +383,16 @@ impl GlobalCollector {
 
             active_collectors: HashMap::new(),
             committed_records: Vec::new(),
+            redirects: HashMap::new(),
+            streams: HashMap::new(),
+            snapshot_dangling_items: HashMap::new(),
             last_report: std::time::Instant::now(),
 
             start_collects: Vec::new(),
             drop_collects: Vec::new(),
             commit_collects: Vec::new(),
             submit_spans: Vec::new(),
-            dangling_events: HashMap::new(),
+            dangling_items: HashMap::new(),
         }
     }
 
@@ -237,7 +401,7 @@ impl GlobalCollector {
         debug_assert!(self.drop_collects.is_empty());
         debug_assert!(self.commit_collects.is_empty());
         debug_assert!(self.submit_spans.is_empty());
-        debug_assert!(self.dangling_events.is_empty());
+        debug_assert!(self.dangling_items.is_empty());
 
         let start_collects = &mut self.start_collects;
         let drop_collects = &mut self.drop_collects;
@@ -275,12 +439,20 @@ impl GlobalCollector {
             return;
         }
 
-        for StartCollect { collect_id } in self.start_collects.drain(..) {
+        for StartCollect { collect_id, stream } in self.start_collects.drain(..) {
             self.active_collectors.insert(collect_id, (Vec::new(), 0));
+            if let Some(sender) = stream {
+                self.streams.insert(collect_id, Stream {
+                    sender,
+                    dangling_items: HashMap::new(),
+                });
+            }
         }
 
         for DropCollect { collect_id } in self.drop_collects.drain(..) {
             self.active_collectors.remove(&collect_id);
+            self.streams.remove(&collect_id);
+            self.snapshot_dangling_items.remove(&collect_id);
         }
 
         for SubmitSpans {
@@ -325,81 +497,63 @@ impl GlobalCollector {
             }
         }
 
+        for (collect_id, stream) in self.streams.iter_mut() {
+            if let Some((span_collections, _)) = self.active_collectors.get_mut(collect_id) {
+                if span_collections.is_empty() {
+                    continue;
+                }
+
+                let anchor: Anchor = Anchor::new();
+                let mut records = Vec::new();
+                for span_collection in span_collections.drain(..) {
+                    amend_span_collection(
+                        span_collection,
+                        &mut records,
+                        &mut stream.dangling_items,
+                        &anchor,
+                    );
+                }
+                mount_items(&mut records, &mut stream.dangling_items);
+
+                // Send the records in the order the spans completed.
+                records.sort_by_key(|record| record.begin_time_unix_ns + record.duration_ns);
+                for record in records {
+                    stream.sender.send(record).ok();
+                }
+            }
+        }
+
         for CommitCollect { collect_id } in commit_collects.drain(..) {
+            // All records of a streaming collect have been sent above. Dropping the stream
+            // disconnects the receiver.
+            if self.streams.remove(&collect_id).is_some() {
+                self.active_collectors.remove(&collect_id);
+                continue;
+            }
+
             if let Some((span_collections, _)) = self.active_collectors.remove(&collect_id) {
-                debug_assert!(self.dangling_events.is_empty());
-                let dangling_events = &mut self.dangling_events;
+                debug_assert!(self.dangling_items.is_empty());
+                let dangling_items = &mut self.dangling_items;
+                if let Some(items) = self.snapshot_dangling_items.remove(&collect_id) {
+                    dangling_items.extend(items);
+                }
+
+                // Records of a redirected collect go to the caller of `Span::record_into()`
+                // instead of the reporter.
+                let records = match self.redirects.get_mut(&collect_id) {
+                    Some(records) => records,
+                    None => &mut *committed_records,
+                };
 
                 let anchor: Anchor = Anchor::new();
-                let committed_len = committed_records.len();
+                let committed_len = records.len();
 
                 for span_collection in span_collections {
-                    match span_collection {
-                        SpanCollection::Owned {
-                            spans,
-                            trace_id,
-                            parent_id,
-                        } => match spans {
-                            SpanSet::Span(raw_span) => amend_span(
-                                &raw_span,
-                                trace_id,
-                                parent_id,
-                                committed_records,
-                                dangling_events,
-                                &anchor,
-                            ),
-                            SpanSet::LocalSpansInner(local_spans) => amend_local_span(
-                                &local_spans,
-                                trace_id,
-                                parent_id,
-                                committed_records,
-                                dangling_events,
-                                &anchor,
-                            ),
-                            SpanSet::SharedLocalSpans(local_spans) => amend_local_span(
-                                &local_spans,
-                                trace_id,
-                                parent_id,
-                                committed_records,
-                                dangling_events,
-                                &anchor,
-                            ),
-                        },
-                        SpanCollection::Shared {
-                            spans,
-                            trace_id,
-                            parent_id,
-                        } => match &*spans {
-                            SpanSet::Span(raw_span) => amend_span(
-                                raw_span,
-                                trace_id,
-                                parent_id,
-                                committed_records,
-                                dangling_events,
-                                &anchor,
-                            ),
-                            SpanSet::LocalSpansInner(local_spans) => amend_local_span(
-                                local_spans,
-                                trace_id,
-                                parent_id,
-                                committed_records,
-                                dangling_events,
-                                &anchor,
-                            ),
-                            SpanSet::SharedLocalSpans(local_spans) => amend_local_span(
-                                local_spans,
-                                trace_id,
-                                parent_id,
-                                committed_records,
-                                dangling_events,
-                                &anchor,
-                            ),
-                        },
-                    }
+                    amend_span_collection(span_collection, records, dangling_items, &anchor);
                 }
 
-                mount_events(&mut committed_records[committed_len..], dangling_events);
-                dangling_events.clear();
+                mount_items(&mut records[committed_len..], dangling_items);
+                dangling_items.clear();
             }
         }
 
@@ -407,21 +561,112 @@ impl GlobalCollector {
             || committed_records.len() > self.config.batch_report_max_spans.unwrap_or(usize::MAX)
             || flush
         {
-            self.reporter
-                .as_mut()
-                .unwrap()
-                .report(committed_records.drain(..).as_slice());
+            if self.config.latency_report {
+                record_latency(committed_records);
+            }
+
+            report(
+                self.reporter.as_deref_mut().unwrap(),
+                committed_records,
+                DROPPED_SPANS.swap(0, Ordering::Relaxed),
+            );
             self.last_report = std::time::Instant::now();
         }
     }
 }
 
+impl GlobalCollector {
+    /// Takes the records of the spans submitted so far to an active collect.
+    fn snapshot(&mut self, collect_id: usize) -> Vec<SpanRecord> {
+        let mut records = Vec::new();
+        if let Some((span_collections, _)) = self.active_collectors.get_mut(&collect_id) {
+            let dangling_items = self.snapshot_dangling_items.entry(collect_id).or_default();
+            let anchor: Anchor = Anchor::new();
+            for span_collection in span_collections.drain(..) {
+                amend_span_collection(span_collection, &mut records, dangling_items, &anchor);
+            }
+            mount_items(&mut records, dangling_items);
+        }
+        records
+    }
+}
+
+fn amend_span_collection(
+    span_collection: SpanCollection,
+    records: &mut Vec<SpanRecord>,
+    dangling_items: &mut HashMap<SpanId, DanglingItems>,
+    anchor: &Anchor,
+) {
+    match span_collection {
+        SpanCollection::Owned {
+            spans,
+            trace_id,
+            parent_id,
+        } => match spans {
+            SpanSet::Span(raw_span) => amend_span(
+                &raw_span,
+                trace_id,
+                parent_id,
+                records,
+                dangling_items,
+                anchor,
+            ),
+            SpanSet::LocalSpansInner(local_spans) => amend_local_span(
+                &local_spans,
+                trace_id,
+                parent_id,
+                records,
+                dangling_items,
+                anchor,
+            ),
+            SpanSet::SharedLocalSpans(local_spans) => amend_local_span(
+                &local_spans,
+                trace_id,
+                parent_id,
+                records,
+                dangling_items,
+                anchor,
+            ),
+        },
+        SpanCollection::Shared {
+            spans,
+            trace_id,
+            parent_id,
+        } => match &*spans {
+            SpanSet::Span(raw_span) => amend_span(
+                raw_span,
+                trace_id,
+                parent_id,
+                records,
+                dangling_items,
+                anchor,
+            ),
+            SpanSet::LocalSpansInner(local_spans) => amend_local_span(
+                local_spans,
+                trace_id,
+                parent_id,
+                records,
+                dangling_items,
+                anchor,
+            ),
+            SpanSet::SharedLocalSpans(local_spans) => amend_local_span(
+                local_spans,
+                trace_id,
+                parent_id,
+                records,
+                dangling_items,
+                anchor,
+            ),
+        },
+    }
+}
+
 fn amend_local_span(
     local_spans: &LocalSpansInner,
     trace_id: TraceId,
     parent_id: SpanId,
     spans: &mut Vec<SpanRecord>,
-    events: &mut HashMap<SpanId, Vec<EventRecord>>,
+    dangling: &mut HashMap<SpanId, DanglingItems>,
     anchor: &Anchor,
 ) {
     for span in local_spans.spans.iter() {
@@ -432,14 +677,29 @@ fn amend_local_span(
             span.parent_id
         };
 
-        if span.is_event {
-            let event = EventRecord {
-                name: span.name.clone(),
-                timestamp_unix_ns: begin_time_unix_ns,
-                properties: span.properties.clone(),
-            };
-            events.entry(parent_id).or_default().push(event);
-            continue;
+        match span.raw_kind {
+            RawKind::Span => {}
+            RawKind::Event => {
+                let event = EventRecord {
+                    name: span.name.clone(),
+                    timestamp_unix_ns: begin_time_unix_ns,
+                    properties: span.properties.clone(),
+                };
+                dangling.entry(parent_id).or_default().events.push(event);
+                continue;
+            }
+            RawKind::Properties => {
+                dangling
+                    .entry(parent_id)
+                    .or_default()
+                    .properties
+                    .extend(span.properties.iter().cloned());
+                continue;
+            }
+            RawKind::Status(status) => {
+                dangling.entry(parent_id).or_default().status = Some(status);
+                continue;
+            }
         }
 
         let end_time_unix_ns = if span.end_instant == span.begin_instant {
@@ -456,6 +716,8 @@ fn amend_local_span(
             name: span.name.clone(),
             properties: span.properties.clone(),
             events: vec![],
+            status: SpanStatus::Unset,
+            thread_id: span.thread_id,
         });
     }
 }
@@ -465,19 +727,34 @@ fn amend_span(
     trace_id: TraceId,
     parent_id: SpanId,
     spans: &mut Vec<SpanRecord>,
-    events: &mut HashMap<SpanId, Vec<EventRecord>>,
+    dangling: &mut HashMap<SpanId, DanglingItems>,
     anchor: &Anchor,
 ) {
     let begin_time_unix_ns = raw_span.begin_instant.as_unix_nanos(anchor);
 
-    if raw_span.is_event {
-        let event = EventRecord {
-            name: raw_span.name.clone(),
-            timestamp_unix_ns: begin_time_unix_ns,
-            properties: raw_span.properties.clone(),
-        };
-        events.entry(parent_id).or_default().push(event);
-        return;
+    match raw_span.raw_kind {
+        RawKind::Span => {}
+        RawKind::Event => {
+            let event = EventRecord {
+                name: raw_span.name.clone(),
+                timestamp_unix_ns: begin_time_unix_ns,
+                properties: raw_span.properties.clone(),
+            };
+            dangling.entry(parent_id).or_default().events.push(event);
+            return;
+        }
+        RawKind::Properties => {
+            dangling
+                .entry(parent_id)
+                .or_default()
+                .properties
+                .extend(raw_span.properties.iter().cloned());
+            return;
+        }
+        RawKind::Status(status) => {
+            dangling.entry(parent_id).or_default().status = Some(status);
+            return;
+        }
     }
 
     let end_time_unix_ns = raw_span.end_instant.as_unix_nanos(anchor);
@@ -490,23 +767,31 @@ fn amend_span(
         name: raw_span.name.clone(),
         properties: raw_span.properties.clone(),
         events: vec![],
+        status: SpanStatus::Unset,
+        thread_id: raw_span.thread_id,
     });
 }
 
-fn mount_events(
-    records: &mut [SpanRecord],
-    dangling_events: &mut HashMap<SpanId, Vec<EventRecord>>,
-) {
+fn mount_items(records: &mut [SpanRecord], dangling_items: &mut HashMap<SpanId, DanglingItems>) {
     for record in records.iter_mut() {
-        if dangling_events.is_empty() {
+        if dangling_items.is_empty() {
             return;
         }
 
-        if let Some(event) = dangling_events.remove(&record.span_id) {
+        if let Some(DanglingItems {
+            events,
+            properties,
+            status,
+        }) = dangling_items.remove(&record.span_id)
+        {
             if record.events.is_empty() {
-                record.events = event;
+                record.events = events;
             } else {
-                record.events.extend(event);
+                record.events.extend(events);
+            }
+            record.properties.extend(properties);
+            if let Some(status) = status {
+                record.status = status;
             }
         }
     }
@@ -521,3 +806,81 @@ impl SpanSet {
         }
     }
 }
+
+fn report(reporter: &mut dyn Reporter, records: &mut Vec<SpanRecord>, dropped: usize) {
+    if dropped > 0 {
+        reporter.report_dropped(dropped);
+    }
+    reporter.report(records.drain(..).as_slice());
+}
+
+#[cfg(test)]
+mod tests {
+    use minstant::Instant;
+
+    use super::*;
+    use crate::collector::CollectTokenItem;
+    use crate::local::raw_span::RawKind;
+    use crate::local::raw_span::RawSpan;
+
+    fn spans() -> SubmitSpans {
+        let span = RawSpan::begin_with(
+            SpanId(1),
+            SpanId::default(),
+            Instant::now(),
+            "span",
+            RawKind::Span,
+        );
+        let item = CollectTokenItem {
+            trace_id: TraceId(1),
+            parent_id: SpanId::default(),
+            collect_id: 0,
+            is_root: false,
+        };
+        SubmitSpans {
+            spans: SpanSet::Span(span),
+            collect_token: item.into(),
+        }
+    }
+
+    #[test]
+    fn drop_spans_when_channel_full() {
+        // The channel is not consumed.
+        let (mut sender, _receiver) = spsc::bounded(CHANNEL_CAPACITY);
+        let dropped = (0..CHANNEL_CAPACITY + 10)
+            .map(|_| send_spans_to(&mut sender, spans(), false))
+            .sum::<usize>();
+        assert_eq!(dropped, 10);
+
+        // Once more than `CHANNEL_CAPACITY` span sets are pending, the oldest ones are dropped
+        // down to half of it.
+        let (mut sender, _receiver) = spsc::bounded(CHANNEL_CAPACITY);
+        let dropped = (0..CHANNEL_CAPACITY * 2 + 10)
+            .map(|_| send_spans_to(&mut sender, spans(), true))
+            .sum::<usize>();
+        assert_eq!(dropped, CHANNEL_CAPACITY / 2 + 1);
+    }
+
+    #[test]
+    fn report_dropped_spans() {
+        #[derive(Default)]
+        struct DroppedReporter {
+            calls: Vec<(usize, usize)>,
+        }
+
+        impl Reporter for DroppedReporter {
+            fn report(&mut self, spans: &[SpanRecord]) {
+                self.calls.push((0, spans.len()));
+            }
+
+            fn report_dropped(&mut self, count: usize) {
+                self.calls.push((count, 0));
+            }
+        }
+
+        let mut reporter = DroppedReporter::default();
+        report(&mut reporter, &mut vec![SpanRecord::default()], 0);
+        report(&mut reporter, &mut vec![SpanRecord::default(); 2], 5);
+        assert_eq!(reporter.calls, [(0, 1), (5, 0), (0, 2)]);
+    }
+}
@@ -1,19 +1,81 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::cell::Cell;
+use std::fmt;
 
 /// An identifier for a trace, which groups a set of related spans together.
+///
+/// The identifier is displayed as 32 lowercase hex digits, the same encoding as the
+/// `trace-id` field of a [W3C Trace Context](https://www.w3.org/TR/trace-context/).
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct TraceId(pub u128);
 
 /// An identifier for a span within a trace.
+///
+/// The identifier is displayed as 16 lowercase hex digits, the same encoding as the
+/// `parent-id` field of a [W3C Trace Context](https://www.w3.org/TR/trace-context/).
 #[derive(Copy, Clone, Debug, Eq, PartialEq, Hash, Default)]
 pub struct SpanId(pub u64);
 
+impl fmt::Display for TraceId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:032x}", self.0)
+    }
+}
+
+impl From<u128> for TraceId {
+    #[inline]
+    fn from(id: u128) -> Self {
+        TraceId(id)
+    }
+}
+
+impl From<u64> for TraceId {
+    #[inline]
+    fn from(id: u64) -> Self {
+        TraceId(id as u128)
+    }
+}
+
+impl From<TraceId> for u128 {
+    #[inline]
+    fn from(id: TraceId) -> Self {
+        id.0
+    }
+}
+
+impl fmt::Display for SpanId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl From<u64> for SpanId {
+    #[inline]
+    fn from(id: u64) -> Self {
+        SpanId(id)
+    }
+}
+
+impl From<SpanId> for u64 {
+    #[inline]
+    fn from(id: SpanId) -> Self {
+        id.0
+    }
+}
+
 impl SpanId {
     #[inline]
     /// Create a non-zero `SpanId`
     pub(crate) fn next_id() -> SpanId {
+        #[cfg(feature = "random-span-id")]
+        {
+            return LOCAL_RANDOM_ID_GENERATOR
+                .try_with(next_random_id)
+                .unwrap_or_else(|_| SpanId(rand::random::<u64>() | 1));
+        }
+
+        #[allow(unreachable_code)]
         LOCAL_ID_GENERATOR
             .try_with(|g| {
                 let (prefix, mut suffix) = g.get();
@@ -28,8 +90,25 @@ impl SpanId {
     }
 }
 
+/// Generates a pseudo-random non-zero `SpanId` using xorshift64*.
+#[cfg_attr(not(feature = "random-span-id"), allow(dead_code))]
+#[inline]
+fn next_random_id(state: &Cell<u64>) -> SpanId {
+    let mut x = state.get();
+    x ^= x >> 12;
+    x ^= x << 25;
+    x ^= x >> 27;
+    state.set(x);
+
+    // `x` is never zero, and multiplying by an odd number is a bijection, so the result is
+    // never zero either.
+    SpanId(x.wrapping_mul(0x2545_f491_4f6c_dd1d))
+}
+
 thread_local! {
-    static LOCAL_ID_GENERATOR: Cell<(u32, u32)> = Cell::new((rand::random(), 0))
+    static LOCAL_ID_GENERATOR: Cell<(u32, u32)> = Cell::new((rand::random(), 0));
+    #[cfg_attr(not(feature = "random-span-id"), allow(dead_code))]
+    static LOCAL_RANDOM_ID_GENERATOR: Cell<u64> = Cell::new(rand::random::<u64>() | 1);
 }
 
 #[cfg(test)]
@@ -58,4 +137,45 @@ mod tests {
 
         assert_eq!(k.len(), 32 * 1000);
     }
+
+    #[test]
+    fn random_id() {
+        let state = Cell::new(rand::random::<u64>() | 1);
+        let ids = std::iter::repeat_with(|| next_random_id(&state))
+            .take(100000)
+            .collect::<Vec<_>>();
+
+        assert!(ids.iter().all(|id| *id != SpanId::default()));
+        assert!(ids.windows(2).all(|w| w[0].0.wrapping_add(1) != w[1].0));
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), ids.len());
+    }
+
+    #[test]
+    fn id_conversion_and_display() {
+        let record = crate::collector::SpanRecord {
+            trace_id: TraceId::from(0x0af7651916cd43dd8448eb211c80319c_u128),
+            span_id: SpanId::from(0xb7ad6b7169203331_u64),
+            parent_id: SpanId::from(1_u64),
+            ..Default::default()
+        };
+
+        assert_eq!(
+            record.trace_id.to_string(),
+            "0af7651916cd43dd8448eb211c80319c"
+        );
+        assert_eq!(record.span_id.to_string(), "b7ad6b7169203331");
+        assert_eq!(record.parent_id.to_string(), "0000000000000001");
+        assert_eq!(TraceId::from(12_u64), TraceId(12));
+        assert_eq!(
+            u128::from(record.trace_id),
+            0x0af7651916cd43dd8448eb211c80319c
+        );
+        assert_eq!(u64::from(record.parent_id), 1);
+
+        let context = crate::collector::SpanContext::new(record.trace_id, record.span_id);
+        assert_eq!(
+            context.encode_w3c_traceparent(),
+            format!("00-{}-{}-01", record.trace_id, record.span_id)
+        );
+    }
 }
@@ -0,0 +1,271 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+use parking_lot::Mutex;
+
+use crate::collector::SpanRecord;
+
+// Each power of two is split into 2^SUB_BUCKET_BITS buckets, which bounds the relative error
+// of a percentile to about 3%.
+const SUB_BUCKET_BITS: u32 = 4;
+const SUB_BUCKETS: usize = 1 << SUB_BUCKET_BITS;
+const BUCKETS: usize = (64 - SUB_BUCKET_BITS as usize + 1) * SUB_BUCKETS;
+
+/// The number of span names aggregated separately. As a histogram takes about 8 KiB, this bounds
+/// the memory taken by span names of high cardinality, e.g. formatted with the arguments of a
+/// function, to about 2 MiB.
+pub const MAX_LATENCY_NAMES: usize = 256;
+
+/// The name under which the spans are aggregated once [`MAX_LATENCY_NAMES`] names are.
+pub const OTHER_LATENCY_NAME: &str = "<other>";
+
+static LATENCY_HISTOGRAMS: Lazy<Mutex<LatencyHistograms>> =
+    Lazy::new(|| Mutex::new(LatencyHistograms::default()));
+
+/// The latency percentiles of the spans sharing the same name.
+///
+/// The percentiles are approximated by a histogram with a relative error of about 3%.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct LatencyPercentiles {
+    pub name: Cow<'static, str>,
+    pub count: u64,
+    pub p50: Duration,
+    pub p90: Duration,
+    pub p99: Duration,
+}
+
+/// Returns the latency percentiles of all reported spans, grouped by span name and sorted by
+/// name.
+///
+/// The latencies are only aggregated if enabled by [`Config::latency_report()`]. At most
+/// [`MAX_LATENCY_NAMES`] span names are aggregated separately, in the order they are first
+/// reported, and the spans of any further name are aggregated under [`OTHER_LATENCY_NAME`]. Use
+/// [`take_latency_report()`] to start over, e.g. periodically.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::prelude::*;
+///
+/// minitrace::set_reporter(ConsoleReporter, Config::default().latency_report(true));
+///
+/// drop(Span::root("root", SpanContext::random()));
+/// minitrace::flush();
+///
+/// for percentiles in minitrace::collector::latency_report() {
+///     println!("{percentiles:?}");
+/// }
+/// ```
+///
+/// [`Config::latency_report()`]: crate::collector::Config::latency_report
+pub fn latency_report() -> Vec<LatencyPercentiles> {
+    LATENCY_HISTOGRAMS.lock().report()
+}
+
+/// Returns the latency percentiles like [`latency_report()`], and clears the aggregated latencies,
+/// so that the next report only covers the spans reported from now on.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::ConsoleReporter;
+/// use minitrace::prelude::*;
+///
+/// minitrace::set_reporter(ConsoleReporter, Config::default().latency_report(true));
+///
+/// drop(Span::root("root", SpanContext::random()));
+/// minitrace::flush();
+///
+/// let report = minitrace::collector::take_latency_report();
+/// assert!(minitrace::collector::latency_report().is_empty());
+/// ```
+pub fn take_latency_report() -> Vec<LatencyPercentiles> {
+    std::mem::take(&mut *LATENCY_HISTOGRAMS.lock()).report()
+}
+
+pub(crate) fn record_latency(records: &[SpanRecord]) {
+    LATENCY_HISTOGRAMS.lock().record(records);
+}
+
+#[derive(Default)]
+struct LatencyHistograms {
+    histograms: HashMap<Cow<'static, str>, Histogram>,
+}
+
+impl LatencyHistograms {
+    fn record(&mut self, records: &[SpanRecord]) {
+        for record in records {
+            if let Some(histogram) = self.histograms.get_mut(&record.name) {
+                histogram.record(record.duration_ns);
+                continue;
+            }
+
+            let name = if self.histograms.len() < MAX_LATENCY_NAMES {
+                record.name.clone()
+            } else {
+                Cow::Borrowed(OTHER_LATENCY_NAME)
+            };
+            self.histograms
+                .entry(name)
+                .or_default()
+                .record(record.duration_ns);
+        }
+    }
+
+    fn report(&self) -> Vec<LatencyPercentiles> {
+        let mut report: Vec<_> = self
+            .histograms
+            .iter()
+            .map(|(name, histogram)| LatencyPercentiles {
+                name: name.clone(),
+                count: histogram.count,
+                p50: Duration::from_nanos(histogram.percentile(0.5)),
+                p90: Duration::from_nanos(histogram.percentile(0.9)),
+                p99: Duration::from_nanos(histogram.percentile(0.99)),
+            })
+            .collect();
+        report.sort_unstable_by(|a, b| a.name.cmp(&b.name));
+        report
+    }
+}
+
+/// A log-linear histogram of durations in nanoseconds.
+struct Histogram {
+    buckets: Box<[u64; BUCKETS]>,
+    count: u64,
+}
+
+impl Default for Histogram {
+    fn default() -> Self {
+        Self {
+            buckets: Box::new([0; BUCKETS]),
+            count: 0,
+        }
+    }
+}
+
+impl Histogram {
+    fn record(&mut self, value: u64) {
+        self.buckets[bucket_index(value)] += 1;
+        self.count += 1;
+    }
+
+    fn percentile(&self, quantile: f64) -> u64 {
+        let rank = ((quantile * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.buckets.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return bucket_value(index);
+            }
+        }
+        0
+    }
+}
+
+fn bucket_index(value: u64) -> usize {
+    if value < SUB_BUCKETS as u64 {
+        return value as usize;
+    }
+
+    let exp = 63 - value.leading_zeros();
+    let shift = exp - SUB_BUCKET_BITS;
+    let mantissa = (value >> shift) as usize & (SUB_BUCKETS - 1);
+    (shift as usize + 1) * SUB_BUCKETS + mantissa
+}
+
+// Returns the midpoint of the values falling into the bucket.
+fn bucket_value(index: usize) -> u64 {
+    if index < SUB_BUCKETS {
+        return index as u64;
+    }
+
+    let shift = (index / SUB_BUCKETS - 1) as u32;
+    let mantissa = (index % SUB_BUCKETS) as u64;
+    let lower = (SUB_BUCKETS as u64 + mantissa) << shift;
+    lower + ((1 << shift) >> 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bucket_round_trip() {
+        for value in (0..10000).chain([u64::MAX / 3, u64::MAX]) {
+            let index = bucket_index(value);
+            assert!(index < BUCKETS);
+
+            let approx = bucket_value(index);
+            let error = (approx as f64 - value as f64).abs() / (value.max(1) as f64);
+            assert!(error <= 1.0 / 32.0, "value: {value}, approx: {approx}");
+        }
+    }
+
+    #[test]
+    fn latency_percentiles() {
+        let mut histograms = LatencyHistograms::default();
+
+        let records: Vec<_> = (1..=10000)
+            .map(|i| SpanRecord {
+                name: "op".into(),
+                duration_ns: i * 1000,
+                ..SpanRecord::default()
+            })
+            .chain(std::iter::once(SpanRecord {
+                name: "other".into(),
+                duration_ns: 42,
+                ..SpanRecord::default()
+            }))
+            .collect();
+        histograms.record(&records);
+
+        let report = histograms.report();
+        assert_eq!(report.len(), 2);
+
+        let op = &report[0];
+        assert_eq!(op.name, "op");
+        assert_eq!(op.count, 10000);
+        let within = |actual: Duration, expected: Duration| {
+            let error = (actual.as_nanos() as f64 - expected.as_nanos() as f64).abs();
+            error / expected.as_nanos() as f64 <= 0.04
+        };
+        assert!(within(op.p50, Duration::from_millis(5)), "{op:?}");
+        assert!(within(op.p90, Duration::from_millis(9)), "{op:?}");
+        assert!(within(op.p99, Duration::from_micros(9900)), "{op:?}");
+
+        let other = &report[1];
+        assert_eq!(other.name, "other");
+        assert_eq!(other.count, 1);
+        assert_eq!(other.p50, other.p99);
+    }
+
+    #[test]
+    fn latency_names_capped() {
+        let mut histograms = LatencyHistograms::default();
+
+        let records: Vec<_> = (0..MAX_LATENCY_NAMES + 10)
+            .map(|i| SpanRecord {
+                name: format!("op-{i:04}").into(),
+                duration_ns: 1000,
+                ..SpanRecord::default()
+            })
+            .collect();
+        histograms.record(&records);
+        histograms.record(&records[..1]);
+
+        let report = histograms.report();
+        assert_eq!(report.len(), MAX_LATENCY_NAMES + 1);
+        assert_eq!(report[0].name, OTHER_LATENCY_NAME);
+        assert_eq!(report[0].count, 10);
+        assert_eq!(report[1].name, "op-0000");
+        assert_eq!(report[1].count, 2);
+    }
+}
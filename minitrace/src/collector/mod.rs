@@ -4,18 +4,42 @@
 
 #![cfg_attr(test, allow(dead_code))]
 
+#[cfg(feature = "chrome-trace")]
+mod chrome_trace;
 pub(crate) mod command;
 mod console_reporter;
+#[cfg(feature = "csv")]
+mod csv;
+mod diff;
+#[cfg(feature = "flamegraph")]
+mod flamegraph;
+mod folded;
 pub(crate) mod global_collector;
 pub(crate) mod id;
+mod latency;
+mod replay;
+pub(crate) mod resource;
+mod sampler;
+mod sort;
+mod span_tree;
 mod test_reporter;
+mod tree_reporter;
 
 use std::borrow::Cow;
 use std::rc::Rc;
 use std::sync::Arc;
 use std::time::Duration;
 
+#[cfg(feature = "chrome-trace")]
+pub use chrome_trace::to_chrome_trace;
 pub use console_reporter::ConsoleReporter;
+#[cfg(feature = "csv")]
+pub use csv::to_csv;
+pub use diff::diff;
+pub use diff::TraceDiff;
+#[cfg(feature = "flamegraph")]
+pub use flamegraph::to_flamegraph_svg;
+pub use folded::to_folded_stacks;
 #[cfg(not(test))]
 pub(crate) use global_collector::GlobalCollect;
 #[cfg(test)]
@@ -23,8 +47,24 @@ pub(crate) use global_collector::MockGlobalCollect;
 pub use global_collector::Reporter;
 pub use id::SpanId;
 pub use id::TraceId;
+pub use latency::latency_report;
+pub(crate) use latency::record_latency;
+pub use latency::take_latency_report;
+pub use latency::LatencyPercentiles;
+pub use latency::MAX_LATENCY_NAMES;
+pub use latency::OTHER_LATENCY_NAME;
+pub use replay::replay;
+pub use resource::resource;
+pub use sampler::AlwaysOff;
+pub use sampler::AlwaysOn;
+pub use sampler::PrefixSampler;
+pub use sampler::RatioSampler;
+pub use sampler::Sampler;
+pub use sort::collect_sorted_by;
+pub use sort::SortKey;
 #[doc(hidden)]
 pub use test_reporter::TestReporter;
+pub use tree_reporter::TreeReporter;
 
 use crate::local::local_collector::LocalSpansInner;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
@@ -51,8 +91,37 @@ pub struct SpanRecord {
     pub begin_time_unix_ns: u64,
     pub duration_ns: u64,
     pub name: Cow<'static, str>,
+    /// The properties of the span in the order they were added. The properties added by
+    /// [`LocalSpan::add_property()`] and [`LocalSpan::add_properties()`] follow the ones set on
+    /// the span itself.
+    ///
+    /// [`LocalSpan::add_property()`]: crate::local::LocalSpan::add_property
+    /// [`LocalSpan::add_properties()`]: crate::local::LocalSpan::add_properties
     pub properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
     pub events: Vec<EventRecord>,
+    /// The status of the span, set by [`LocalSpan::set_status()`].
+    ///
+    /// [`LocalSpan::set_status()`]: crate::local::LocalSpan::set_status
+    pub status: SpanStatus,
+    /// The id of the thread that ended the span, to tell apart the spans of work running in
+    /// parallel. It is assigned by minitrace, starting at 1 in the order the threads record their
+    /// first span, and is unrelated to the id of the thread in the operating system. It is 0 for a
+    /// span collected before it ended.
+    pub thread_id: u64,
+}
+
+/// The status of a span, which indicates whether the operation it represents succeeded.
+///
+/// It corresponds to the status of a span in OpenTelemetry.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum SpanStatus {
+    /// The status is not set.
+    #[default]
+    Unset,
+    /// The operation completed successfully.
+    Ok,
+    /// The operation failed.
+    Error,
 }
 
 /// A record of an event that occurred during the execution of a span.
@@ -60,6 +129,7 @@ pub struct SpanRecord {
 pub struct EventRecord {
     pub name: Cow<'static, str>,
     pub timestamp_unix_ns: u64,
+    /// The properties of the event in the order they were added.
     pub properties: Vec<(Cow<'static, str>, Cow<'static, str>)>,
 }
 
@@ -255,8 +325,8 @@ impl SpanContext {
     /// ```
     pub fn encode_w3c_traceparent_with_sampled(&self, sampled: bool) -> String {
         format!(
-            "00-{:032x}-{:016x}-{:02x}",
-            self.trace_id.0, self.span_id.0, sampled as u8,
+            "00-{}-{}-{:02x}",
+            self.trace_id, self.span_id, sampled as u8,
         )
     }
 }
@@ -268,6 +338,26 @@ pub struct Config {
     pub(crate) max_spans_per_trace: Option<usize>,
     pub(crate) batch_report_interval: Duration,
     pub(crate) batch_report_max_spans: Option<usize>,
+    pub(crate) latency_report: bool,
+    pub(crate) drop_policy: DropPolicy,
+}
+
+/// The policy to apply when a thread submits spans faster than the global collector consumes them.
+///
+/// Each thread sends its spans to the global collector through a channel with a capacity of
+/// 10240 span sets. The policy decides which spans are dropped once the channel is full. The
+/// number of dropped spans is passed to [`Reporter::report_dropped()`] before the next batch of
+/// spans is reported. That method does nothing by default, so a custom reporter has to override
+/// it to be told about dropped spans.
+#[derive(Debug, Copy, Clone, Default, Eq, PartialEq)]
+pub enum DropPolicy {
+    /// Drop the spans being submitted.
+    #[default]
+    DropNewest,
+    /// Keep up to another 10240 span sets waiting for the channel. Once more are waiting, drop
+    /// the oldest ones among them, down to 5120, to make room for the spans being submitted. The
+    /// span sets already in the channel are never dropped.
+    DropOldest,
 }
 
 impl Config {
@@ -346,6 +436,53 @@ impl Config {
             ..self
         }
     }
+
+    /// Whether to aggregate the durations of the reported spans by span name, so that their
+    /// percentiles can be queried by [`latency_report()`].
+    ///
+    /// The number of span names aggregated separately is capped, so span names of high
+    /// cardinality, e.g. formatted with the arguments of a function, share a single aggregate
+    /// once the cap is reached.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    ///
+    /// let config = Config::default().latency_report(true);
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    ///
+    /// [`latency_report()`]: crate::collector::latency_report
+    pub fn latency_report(self, latency_report: bool) -> Self {
+        Self {
+            latency_report,
+            ..self
+        }
+    }
+
+    /// The policy to apply when a thread submits spans faster than the global collector
+    /// consumes them. See [`DropPolicy`] for details.
+    ///
+    /// The default value is [`DropPolicy::DropNewest`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::DropPolicy;
+    ///
+    /// let config = Config::default().drop_policy(DropPolicy::DropOldest);
+    /// minitrace::set_reporter(minitrace::collector::ConsoleReporter, config);
+    /// ```
+    pub fn drop_policy(self, drop_policy: DropPolicy) -> Self {
+        Self {
+            drop_policy,
+            ..self
+        }
+    }
 }
 
 impl Default for Config {
@@ -354,6 +491,8 @@ impl Default for Config {
             max_spans_per_trace: None,
             batch_report_interval: Duration::from_millis(500),
             batch_report_max_spans: None,
+            latency_report: false,
+            drop_policy: DropPolicy::DropNewest,
         }
     }
 }
@@ -0,0 +1,98 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::collector::Reporter;
+use crate::collector::SpanRecord;
+
+/// Reports previously collected span records to `reporter` in a single batch, as the global
+/// collector does when it is flushed. Useful to test a reporter end-to-end against a fixed
+/// trace, e.g. to compare its output with a golden file.
+///
+/// The span records are reported as given, in their order and with their ids and timing, without
+/// going through the global collector, so neither [`set_reporter()`] nor the `enable` feature is
+/// needed.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let records = vec![SpanRecord {
+///     trace_id: TraceId(1),
+///     span_id: SpanId(1),
+///     name: "root".into(),
+///     ..Default::default()
+/// }];
+///
+/// let (mut reporter, reported) = TestReporter::new();
+/// minitrace::collector::replay(records, &mut reporter);
+/// assert_eq!(reported.lock()[0].name, "root");
+/// ```
+///
+/// [`set_reporter()`]: crate::set_reporter
+pub fn replay(records: impl IntoIterator<Item = SpanRecord>, reporter: &mut impl Reporter) {
+    let records = records.into_iter().collect::<Vec<_>>();
+    reporter.report(&records);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+    use crate::collector::TraceId;
+
+    #[derive(Default)]
+    struct CapturingReporter {
+        batches: Vec<Vec<SpanRecord>>,
+    }
+
+    impl Reporter for CapturingReporter {
+        fn report(&mut self, spans: &[SpanRecord]) {
+            self.batches.push(spans.to_vec());
+        }
+    }
+
+    #[test]
+    fn replay_fixture() {
+        // root [0, 100)
+        //   get [10, 30) key=a
+        let fixture = vec![
+            SpanRecord {
+                trace_id: TraceId(7),
+                span_id: SpanId(2),
+                parent_id: SpanId(1),
+                begin_time_unix_ns: 10,
+                duration_ns: 20,
+                name: "get".into(),
+                properties: vec![("key".into(), "a".into())],
+                thread_id: 1,
+                ..SpanRecord::default()
+            },
+            SpanRecord {
+                trace_id: TraceId(7),
+                span_id: SpanId(1),
+                duration_ns: 100,
+                name: "root".into(),
+                thread_id: 1,
+                ..SpanRecord::default()
+            },
+        ];
+
+        let mut reporter = CapturingReporter::default();
+        replay(fixture.clone(), &mut reporter);
+
+        assert_eq!(reporter.batches.len(), 1);
+        let received = &reporter.batches[0];
+        assert_eq!(received.len(), fixture.len());
+        for (received, expected) in received.iter().zip(&fixture) {
+            assert_eq!(received.trace_id, expected.trace_id);
+            assert_eq!(received.span_id, expected.span_id);
+            assert_eq!(received.parent_id, expected.parent_id);
+            assert_eq!(received.begin_time_unix_ns, expected.begin_time_unix_ns);
+            assert_eq!(received.duration_ns, expected.duration_ns);
+            assert_eq!(received.name, expected.name);
+            assert_eq!(received.properties, expected.properties);
+            assert_eq!(received.thread_id, expected.thread_id);
+        }
+    }
+}
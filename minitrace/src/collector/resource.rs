@@ -0,0 +1,41 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+
+use once_cell::sync::OnceCell;
+
+static RESOURCE: OnceCell<Vec<(Cow<'static, str>, Cow<'static, str>)>> = OnceCell::new();
+
+/// Sets the attributes describing the entity producing the traces, e.g. `service.name` and
+/// `service.version`, to be attached to the exported traces by the reporters supporting it.
+///
+/// The resource can only be set once. Later calls are ignored.
+///
+/// # Examples
+///
+/// ```
+/// minitrace::set_resource([
+///     ("service.name", env!("CARGO_PKG_NAME")),
+///     ("service.version", env!("CARGO_PKG_VERSION")),
+/// ]);
+/// ```
+pub fn set_resource<K, V, I>(attributes: I)
+where
+    K: Into<Cow<'static, str>>,
+    V: Into<Cow<'static, str>>,
+    I: IntoIterator<Item = (K, V)>,
+{
+    RESOURCE.get_or_init(|| {
+        attributes
+            .into_iter()
+            .map(|(k, v)| (k.into(), v.into()))
+            .collect()
+    });
+}
+
+/// Returns the attributes set by [`set_resource()`], or an empty slice if not set.
+///
+/// [`set_resource()`]: crate::set_resource
+pub fn resource() -> &'static [(Cow<'static, str>, Cow<'static, str>)] {
+    RESOURCE.get().map(Vec::as_slice).unwrap_or_default()
+}
@@ -0,0 +1,124 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+
+/// A strategy deciding whether a trace is recorded, consulted once by
+/// [`Span::root_with_sampler()`] when the root span of the trace is created.
+///
+/// # Examples
+///
+/// ```
+/// use std::sync::atomic::AtomicBool;
+/// use std::sync::atomic::Ordering;
+///
+/// use minitrace::collector::Sampler;
+/// use minitrace::prelude::*;
+///
+/// /// Records every other trace.
+/// #[derive(Default)]
+/// struct EveryOther(AtomicBool);
+///
+/// impl Sampler for EveryOther {
+///     fn should_sample(&self, _name: &str) -> bool {
+///         !self.0.fetch_xor(true, Ordering::Relaxed)
+///     }
+/// }
+///
+/// let sampler = EveryOther::default();
+/// let root = Span::root_with_sampler("root", SpanContext::random(), &sampler);
+/// ```
+///
+/// [`Span::root_with_sampler()`]: crate::Span::root_with_sampler
+pub trait Sampler {
+    /// Returns whether the trace with a root span named `name` is recorded.
+    fn should_sample(&self, name: &str) -> bool;
+}
+
+/// A [`Sampler`] recording every trace.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AlwaysOn;
+
+impl Sampler for AlwaysOn {
+    #[inline]
+    fn should_sample(&self, _name: &str) -> bool {
+        true
+    }
+}
+
+/// A [`Sampler`] recording no trace.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct AlwaysOff;
+
+impl Sampler for AlwaysOff {
+    #[inline]
+    fn should_sample(&self, _name: &str) -> bool {
+        false
+    }
+}
+
+/// A [`Sampler`] recording a random subset of the traces, in the given ratio between `0.0` and
+/// `1.0`.
+#[derive(Clone, Copy, Debug)]
+pub struct RatioSampler {
+    ratio: f64,
+}
+
+impl RatioSampler {
+    /// Creates a sampler recording the traces with the probability of `ratio`, e.g. `0.1` to
+    /// record one in ten traces. A ratio below `0.0` or above `1.0` is treated as `0.0` or `1.0`.
+    pub fn new(ratio: f64) -> Self {
+        Self { ratio }
+    }
+}
+
+impl Sampler for RatioSampler {
+    #[inline]
+    fn should_sample(&self, _name: &str) -> bool {
+        crate::util::sample(self.ratio)
+    }
+}
+
+/// A [`Sampler`] recording the traces whose root span name starts with the given prefix.
+#[derive(Clone, Debug)]
+pub struct PrefixSampler {
+    prefix: Cow<'static, str>,
+}
+
+impl PrefixSampler {
+    /// Creates a sampler recording the traces whose root span name starts with `prefix`, e.g.
+    /// `"rpc."`.
+    pub fn new(prefix: impl Into<Cow<'static, str>>) -> Self {
+        Self {
+            prefix: prefix.into(),
+        }
+    }
+}
+
+impl Sampler for PrefixSampler {
+    #[inline]
+    fn should_sample(&self, name: &str) -> bool {
+        name.starts_with(&*self.prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn samplers() {
+        assert!(AlwaysOn.should_sample("a"));
+        assert!(!AlwaysOff.should_sample("a"));
+
+        assert!(RatioSampler::new(1.0).should_sample("a"));
+        assert!(!RatioSampler::new(0.0).should_sample("a"));
+        let sampled = (0..10000)
+            .filter(|_| RatioSampler::new(0.5).should_sample("a"))
+            .count();
+        assert!((4000..6000).contains(&sampled), "{sampled}");
+
+        let sampler = PrefixSampler::new("rpc.");
+        assert!(sampler.should_sample("rpc.get"));
+        assert!(!sampler.should_sample("get"));
+    }
+}
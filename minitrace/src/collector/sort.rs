@@ -0,0 +1,115 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use crate::collector::SpanRecord;
+
+/// The order of the span records returned by [`collect_sorted_by()`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    /// In the order the spans started, as given by [`SpanRecord::begin_time_unix_ns`].
+    Start,
+    /// In the order the spans ended, as given by the sum of
+    /// [`SpanRecord::begin_time_unix_ns`] and [`SpanRecord::duration_ns`].
+    End,
+    /// In the order of [`SpanRecord::trace_id`], then of [`SpanRecord::span_id`].
+    Id,
+}
+
+/// Collects span records into a `Vec` sorted by `key`, so that exporters expecting a given order
+/// get it regardless of the order in which the spans were reported.
+///
+/// The sort is stable: span records comparing equal keep their relative order.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::SortKey;
+/// use minitrace::collector::TestReporter;
+/// use minitrace::prelude::*;
+///
+/// let (reporter, records) = TestReporter::new();
+/// minitrace::set_reporter(reporter, Config::default());
+///
+/// {
+///     let root = Span::root("root", SpanContext::random());
+///     let _g = root.set_local_parent();
+///     let _span = LocalSpan::enter_with_local_parent("child");
+/// }
+/// minitrace::flush();
+///
+/// let records = minitrace::collector::collect_sorted_by(records.lock().clone(), SortKey::End);
+/// assert_eq!(records.last().unwrap().name, "root");
+/// ```
+pub fn collect_sorted_by(
+    records: impl IntoIterator<Item = SpanRecord>,
+    key: SortKey,
+) -> Vec<SpanRecord> {
+    let mut records = records.into_iter().collect::<Vec<_>>();
+    match key {
+        SortKey::Start => records.sort_by_key(|record| record.begin_time_unix_ns),
+        SortKey::End => records.sort_by_key(|record| {
+            record
+                .begin_time_unix_ns
+                .saturating_add(record.duration_ns)
+        }),
+        SortKey::Id => records.sort_by_key(|record| (record.trace_id.0, record.span_id.0)),
+    }
+    records
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::collector::SpanId;
+    use crate::collector::TraceId;
+
+    fn record(span_id: u64, begin_time_unix_ns: u64, duration_ns: u64) -> SpanRecord {
+        SpanRecord {
+            trace_id: TraceId(1),
+            span_id: SpanId(span_id),
+            begin_time_unix_ns,
+            duration_ns,
+            name: format!("span{span_id}").into(),
+            ..SpanRecord::default()
+        }
+    }
+
+    #[test]
+    fn sort_keys() {
+        // span1 [0, 100)
+        //   span3 [10, 20)
+        //   span2 [15, 60)
+        //     span4 [30, 40)
+        let records = vec![
+            record(4, 30, 10),
+            record(2, 15, 45),
+            record(1, 0, 100),
+            record(3, 10, 10),
+        ];
+        let names = |key| {
+            collect_sorted_by(records.clone(), key)
+                .into_iter()
+                .map(|record| record.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(SortKey::Start), ["span1", "span3", "span2", "span4"]);
+        assert_eq!(names(SortKey::End), ["span3", "span4", "span2", "span1"]);
+        assert_eq!(names(SortKey::Id), ["span1", "span2", "span3", "span4"]);
+    }
+
+    #[test]
+    fn stable() {
+        let records = vec![record(2, 10, 10), record(1, 10, 10)];
+        let ids = |key| {
+            collect_sorted_by(records.clone(), key)
+                .into_iter()
+                .map(|record| record.span_id.0)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(ids(SortKey::Start), [2, 1]);
+        assert_eq!(ids(SortKey::End), [2, 1]);
+        assert_eq!(ids(SortKey::Id), [1, 2]);
+    }
+}
@@ -0,0 +1,54 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+
+use crate::collector::SpanId;
+use crate::collector::SpanRecord;
+use crate::collector::TraceId;
+
+/// The span records arranged as trees by their parent ids, with children ordered by begin time.
+///
+/// A span whose parent is not among the records is a root.
+pub(crate) struct SpanTree<'a> {
+    pub records: &'a [SpanRecord],
+    pub roots: Vec<usize>,
+    children: HashMap<(TraceId, SpanId), Vec<usize>>,
+}
+
+impl<'a> SpanTree<'a> {
+    pub fn new(records: &'a [SpanRecord]) -> Self {
+        let mut order = (0..records.len()).collect::<Vec<_>>();
+        order.sort_by_key(|&i| records[i].begin_time_unix_ns);
+
+        let ids = records
+            .iter()
+            .map(|record| (record.trace_id, record.span_id))
+            .collect::<HashSet<_>>();
+
+        let mut roots = Vec::new();
+        let mut children = HashMap::<_, Vec<_>>::new();
+        for i in order {
+            let parent = (records[i].trace_id, records[i].parent_id);
+            if records[i].parent_id != records[i].span_id && ids.contains(&parent) {
+                children.entry(parent).or_default().push(i);
+            } else {
+                roots.push(i);
+            }
+        }
+
+        SpanTree {
+            records,
+            roots,
+            children,
+        }
+    }
+
+    pub fn children(&self, i: usize) -> &[usize] {
+        let record = &self.records[i];
+        self.children
+            .get(&(record.trace_id, record.span_id))
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
@@ -0,0 +1,175 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::io::Stderr;
+use std::io::Write;
+use std::time::Duration;
+
+use super::global_collector::Reporter;
+use super::span_tree::SpanTree;
+use super::SpanRecord;
+
+/// A reporter that prints the span records of each trace as an indented tree, useful for
+/// examples and local debugging.
+///
+/// Each span is printed on its own line with its duration and properties, indented under its
+/// parent span. The trees are printed to the stderr unless another writer is given by
+/// [`TreeReporter::with_writer()`].
+///
+/// ```text
+/// root (1.2ms)
+///     child (300µs) key=value read.bytes=1572864
+/// ```
+///
+/// If spans were dropped because they were submitted faster than they were collected, their
+/// number is printed before the next trees, e.g. `(42 spans dropped)`.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::collector::Config;
+/// use minitrace::collector::TreeReporter;
+///
+/// minitrace::set_reporter(TreeReporter::new(), Config::default());
+/// ```
+pub struct TreeReporter<W = Stderr> {
+    writer: W,
+    format_bytes: bool,
+}
+
+impl TreeReporter {
+    /// Creates a reporter printing to the stderr.
+    pub fn new() -> Self {
+        Self::with_writer(std::io::stderr())
+    }
+}
+
+impl Default for TreeReporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<W: Write + Send + 'static> TreeReporter<W> {
+    /// Creates a reporter printing to `writer`.
+    pub fn with_writer(writer: W) -> Self {
+        TreeReporter {
+            writer,
+            format_bytes: false,
+        }
+    }
+
+    /// Whether to print a property whose key ends with `.bytes` or `_bytes` and whose value is an
+    /// integer as a byte size in binary units, e.g. `read.bytes=1.5 MiB` rather than
+    /// `read.bytes=1572864`. The span records themselves keep the raw integer, so the other
+    /// reporters are unaffected.
+    ///
+    /// The default value is `false`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::TreeReporter;
+    ///
+    /// let reporter = TreeReporter::new().format_bytes(true);
+    /// minitrace::set_reporter(reporter, Config::default());
+    /// ```
+    pub fn format_bytes(self, format_bytes: bool) -> Self {
+        Self {
+            format_bytes,
+            ..self
+        }
+    }
+
+    fn write_span(&mut self, tree: &SpanTree, i: usize, depth: usize) -> std::io::Result<()> {
+        let record = &tree.records[i];
+        write!(
+            self.writer,
+            "{:indent$}{} ({:?})",
+            "",
+            record.name,
+            Duration::from_nanos(record.duration_ns),
+            indent = depth * 4
+        )?;
+        for (k, v) in &record.properties {
+            match v
+                .parse()
+                .ok()
+                .filter(|_| self.format_bytes && is_bytes_key(k))
+            {
+                Some(bytes) => write!(self.writer, " {k}={}", format_bytes(bytes))?,
+                None => write!(self.writer, " {k}={v}")?,
+            }
+        }
+        writeln!(self.writer)?;
+
+        for &child in tree.children(i) {
+            self.write_span(tree, child, depth + 1)?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write + Send + 'static> Reporter for TreeReporter<W> {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        let tree = SpanTree::new(spans);
+        for &root in &tree.roots {
+            self.write_span(&tree, root, 0).ok();
+        }
+        self.writer.flush().ok();
+    }
+
+    fn report_dropped(&mut self, count: usize) {
+        writeln!(self.writer, "({count} spans dropped)").ok();
+    }
+}
+
+fn is_bytes_key(key: &str) -> bool {
+    key.ends_with(".bytes") || key.ends_with("_bytes")
+}
+
+/// Formats a byte size in binary units with one decimal, e.g. `1.5 MiB`.
+fn format_bytes(bytes: u64) -> String {
+    const UNITS: [&str; 6] = ["KiB", "MiB", "GiB", "TiB", "PiB", "EiB"];
+
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(1024), "1.0 KiB");
+        assert_eq!(format_bytes(1536), "1.5 KiB");
+        assert_eq!(format_bytes(3 << 19), "1.5 MiB");
+        assert_eq!(format_bytes(5 << 30), "5.0 GiB");
+        assert_eq!(format_bytes(u64::MAX), "16.0 EiB");
+
+        assert!(is_bytes_key("read.bytes"));
+        assert!(is_bytes_key("body_bytes"));
+        assert!(!is_bytes_key("bytes"));
+    }
+
+    #[test]
+    fn dropped() {
+        let mut reporter = TreeReporter::with_writer(Vec::new());
+        reporter.report_dropped(42);
+        assert_eq!(
+            String::from_utf8(reporter.writer).unwrap(),
+            "(42 spans dropped)\n"
+        );
+    }
+}
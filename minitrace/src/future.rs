@@ -2,9 +2,9 @@
 
 //! This module provides tools to trace a `Future`.
 //!
-//! The [`FutureExt`] trait extends `Future` with two methods: [`in_span()`] and
-//! [`enter_on_poll()`]. It is crucial that the outermost future uses `in_span()`,
-//! otherwise, the traces inside the `Future` will be lost.
+//! The [`FutureExt`] trait extends `Future` with three methods: [`in_span()`],
+//! [`in_lazy_span()`] and [`enter_on_poll()`]. It is crucial that the outermost future uses
+//! `in_span()`, otherwise, the traces inside the `Future` will be lost.
 //!
 //! # Example
 //!
@@ -28,6 +28,7 @@
 //! ```
 //!
 //! [`in_span()`]:(FutureExt::in_span)
+//! [`in_lazy_span()`]:(FutureExt::in_lazy_span)
 //! [`enter_on_poll()`]:(FutureExt::enter_on_poll)
 
 use std::borrow::Cow;
@@ -73,6 +74,41 @@ pub trait FutureExt: std::future::Future + Sized {
         }
     }
 
+    /// Binds a [`Span`] to the [`Future`] like [`in_span()`](FutureExt::in_span), but defers
+    /// the creation of the span until the future is polled for the first time.
+    ///
+    /// The span is created with [`Span::enter_with_local_parent()`] at the first poll, so it
+    /// attaches to the local parent of the poller rather than the one at construction. A future
+    /// that is dropped without ever being polled records no span at all.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// # #[tokio::main]
+    /// # async fn main() {
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("Root", SpanContext::random());
+    /// let task = async {
+    ///     // ...
+    /// }
+    /// .in_lazy_span("Task");
+    ///
+    /// tokio::spawn(task.in_span(root));
+    /// # }
+    /// ```
+    ///
+    /// [`Future`]:(std::future::Future)
+    /// [`Span::enter_with_local_parent()`](Span::enter_with_local_parent)
+    #[inline]
+    fn in_lazy_span(self, name: impl Into<Cow<'static, str>>) -> InLazySpan<Self> {
+        InLazySpan {
+            inner: self,
+            name: Some(name.into()),
+            span: None,
+        }
+    }
+
     /// Starts a [`LocalSpan`] at every [`Future::poll()`]. If the future gets polled multiple
     /// times, it will create multiple _short_ spans.
     ///
@@ -134,6 +170,42 @@ impl<T: std::future::Future> std::future::Future for InSpan<T> {
     }
 }
 
+/// Adapter for [`FutureExt::in_lazy_span()`](FutureExt::in_lazy_span).
+#[pin_project::pin_project]
+pub struct InLazySpan<T> {
+    #[pin]
+    inner: T,
+    name: Option<Cow<'static, str>>,
+    span: Option<Span>,
+}
+
+impl<T: std::future::Future> std::future::Future for InLazySpan<T> {
+    type Output = T::Output;
+
+    fn poll(self: std::pin::Pin<&mut Self>, cx: &mut std::task::Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+
+        if let Some(name) = this.name.take() {
+            *this.span = Some(Span::enter_with_local_parent(
+                name,
+                #[cfg(test)]
+                Default::default(),
+            ));
+        }
+
+        let _guard = this.span.as_ref().map(|s| s.set_local_parent());
+        let res = this.inner.poll(cx);
+
+        match res {
+            r @ Poll::Pending => r,
+            other => {
+                this.span.take();
+                other
+            }
+        }
+    }
+}
+
 /// Adapter for [`FutureExt::enter_on_poll()`](FutureExt::enter_on_poll).
 #[pin_project::pin_project]
 pub struct EnterOnPoll<T> {
@@ -66,6 +66,26 @@
 //! minitrace = { version = "0.6", features = ["enable"] }
 //! ```
 //!
+//! By default, span ids are allocated sequentially per thread. Set the `random-span-id` feature
+//! to generate pseudo-random span ids instead, as recommended by OpenTelemetry, so that the ids
+//! correlate well with other tracing tools. Together with [`SpanContext::random()`], which
+//! generates a random 128-bit trace id, the root span then carries OpenTelemetry-compatible ids.
+//!
+//! Set the `chrome-trace` feature to export span records as a JSON trace with
+//! `collector::to_chrome_trace()`, which can be opened in `chrome://tracing` or Perfetto.
+//!
+//! Set the `csv` feature to export span records as CSV with `collector::to_csv()` for
+//! analysis in a spreadsheet.
+//!
+//! Set the `flamegraph` feature to render span records as a flamegraph SVG with
+//! `collector::to_flamegraph_svg()`.
+//!
+//! Set the `tokio` feature to record the id of the current tokio task as the `tokio.task_id`
+//! property of the spans created by [`trace`], so that spans can be correlated by task.
+//!
+//! Set the `timestamp` feature to support `#[trace(timestamp = true)]`, which records the
+//! wall-clock start time of a span as an RFC 3339 string.
+//!
 //! Executables should initialize a reporter implementation early in the program's runtime.
 //! Span records generated before the implementation is initialized will be ignored. Before
 //! terminating, the reporter should be flushed to ensure all span records are reported.
@@ -314,22 +334,27 @@
 //! `minitrace` is designed to be fast and lightweight, considering four scenarios:
 //!
 //! - **No Tracing**: `minitrace` is not included as dependency in the executable, while the
-//! libraries has been instrumented. In this case, it will be completely removed from libraries,
-//! causing zero overhead.
+//!   libraries has been instrumented. In this case, it will be completely removed from libraries,
+//!   causing zero overhead.
 //!
 //! - **Sample Tracing**: `minitrace` is enabled in the executable, but only a small portion
-//! of the traces are enabled via [`Span::root()`], while the other portion start with placeholders
-//! by [`Span::noop()`]. The overhead in this case is very small - merely an integer
-//! load, comparison, and jump.
+//!   of the traces are enabled via [`Span::root()`], while the other portion start with placeholders
+//!   by [`Span::noop()`]. The overhead in this case is very small - merely an integer
+//!   load, comparison, and jump.
 //!
 //! - **Full Tracing with Tail Sampling**: `minitrace` is enabled in the executable, and all
-//! traces are enabled. However, only a select few abnormal tracing records (e.g., P99) are
-//! reported. Normal traces can be dismissed by using [`Span::cancel()`] to avoid reporting.
-//! This could be useful when you are interested in examining program's tail latency.
+//!   traces are enabled. However, only a select few abnormal tracing records (e.g., P99) are
+//!   reported. Normal traces can be dismissed by using [`Span::cancel()`] to avoid reporting.
+//!   This could be useful when you are interested in examining program's tail latency.
 //!
 //! - **Full Tracing**: `minitrace` is enabled in the executable, and all traces are enabled.
-//! All tracing records are reported. `minitrace` performs 10x to 100x faster than other tracing
-//! libraries in this case.
+//!   All tracing records are reported. `minitrace` performs 10x to 100x faster than other tracing
+//!   libraries in this case.
+//!
+//! Recording never takes a lock on the hot path. [`LocalSpan`]s are pushed into a thread-local
+//! buffer, and when the local parent finishes, the whole batch is handed to a per-thread
+//! lock-free ring buffer. A background thread drains these buffers periodically and assembles
+//! the [`SpanRecord`]s, so threads recording concurrently do not contend with each other.
 //!
 //!
 //! [`Span`]: crate::Span
@@ -341,6 +366,7 @@
 //! [`Span::root()`]: crate::Span::root
 //! [`Span::noop()`]: crate::Span::noop
 //! [`Span::cancel()`]: crate::Span::cancel
+//! [`SpanContext::random()`]: crate::collector::SpanContext::random
 //! [`Span::enter_with_parent()`]: crate::Span::enter_with_parent
 //! [`Span::set_local_parent()`]: crate::Span::set_local_parent
 //! [`LocalSpan::enter_with_local_parent()`]: crate::local::LocalSpan::enter_with_local_parent
@@ -356,22 +382,28 @@
 #![cfg_attr(not(feature = "enable"), allow(unused_mut))]
 #![cfg_attr(not(feature = "enable"), allow(unused_imports))]
 #![cfg_attr(not(feature = "enable"), allow(unused_variables))]
+#![cfg_attr(not(feature = "enable"), allow(clippy::ptr_arg))]
 
 pub mod collector;
 mod event;
 pub mod future;
 pub mod local;
 mod macros;
+mod scope;
 mod span;
 #[doc(hidden)]
 pub mod util;
 
+pub use minitrace_macro::main;
 pub use minitrace_macro::trace;
 
 pub use crate::collector::global_collector::flush;
 pub use crate::collector::global_collector::set_reporter;
+pub use crate::collector::resource::set_resource;
 pub use crate::event::Event;
+pub use crate::scope::Scope;
 pub use crate::span::Span;
+pub use crate::util::install_panic_hook;
 
 pub mod prelude {
     //! A "prelude" for crates using `minitrace`.
@@ -390,6 +422,8 @@ pub mod prelude {
     #[doc(no_inline)]
     pub use crate::local::LocalSpan;
     #[doc(no_inline)]
+    pub use crate::scope::Scope;
+    #[doc(no_inline)]
     pub use crate::span::Span;
     #[doc(no_inline)]
     pub use crate::trace;
@@ -96,6 +96,10 @@ pub struct LocalSpansInner {
 }
 
 impl LocalCollector {
+    /// Starts collecting [`LocalSpan`]s recorded on the current thread into a thread-local
+    /// buffer.
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
     pub fn start() -> Self {
         #[cfg(not(feature = "enable"))]
         {
@@ -110,6 +114,9 @@ impl LocalCollector {
         }
     }
 
+    /// Drains the [`LocalSpan`]s recorded since [`LocalCollector::start()`].
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
     pub fn collect(self) -> LocalSpans {
         #[cfg(not(feature = "enable"))]
         {
@@ -130,10 +137,20 @@ impl LocalCollector {
     pub(crate) fn new(
         collect_token: Option<CollectToken>,
         stack: Rc<RefCell<LocalSpanStack>>,
+    ) -> Self {
+        Self::new_with_parent_name(collect_token, None, stack)
+    }
+
+    /// Starts collecting like [`LocalCollector::new()`], recording the static name of the `Span`
+    /// whose local spans are collected.
+    pub(crate) fn new_with_parent_name(
+        collect_token: Option<CollectToken>,
+        parent_name: Option<&'static str>,
+        stack: Rc<RefCell<LocalSpanStack>>,
     ) -> Self {
         let span_line_epoch = {
             let stack = &mut (*stack).borrow_mut();
-            stack.register_span_line(collect_token)
+            stack.register_span_line(collect_token, parent_name)
         };
 
         Self {
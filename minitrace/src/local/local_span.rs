@@ -3,7 +3,9 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
+use crate::collector::SpanStatus;
 use crate::local::local_span_line::LocalSpanHandle;
 use crate::local::local_span_stack::LocalSpanStack;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
@@ -21,6 +23,8 @@ pub struct LocalSpan {
 struct LocalSpanInner {
     stack: Rc<RefCell<LocalSpanStack>>,
     span_handle: LocalSpanHandle,
+    min_duration: Option<Duration>,
+    coalesce: bool,
 }
 
 impl LocalSpan {
@@ -54,6 +58,83 @@ impl LocalSpan {
         }
     }
 
+    /// Add a single property to the current local parent span.
+    ///
+    /// This is useful to record information that is only known after the span has started,
+    /// e.g. the outcome of an operation. If no local parent is set, this function is no-op.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// LocalSpan::add_property(|| ("key", "value"));
+    /// ```
+    #[inline]
+    pub fn add_property<K, V, F>(property: F)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        F: FnOnce() -> (K, V),
+    {
+        Self::add_properties(|| [property()])
+    }
+
+    /// Add multiple properties to the current local parent span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// LocalSpan::add_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// ```
+    #[inline]
+    pub fn add_properties<K, V, I, F>(properties: F)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| stack.borrow_mut().add_parent_properties(properties))
+                .ok();
+        }
+    }
+
+    /// Set the status of the current local parent span. If no local parent is set, this function
+    /// is no-op.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::SpanStatus;
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// LocalSpan::set_status(SpanStatus::Error);
+    /// ```
+    #[inline]
+    pub fn set_status(status: SpanStatus) {
+        #[cfg(feature = "enable")]
+        {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| stack.borrow_mut().set_parent_status(status))
+                .ok();
+        }
+    }
+
     /// Add a single property to the `LocalSpan` and return the modified `LocalSpan`.
     ///
     /// A property is an arbitrary key-value pair associated with a span.
@@ -95,13 +176,135 @@ impl LocalSpan {
         F: FnOnce() -> I,
     {
         #[cfg(feature = "enable")]
-        if let Some(LocalSpanInner { stack, span_handle }) = &self.inner {
+        if let Some(LocalSpanInner {
+            stack, span_handle, ..
+        }) = &self.inner
+        {
             let span_stack = &mut *stack.borrow_mut();
             span_stack.add_properties(span_handle, properties);
         }
 
         self
     }
+
+    /// Reserve capacity for at least `capacity` more properties on the `LocalSpan` and return
+    /// the modified `LocalSpan`.
+    ///
+    /// This avoids reallocations when the number of properties is known in advance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = LocalSpan::enter_with_local_parent("a child span")
+    ///     .with_properties_capacity(2)
+    ///     .with_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// ```
+    #[inline]
+    pub fn with_properties_capacity(self, capacity: usize) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(LocalSpanInner {
+            stack, span_handle, ..
+        }) = &self.inner
+        {
+            let span_stack = &mut *stack.borrow_mut();
+            span_stack.reserve_properties(span_handle, capacity);
+        }
+
+        self
+    }
+
+    /// Return the number of the child spans recorded under the `LocalSpan` so far.
+    ///
+    /// Only the child spans recorded on the current thread, e.g. by [`LocalSpan`], are counted.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// let span = LocalSpan::enter_with_local_parent("parent");
+    /// for _ in 0..3 {
+    ///     let _child = LocalSpan::enter_with_local_parent("child");
+    /// }
+    /// let count = span.child_count();
+    /// ```
+    #[inline]
+    pub fn child_count(&self) -> usize {
+        #[cfg(not(feature = "enable"))]
+        {
+            0
+        }
+
+        #[cfg(feature = "enable")]
+        match &self.inner {
+            Some(LocalSpanInner {
+                stack, span_handle, ..
+            }) => stack.borrow_mut().child_count(span_handle),
+            None => 0,
+        }
+    }
+
+    /// Discard the `LocalSpan`, along with all of its child spans and events, if it lasts shorter
+    /// than `min_duration`, and return the modified `LocalSpan`.
+    ///
+    /// This is useful to reduce the noise of frequent and fast operations.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use std::time::Duration;
+    ///
+    /// use minitrace::prelude::*;
+    ///
+    /// let span = LocalSpan::enter_with_local_parent("a child span")
+    ///     .with_min_duration(Duration::from_millis(1));
+    /// ```
+    #[inline]
+    pub fn with_min_duration(mut self, min_duration: Duration) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = &mut self.inner {
+            inner.min_duration = Some(min_duration);
+        }
+
+        self
+    }
+
+    /// Merge the `LocalSpan` into the previous span when both are leaf spans of the same name
+    /// and the same parent, both marked by this function, and nothing is recorded between them,
+    /// and return the modified `LocalSpan`. The merged span lasts from the beginning of the first
+    /// span to the end of the last one, and records the number of spans merged into it as the
+    /// `count` property.
+    ///
+    /// This is useful to keep a single span for an operation repeated in a tight loop. It has no
+    /// effect together with [`LocalSpan::with_min_duration()`].
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// for _ in 0..100 {
+    ///     // Recorded as a single span with the property `count` set to `100`.
+    ///     let _span = LocalSpan::enter_with_local_parent("step").with_coalescing();
+    /// }
+    /// ```
+    #[inline]
+    pub fn with_coalescing(mut self) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = &mut self.inner {
+            inner.coalesce = true;
+        }
+
+        self
+    }
 }
 
 #[cfg(feature = "enable")]
@@ -116,7 +319,12 @@ impl LocalSpan {
             stack.enter_span(name)
         };
 
-        let inner = span_handle.map(|span_handle| LocalSpanInner { stack, span_handle });
+        let inner = span_handle.map(|span_handle| LocalSpanInner {
+            stack,
+            span_handle,
+            min_duration: None,
+            coalesce: false,
+        });
 
         Self { inner }
     }
@@ -126,9 +334,21 @@ impl Drop for LocalSpan {
     #[inline]
     fn drop(&mut self) {
         #[cfg(feature = "enable")]
-        if let Some(LocalSpanInner { stack, span_handle }) = self.inner.take() {
+        if let Some(LocalSpanInner {
+            stack,
+            span_handle,
+            min_duration,
+            coalesce,
+        }) = self.inner.take()
+        {
             let mut span_stack = stack.borrow_mut();
-            span_stack.exit_span(span_handle);
+            match min_duration {
+                Some(min_duration) => {
+                    span_stack.exit_span_with_min_duration(span_handle, min_duration)
+                }
+                None if coalesce => span_stack.exit_span_coalesced(span_handle),
+                None => span_stack.exit_span(span_handle),
+            }
         }
     }
 }
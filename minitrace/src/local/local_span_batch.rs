@@ -0,0 +1,105 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::borrow::Cow;
+
+use minstant::Instant;
+
+use crate::local::local_span_stack::LOCAL_SPAN_STACK;
+
+/// A buffer to record many short [`LocalSpan`]s, e.g. in a tight loop, at a lower cost.
+///
+/// Unlike [`LocalSpan`], entering a span of a batch does not touch the local span stack of
+/// the thread. Instead, the spans are kept in the batch and added as children of the current
+/// local parent all at once when the batch is dropped. As a consequence, the spans of a batch
+/// can not have child spans. If no local parent is set when the batch is dropped, the spans
+/// are discarded.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::local::LocalSpanBatch;
+/// use minitrace::prelude::*;
+///
+/// let root = Span::root("root", SpanContext::random());
+/// let _g = root.set_local_parent();
+///
+/// let mut batch = LocalSpanBatch::with_capacity(100);
+/// for _ in 0..100 {
+///     let _guard = batch.enter("step");
+///     // ...
+/// }
+/// ```
+///
+/// [`LocalSpan`]: crate::local::LocalSpan
+#[must_use]
+#[derive(Default)]
+pub struct LocalSpanBatch {
+    #[cfg(feature = "enable")]
+    spans: Vec<(Cow<'static, str>, Instant, Instant)>,
+}
+
+/// A guard of a span in a [`LocalSpanBatch`]. The span ends when the guard is dropped.
+#[must_use]
+pub struct LocalSpanBatchGuard<'a> {
+    #[cfg(feature = "enable")]
+    batch: &'a mut LocalSpanBatch,
+    #[cfg(not(feature = "enable"))]
+    _batch: std::marker::PhantomData<&'a mut LocalSpanBatch>,
+}
+
+impl LocalSpanBatch {
+    /// Creates a batch with room for `capacity` spans without reallocation.
+    #[inline]
+    pub fn with_capacity(capacity: usize) -> Self {
+        #[cfg(not(feature = "enable"))]
+        {
+            LocalSpanBatch::default()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            LocalSpanBatch {
+                spans: Vec::with_capacity(capacity),
+            }
+        }
+    }
+
+    /// Starts a span in the batch, which ends when the returned guard is dropped.
+    #[inline]
+    pub fn enter(&mut self, name: impl Into<Cow<'static, str>>) -> LocalSpanBatchGuard<'_> {
+        #[cfg(not(feature = "enable"))]
+        {
+            LocalSpanBatchGuard {
+                _batch: std::marker::PhantomData,
+            }
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            let now = Instant::now();
+            self.spans.push((name.into(), now, now));
+            LocalSpanBatchGuard { batch: self }
+        }
+    }
+}
+
+impl Drop for LocalSpanBatchGuard<'_> {
+    #[inline]
+    fn drop(&mut self) {
+        #[cfg(feature = "enable")]
+        if let Some((_, _, end)) = self.batch.spans.last_mut() {
+            *end = Instant::now();
+        }
+    }
+}
+
+impl Drop for LocalSpanBatch {
+    fn drop(&mut self) {
+        #[cfg(feature = "enable")]
+        if !self.spans.is_empty() {
+            LOCAL_SPAN_STACK
+                .try_with(|stack| stack.borrow_mut().add_spans(self.spans.drain(..)))
+                .ok();
+        }
+    }
+}
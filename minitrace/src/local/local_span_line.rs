@@ -1,8 +1,12 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::borrow::Cow;
+use std::time::Duration;
+
+use minstant::Instant;
 
 use crate::collector::CollectTokenItem;
+use crate::collector::SpanStatus;
 use crate::local::span_queue::SpanHandle;
 use crate::local::span_queue::SpanQueue;
 use crate::util::CollectToken;
@@ -12,6 +16,8 @@ pub struct SpanLine {
     span_queue: SpanQueue,
     epoch: usize,
     collect_token: Option<CollectToken>,
+    /// The name of the `Span` whose local spans are collected by the span line, if it is static.
+    parent_name: Option<&'static str>,
 }
 
 impl SpanLine {
@@ -24,6 +30,7 @@ impl SpanLine {
             span_queue: SpanQueue::with_capacity(capacity),
             epoch: span_line_epoch,
             collect_token,
+            parent_name: None,
         }
     }
 
@@ -47,6 +54,25 @@ impl SpanLine {
         }
     }
 
+    #[inline]
+    pub fn finish_span_with_min_duration(
+        &mut self,
+        handle: LocalSpanHandle,
+        min_duration: Duration,
+    ) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue
+                .finish_span_with_min_duration(handle.span_handle, min_duration);
+        }
+    }
+
+    #[inline]
+    pub fn finish_span_coalesced(&mut self, handle: LocalSpanHandle) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue.finish_span_coalesced(handle.span_handle);
+        }
+    }
+
     #[inline]
     pub fn add_event<I, F>(&mut self, name: impl Into<Cow<'static, str>>, properties: F)
     where
@@ -70,6 +96,47 @@ impl SpanLine {
         }
     }
 
+    #[inline]
+    pub fn child_count(&self, handle: &LocalSpanHandle) -> usize {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue.child_count(&handle.span_handle)
+        } else {
+            0
+        }
+    }
+
+    #[inline]
+    pub fn reserve_properties(&mut self, handle: &LocalSpanHandle, additional: usize) {
+        if self.epoch == handle.span_line_epoch {
+            self.span_queue
+                .reserve_properties(&handle.span_handle, additional);
+        }
+    }
+
+    #[inline]
+    pub fn add_spans(
+        &mut self,
+        spans: impl IntoIterator<Item = (Cow<'static, str>, Instant, Instant)>,
+    ) {
+        self.span_queue.add_spans(spans);
+    }
+
+    #[inline]
+    pub fn add_parent_properties<K, V, I, F>(&mut self, properties: F)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        self.span_queue.add_parent_properties(properties());
+    }
+
+    #[inline]
+    pub fn set_parent_status(&mut self, status: SpanStatus) {
+        self.span_queue.set_parent_status(status);
+    }
+
     #[inline]
     pub fn current_collect_token(&self) -> Option<CollectToken> {
         self.collect_token.as_ref().map(|collect_token| {
@@ -85,6 +152,35 @@ impl SpanLine {
         })
     }
 
+    #[inline]
+    pub fn set_parent_name(&mut self, span_line_epoch: usize, name: &'static str) {
+        if self.epoch == span_line_epoch {
+            self.parent_name = Some(name);
+        }
+    }
+
+    /// Returns the collect token for a child of the nearest enclosing span named `name`: an
+    /// unfinished local span, or else the `Span` whose local spans are collected.
+    pub fn ancestor_collect_token(&self, name: &str) -> Option<CollectToken> {
+        let parent_id = match self.span_queue.ancestor_span_id(name) {
+            Some(span_id) => Some(span_id),
+            None if self.parent_name == Some(name) => None,
+            None => return None,
+        };
+
+        self.collect_token.as_ref().map(|collect_token| {
+            collect_token
+                .iter()
+                .map(|item| CollectTokenItem {
+                    trace_id: item.trace_id,
+                    parent_id: parent_id.unwrap_or(item.parent_id),
+                    collect_id: item.collect_id,
+                    is_root: false,
+                })
+                .collect()
+        })
+    }
+
     #[inline]
     pub fn collect(self, span_line_epoch: usize) -> Option<(RawSpans, Option<CollectToken>)> {
         (self.epoch == span_line_epoch)
@@ -3,7 +3,11 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::time::Duration;
 
+use minstant::Instant;
+
+use crate::collector::SpanStatus;
 use crate::local::local_span_line::LocalSpanHandle;
 use crate::local::local_span_line::SpanLine;
 use crate::util::CollectToken;
@@ -49,6 +53,32 @@ impl LocalSpanStack {
         }
     }
 
+    #[inline]
+    pub fn exit_span_with_min_duration(
+        &mut self,
+        local_span_handle: LocalSpanHandle,
+        min_duration: Duration,
+    ) {
+        if let Some(span_line) = self.current_span_line() {
+            debug_assert_eq!(
+                span_line.span_line_epoch(),
+                local_span_handle.span_line_epoch
+            );
+            span_line.finish_span_with_min_duration(local_span_handle, min_duration);
+        }
+    }
+
+    #[inline]
+    pub fn exit_span_coalesced(&mut self, local_span_handle: LocalSpanHandle) {
+        if let Some(span_line) = self.current_span_line() {
+            debug_assert_eq!(
+                span_line.span_line_epoch(),
+                local_span_handle.span_line_epoch
+            );
+            span_line.finish_span_coalesced(local_span_handle);
+        }
+    }
+
     #[inline]
     pub fn add_event<I, F>(&mut self, name: impl Into<Cow<'static, str>>, properties: F)
     where
@@ -67,9 +97,13 @@ impl LocalSpanStack {
     ///
     /// [`LocalSpanStack::unregister_and_collect`](LocalSpanStack::unregister_and_collect)
     #[inline]
+    /// Registers a span line, recording the static name of the `Span` whose local spans it
+    /// collects, if any, so that its descendants can find it by
+    /// [`LocalSpanStack::ancestor_collect_token()`].
     pub fn register_span_line(
         &mut self,
         collect_token: Option<CollectToken>,
+        parent_name: Option<&'static str>,
     ) -> Option<SpanLineHandle> {
         if self.span_lines.len() >= self.capacity {
             return None;
@@ -78,7 +112,10 @@ impl LocalSpanStack {
         let epoch = self.next_span_line_epoch;
         self.next_span_line_epoch = self.next_span_line_epoch.wrapping_add(1);
 
-        let span_line = SpanLine::new(DEFAULT_SPAN_QUEUE_SIZE, epoch, collect_token);
+        let mut span_line = SpanLine::new(DEFAULT_SPAN_QUEUE_SIZE, epoch, collect_token);
+        if let Some(parent_name) = parent_name {
+            span_line.set_parent_name(epoch, parent_name);
+        }
         self.span_lines.push(span_line);
         Some(SpanLineHandle {
             span_line_epoch: epoch,
@@ -115,11 +152,71 @@ impl LocalSpanStack {
         }
     }
 
+    #[inline]
+    pub fn child_count(&mut self, local_span_handle: &LocalSpanHandle) -> usize {
+        match self.current_span_line() {
+            Some(span_line) => span_line.child_count(local_span_handle),
+            None => 0,
+        }
+    }
+
+    #[inline]
+    pub fn reserve_properties(&mut self, local_span_handle: &LocalSpanHandle, additional: usize) {
+        debug_assert!(self.current_span_line().is_some());
+        if let Some(span_line) = self.current_span_line() {
+            debug_assert_eq!(
+                span_line.span_line_epoch(),
+                local_span_handle.span_line_epoch
+            );
+            span_line.reserve_properties(local_span_handle, additional);
+        }
+    }
+
+    #[inline]
+    pub fn add_spans(
+        &mut self,
+        spans: impl IntoIterator<Item = (Cow<'static, str>, Instant, Instant)>,
+    ) {
+        if let Some(span_line) = self.current_span_line() {
+            span_line.add_spans(spans);
+        }
+    }
+
+    #[inline]
+    pub fn add_parent_properties<K, V, I, F>(&mut self, properties: F)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+        F: FnOnce() -> I,
+    {
+        if let Some(span_line) = self.current_span_line() {
+            span_line.add_parent_properties(properties);
+        }
+    }
+
+    #[inline]
+    pub fn set_parent_status(&mut self, status: SpanStatus) {
+        if let Some(span_line) = self.current_span_line() {
+            span_line.set_parent_status(status);
+        }
+    }
+
     pub fn current_collect_token(&mut self) -> Option<CollectToken> {
         let span_line = self.current_span_line()?;
         span_line.current_collect_token()
     }
 
+    pub fn ancestor_collect_token(&mut self, name: &str) -> Option<CollectToken> {
+        let span_line = self.current_span_line()?;
+        span_line.ancestor_collect_token(name)
+    }
+
+    #[inline]
+    pub fn has_span_line(&self) -> bool {
+        !self.span_lines.is_empty()
+    }
+
     #[inline]
     fn current_span_line(&mut self) -> Option<&mut SpanLine> {
         self.span_lines.last_mut()
@@ -148,7 +245,9 @@ mod tests {
             collect_id: 42,
             is_root: false,
         };
-        let span_line1 = span_stack.register_span_line(Some(token1.into())).unwrap();
+        let span_line1 = span_stack
+            .register_span_line(Some(token1.into()), None)
+            .unwrap();
         {
             {
                 let span1 = span_stack.enter_span("span1").unwrap();
@@ -165,7 +264,9 @@ mod tests {
                 collect_id: 48,
                 is_root: false,
             };
-            let span_line2 = span_stack.register_span_line(Some(token2.into())).unwrap();
+            let span_line2 = span_stack
+                .register_span_line(Some(token2.into()), None)
+                .unwrap();
             {
                 let span3 = span_stack.enter_span("span3").unwrap();
                 {
@@ -201,58 +302,67 @@ span1 []
     fn span_stack_is_full() {
         let mut span_stack = LocalSpanStack::with_capacity(4);
 
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         {
-            let span_line2 = span_stack.register_span_line(None).unwrap();
+            let span_line2 = span_stack.register_span_line(None, None).unwrap();
             {
                 let span_line3 = span_stack
-                    .register_span_line(Some(
-                        CollectTokenItem {
-                            trace_id: TraceId(1234),
-                            parent_id: SpanId::default(),
-                            collect_id: 42,
-                            is_root: false,
-                        }
-                        .into(),
-                    ))
+                    .register_span_line(
+                        Some(
+                            CollectTokenItem {
+                                trace_id: TraceId(1234),
+                                parent_id: SpanId::default(),
+                                collect_id: 42,
+                                is_root: false,
+                            }
+                            .into(),
+                        ),
+                        None,
+                    )
                     .unwrap();
                 {
-                    let span_line4 = span_stack.register_span_line(None).unwrap();
+                    let span_line4 = span_stack.register_span_line(None, None).unwrap();
                     {
                         assert!(
                             span_stack
-                                .register_span_line(Some(
-                                    CollectTokenItem {
-                                        trace_id: TraceId(1235),
-                                        parent_id: SpanId::default(),
-                                        collect_id: 43,
-                                        is_root: false,
-                                    }
-                                    .into()
-                                ))
+                                .register_span_line(
+                                    Some(
+                                        CollectTokenItem {
+                                            trace_id: TraceId(1235),
+                                            parent_id: SpanId::default(),
+                                            collect_id: 43,
+                                            is_root: false,
+                                        }
+                                        .into()
+                                    ),
+                                    None
+                                )
                                 .is_none()
                         );
-                        assert!(span_stack.register_span_line(None).is_none());
+                        assert!(span_stack.register_span_line(None, None).is_none());
                     }
                     let _ = span_stack.unregister_and_collect(span_line4).unwrap();
                 }
                 {
-                    let span_line5 = span_stack.register_span_line(None).unwrap();
+                    let span_line5 = span_stack.register_span_line(None, None).unwrap();
                     {
                         assert!(
                             span_stack
-                                .register_span_line(Some(
-                                    CollectTokenItem {
-                                        trace_id: TraceId(1236),
-                                        parent_id: SpanId::default(),
-                                        collect_id: 44,
-                                        is_root: false,
-                                    }
-                                    .into()
-                                ))
+                                .register_span_line(
+                                    Some(
+                                        CollectTokenItem {
+                                            trace_id: TraceId(1236),
+                                            parent_id: SpanId::default(),
+                                            collect_id: 44,
+                                            is_root: false,
+                                        }
+                                        .into()
+                                    ),
+                                    None
+                                )
                                 .is_none()
                         );
-                        assert!(span_stack.register_span_line(None).is_none());
+                        assert!(span_stack.register_span_line(None, None).is_none());
                     }
                     let _ = span_stack.unregister_and_collect(span_line5).unwrap();
                 }
@@ -273,12 +383,14 @@ span1 []
             collect_id: 1,
             is_root: false,
         };
-        let span_line1 = span_stack.register_span_line(Some(token1.into())).unwrap();
+        let span_line1 = span_stack
+            .register_span_line(Some(token1.into()), None)
+            .unwrap();
         assert_eq!(span_stack.current_collect_token().unwrap().as_slice(), &[
             token1
         ]);
         {
-            let span_line2 = span_stack.register_span_line(None).unwrap();
+            let span_line2 = span_stack.register_span_line(None, None).unwrap();
             assert!(span_stack.current_collect_token().is_none());
             {
                 let token3 = CollectTokenItem {
@@ -287,7 +399,9 @@ span1 []
                     collect_id: 3,
                     is_root: false,
                 };
-                let span_line3 = span_stack.register_span_line(Some(token3.into())).unwrap();
+                let span_line3 = span_stack
+                    .register_span_line(Some(token3.into()), None)
+                    .unwrap();
                 assert_eq!(span_stack.current_collect_token().unwrap().as_slice(), &[
                     token3
                 ]);
@@ -302,7 +416,9 @@ span1 []
                 collect_id: 4,
                 is_root: false,
             };
-            let span_line4 = span_stack.register_span_line(Some(token4.into())).unwrap();
+            let span_line4 = span_stack
+                .register_span_line(Some(token4.into()), None)
+                .unwrap();
             assert_eq!(span_stack.current_collect_token().unwrap().as_slice(), &[
                 token4
             ]);
@@ -319,19 +435,22 @@ span1 []
     #[should_panic]
     fn unmatched_span_line_exit_span() {
         let mut span_stack = LocalSpanStack::with_capacity(16);
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         let span1 = span_stack.enter_span("span1").unwrap();
         {
             let span_line2 = span_stack
-                .register_span_line(Some(
-                    CollectTokenItem {
-                        trace_id: TraceId(1234),
-                        parent_id: SpanId::default(),
-                        collect_id: 42,
-                        is_root: false,
-                    }
-                    .into(),
-                ))
+                .register_span_line(
+                    Some(
+                        CollectTokenItem {
+                            trace_id: TraceId(1234),
+                            parent_id: SpanId::default(),
+                            collect_id: 42,
+                            is_root: false,
+                        }
+                        .into(),
+                    ),
+                    None,
+                )
                 .unwrap();
             span_stack.exit_span(span1);
             let _ = span_stack.unregister_and_collect(span_line2).unwrap();
@@ -343,19 +462,22 @@ span1 []
     #[should_panic]
     fn unmatched_span_line_add_properties() {
         let mut span_stack = LocalSpanStack::with_capacity(16);
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         let span1 = span_stack.enter_span("span1").unwrap();
         {
             let span_line2 = span_stack
-                .register_span_line(Some(
-                    CollectTokenItem {
-                        trace_id: TraceId(1234),
-                        parent_id: SpanId::default(),
-                        collect_id: 42,
-                        is_root: false,
-                    }
-                    .into(),
-                ))
+                .register_span_line(
+                    Some(
+                        CollectTokenItem {
+                            trace_id: TraceId(1234),
+                            parent_id: SpanId::default(),
+                            collect_id: 42,
+                            is_root: false,
+                        }
+                        .into(),
+                    ),
+                    None,
+                )
                 .unwrap();
             span_stack.add_properties(&span1, || [("k1", "v1")]);
             let _ = span_stack.unregister_and_collect(span_line2).unwrap();
@@ -368,18 +490,21 @@ span1 []
     #[should_panic]
     fn unmatched_span_line_collect() {
         let mut span_stack = LocalSpanStack::with_capacity(16);
-        let span_line1 = span_stack.register_span_line(None).unwrap();
+        let span_line1 = span_stack.register_span_line(None, None).unwrap();
         {
             let span_line2 = span_stack
-                .register_span_line(Some(
-                    CollectTokenItem {
-                        trace_id: TraceId(1234),
-                        parent_id: SpanId::default(),
-                        collect_id: 42,
-                        is_root: false,
-                    }
-                    .into(),
-                ))
+                .register_span_line(
+                    Some(
+                        CollectTokenItem {
+                            trace_id: TraceId(1234),
+                            parent_id: SpanId::default(),
+                            collect_id: 42,
+                            is_root: false,
+                        }
+                        .into(),
+                    ),
+                    None,
+                )
                 .unwrap();
             let _ = span_stack.unregister_and_collect(span_line1).unwrap();
             let _ = span_stack.unregister_and_collect(span_line2).unwrap();
@@ -4,6 +4,7 @@
 
 pub(crate) mod local_collector;
 pub(crate) mod local_span;
+pub(crate) mod local_span_batch;
 pub(crate) mod local_span_line;
 pub(crate) mod local_span_stack;
 pub(crate) mod raw_span;
@@ -12,4 +13,6 @@ pub(crate) mod span_queue;
 pub use self::local_collector::LocalCollector;
 pub use self::local_collector::LocalSpans;
 pub use self::local_span::LocalSpan;
+pub use self::local_span_batch::LocalSpanBatch;
+pub use self::local_span_batch::LocalSpanBatchGuard;
 pub use crate::span::LocalParentGuard;
@@ -5,8 +5,24 @@ use std::borrow::Cow;
 use minstant::Instant;
 
 use crate::collector::SpanId;
+use crate::collector::SpanStatus;
+use crate::util::current_thread_id;
 use crate::util::Properties;
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RawKind {
+    Span,
+    Event,
+    /// Properties to be appended to the parent span.
+    Properties,
+    /// The status to be set on the parent span.
+    Status(SpanStatus),
+}
+
+/// A span, an event or a placeholder recorded on a thread.
+///
+/// A span is never its own parent, that is, `parent_id` is never equal to `id`, otherwise the
+/// span would be unreachable from the root of the trace. Placeholders have no `id` of their own.
 #[derive(Debug)]
 pub struct RawSpan {
     pub id: SpanId,
@@ -14,7 +30,9 @@ pub struct RawSpan {
     pub begin_instant: Instant,
     pub name: Cow<'static, str>,
     pub properties: Properties,
-    pub is_event: bool,
+    pub raw_kind: RawKind,
+    /// The thread that ended the span or recorded the event, or 0 if it is not known yet.
+    pub thread_id: u64,
 
     // Will write this field at post processing
     pub end_instant: Instant,
@@ -27,15 +45,21 @@ impl RawSpan {
         parent_id: SpanId,
         begin_instant: Instant,
         name: impl Into<Cow<'static, str>>,
-        is_event: bool,
+        raw_kind: RawKind,
     ) -> Self {
+        debug_assert!(
+            id == SpanId::default() || id != parent_id,
+            "span {id} can not be its own parent"
+        );
+
         RawSpan {
             id,
             parent_id,
             begin_instant,
             name: name.into(),
             properties: Properties::default(),
-            is_event,
+            raw_kind,
+            thread_id: 0,
             end_instant: begin_instant,
         }
     }
@@ -43,6 +67,7 @@ impl RawSpan {
     #[inline]
     pub(crate) fn end_with(&mut self, end_instant: Instant) {
         self.end_instant = end_instant;
+        self.thread_id = current_thread_id();
     }
 }
 
@@ -57,8 +82,32 @@ impl Clone for RawSpan {
             begin_instant: self.begin_instant,
             name: self.name.clone(),
             properties,
-            is_event: self.is_event,
+            raw_kind: self.raw_kind,
+            thread_id: self.thread_id,
             end_instant: self.end_instant,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "can not be its own parent")]
+    fn self_parent() {
+        RawSpan::begin_with(SpanId(1), SpanId(1), Instant::now(), "span", RawKind::Span);
+    }
+
+    #[test]
+    fn placeholder_without_parent() {
+        RawSpan::begin_with(
+            SpanId::default(),
+            SpanId::default(),
+            Instant::now(),
+            "",
+            RawKind::Properties,
+        );
+    }
+}
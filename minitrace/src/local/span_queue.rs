@@ -1,17 +1,24 @@
 // Copyright 2020 TiKV Project Authors. Licensed under Apache-2.0.
 
 use std::borrow::Cow;
+use std::time::Duration;
 
 use minstant::Instant;
 
 use crate::collector::SpanId;
+use crate::collector::SpanStatus;
+use crate::local::raw_span::RawKind;
 use crate::local::raw_span::RawSpan;
+use crate::util::current_thread_id;
 use crate::util::RawSpans;
 
 pub struct SpanQueue {
     span_queue: RawSpans,
     capacity: usize,
     next_parent_id: Option<SpanId>,
+    /// The index of the last span finished by [`SpanQueue::finish_span_coalesced()`] and the
+    /// number of spans merged into it.
+    last_coalesced: Option<(usize, usize)>,
 }
 
 pub struct SpanHandle {
@@ -24,6 +31,7 @@ impl SpanQueue {
             span_queue: RawSpans::default(),
             capacity,
             next_parent_id: None,
+            last_coalesced: None,
         }
     }
 
@@ -38,7 +46,7 @@ impl SpanQueue {
             self.next_parent_id.unwrap_or_default(),
             Instant::now(),
             name,
-            false,
+            RawKind::Span,
         );
         self.next_parent_id = Some(span.id);
 
@@ -62,6 +70,70 @@ impl SpanQueue {
         self.next_parent_id = Some(span.parent_id).filter(|id| *id != SpanId::default());
     }
 
+    /// Finishes the span like [`SpanQueue::finish_span()`], but discards it along with all of
+    /// its descendants if it lasted shorter than `min_duration`.
+    #[inline]
+    pub fn finish_span_with_min_duration(
+        &mut self,
+        span_handle: SpanHandle,
+        min_duration: Duration,
+    ) {
+        debug_assert!(span_handle.index < self.span_queue.len());
+
+        let span = &self.span_queue[span_handle.index];
+        if span.begin_instant.elapsed() >= min_duration {
+            self.finish_span(span_handle);
+            return;
+        }
+
+        debug_assert_eq!(self.next_parent_id, Some(span.id));
+
+        // The span is the current local parent, so everything recorded after it is a
+        // descendant of it.
+        self.next_parent_id = Some(span.parent_id).filter(|id| *id != SpanId::default());
+        self.span_queue.truncate(span_handle.index);
+        if matches!(self.last_coalesced, Some((index, _)) if index >= span_handle.index) {
+            self.last_coalesced = None;
+        }
+    }
+
+    /// Finishes the span like [`SpanQueue::finish_span()`], but merges it into the previous span
+    /// if that one is a sibling of the same name that was also finished by this function, and
+    /// nothing was recorded in between. The merged span lasts until the end of the last span and
+    /// carries the number of spans merged into it as the `count` property.
+    ///
+    /// A span with descendants is never merged, nor merged into.
+    #[inline]
+    pub fn finish_span_coalesced(&mut self, span_handle: SpanHandle) {
+        let index = span_handle.index;
+        self.finish_span(span_handle);
+
+        if index + 1 == self.span_queue.len() {
+            if let Some((last_index, count)) = self.last_coalesced {
+                if last_index + 1 == index {
+                    let (head, tail) = self.span_queue.split_at_mut(index);
+                    let (last, span) = (&mut head[last_index], &tail[0]);
+                    if last.name == span.name && last.parent_id == span.parent_id {
+                        last.end_with(span.end_instant);
+                        if let Some((_, value)) =
+                            last.properties.iter_mut().rev().find(|(k, _)| k == "count")
+                        {
+                            *value = (count + 1).to_string().into();
+                        }
+                        self.span_queue.pop();
+                        self.last_coalesced = Some((last_index, count + 1));
+                        return;
+                    }
+                }
+            }
+
+            self.span_queue[index]
+                .properties
+                .push(("count".into(), "1".into()));
+            self.last_coalesced = Some((index, 1));
+        }
+    }
+
     #[inline]
     pub fn add_event<I, F>(&mut self, name: impl Into<Cow<'static, str>>, properties: F)
     where
@@ -77,8 +149,9 @@ impl SpanQueue {
             self.next_parent_id.unwrap_or_default(),
             Instant::now(),
             name,
-            true,
+            RawKind::Event,
         );
+        span.thread_id = current_thread_id();
         span.properties.extend(properties());
 
         self.span_queue.push(span);
@@ -98,6 +171,96 @@ impl SpanQueue {
             .extend(properties.into_iter().map(|(k, v)| (k.into(), v.into())));
     }
 
+    #[inline]
+    pub fn reserve_properties(&mut self, span_handle: &SpanHandle, additional: usize) {
+        debug_assert!(span_handle.index < self.span_queue.len());
+
+        let span = &mut self.span_queue[span_handle.index];
+        span.properties.reserve(additional);
+    }
+
+    /// Counts the spans recorded so far whose parent is the span. Since a span is recorded after
+    /// its parent, only the spans after it in the queue are visited.
+    #[inline]
+    pub fn child_count(&self, span_handle: &SpanHandle) -> usize {
+        debug_assert!(span_handle.index < self.span_queue.len());
+
+        let id = self.span_queue[span_handle.index].id;
+        self.span_queue[span_handle.index + 1..]
+            .iter()
+            .filter(|span| span.raw_kind == RawKind::Span && span.parent_id == id)
+            .count()
+    }
+
+    /// Adds finished spans as children of the current span.
+    #[inline]
+    pub fn add_spans(
+        &mut self,
+        spans: impl IntoIterator<Item = (Cow<'static, str>, Instant, Instant)>,
+    ) {
+        for (name, begin_instant, end_instant) in spans {
+            if self.span_queue.len() >= self.capacity {
+                return;
+            }
+
+            let mut span = RawSpan::begin_with(
+                SpanId::next_id(),
+                self.next_parent_id.unwrap_or_default(),
+                begin_instant,
+                name,
+                RawKind::Span,
+            );
+            span.end_with(end_instant);
+
+            self.span_queue.push(span);
+        }
+    }
+
+    #[inline]
+    pub fn add_parent_properties<K, V, I>(&mut self, properties: I)
+    where
+        K: Into<Cow<'static, str>>,
+        V: Into<Cow<'static, str>>,
+        I: IntoIterator<Item = (K, V)>,
+    {
+        if self.span_queue.len() >= self.capacity {
+            return;
+        }
+
+        // The parent may be a span outside of this queue, so the properties are carried by a
+        // placeholder and merged into the parent by the global collector.
+        let mut span = RawSpan::begin_with(
+            SpanId::default(),
+            self.next_parent_id.unwrap_or_default(),
+            Instant::now(),
+            "",
+            RawKind::Properties,
+        );
+        span.properties
+            .extend(properties.into_iter().map(|(k, v)| (k.into(), v.into())));
+
+        self.span_queue.push(span);
+    }
+
+    #[inline]
+    pub fn set_parent_status(&mut self, status: SpanStatus) {
+        if self.span_queue.len() >= self.capacity {
+            return;
+        }
+
+        // Like properties, the status is carried by a placeholder and set on the parent by the
+        // global collector.
+        let span = RawSpan::begin_with(
+            SpanId::default(),
+            self.next_parent_id.unwrap_or_default(),
+            Instant::now(),
+            "",
+            RawKind::Status(status),
+        );
+
+        self.span_queue.push(span);
+    }
+
     #[inline]
     pub fn take_queue(self) -> RawSpans {
         self.span_queue
@@ -108,6 +271,24 @@ impl SpanQueue {
         self.next_parent_id
     }
 
+    /// Returns the id of the nearest unfinished span named `name`, starting from the current
+    /// local parent and walking up its ancestors.
+    ///
+    /// The spans are scanned once from the newest, so only the lookup, never the recording of
+    /// spans, pays for it.
+    pub fn ancestor_span_id(&self, name: &str) -> Option<SpanId> {
+        let mut span_id = self.next_parent_id?;
+        // An ancestor is always queued before its descendants.
+        let mut spans = self.span_queue.iter().rev();
+        loop {
+            let span = spans.find(|span| span.id == span_id)?;
+            if span.name == name {
+                return Some(span.id);
+            }
+            span_id = span.parent_id;
+        }
+    }
+
     #[cfg(test)]
     pub fn get_raw_span(&self, handle: &SpanHandle) -> &RawSpan {
         &self.span_queue[handle.index]
@@ -119,6 +300,56 @@ mod tests {
     use super::*;
     use crate::util::tree::tree_str_from_raw_spans;
 
+    #[test]
+    fn span_queue_coalesced() {
+        let mut queue = SpanQueue::with_capacity(16);
+        {
+            let span1 = queue.start_span("span1").unwrap();
+            for _ in 0..3 {
+                let span2 = queue.start_span("span2").unwrap();
+                queue.finish_span_coalesced(span2);
+            }
+            let span3 = queue.start_span("span3").unwrap();
+            queue.finish_span_coalesced(span3);
+            let span2 = queue.start_span("span2").unwrap();
+            {
+                let span4 = queue.start_span("span4").unwrap();
+                queue.finish_span(span4);
+            }
+            queue.finish_span_coalesced(span2);
+            queue.finish_span(span1);
+        }
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r#"
+span1 []
+    span2 [("count", "3")]
+    span2 []
+        span4 []
+    span3 [("count", "1")]
+"#
+        );
+    }
+
+    #[test]
+    fn span_queue_ancestor_span_id() {
+        let mut queue = SpanQueue::with_capacity(16);
+        assert_eq!(queue.ancestor_span_id("span1"), None);
+        let span1 = queue.start_span("span1").unwrap();
+        let span1_id = queue.current_span_id();
+        let span2 = queue.start_span("span2").unwrap();
+        queue.finish_span(span2);
+        let span3 = queue.start_span("span3").unwrap();
+        let span3_id = queue.current_span_id();
+        // A finished span is not an ancestor.
+        assert_eq!(queue.ancestor_span_id("span2"), None);
+        assert_eq!(queue.ancestor_span_id("span1"), span1_id);
+        assert_eq!(queue.ancestor_span_id("span3"), span3_id);
+        queue.finish_span(span3);
+        queue.finish_span(span1);
+        assert_eq!(queue.ancestor_span_id("span1"), None);
+    }
+
     #[test]
     fn span_queue_basic() {
         let mut queue = SpanQueue::with_capacity(16);
@@ -144,6 +375,58 @@ span1 []
         );
     }
 
+    #[test]
+    fn span_add_spans() {
+        let mut queue = SpanQueue::with_capacity(16);
+        {
+            let span1 = queue.start_span("span1").unwrap();
+            let now = Instant::now();
+            queue.add_spans([("span2".into(), now, now), ("span3".into(), now, now)]);
+            queue.finish_span(span1);
+        }
+        queue.add_spans([("span4".into(), Instant::now(), Instant::now())]);
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r#"
+span1 []
+    span2 []
+    span3 []
+
+span4 []
+"#
+        );
+    }
+
+    #[test]
+    fn span_reserve_properties() {
+        let mut queue = SpanQueue::with_capacity(16);
+        let span1 = queue.start_span("span1").unwrap();
+        queue.reserve_properties(&span1, 8);
+        assert!(queue.span_queue[span1.index].properties.capacity() >= 8);
+        queue.add_properties(&span1, [("k1", "v1")]);
+        queue.finish_span(span1);
+        assert_eq!(queue.take_queue()[0].properties.len(), 1);
+    }
+
+    #[test]
+    fn span_child_count() {
+        let mut queue = SpanQueue::with_capacity(16);
+        let span1 = queue.start_span("span1").unwrap();
+        {
+            let span2 = queue.start_span("span2").unwrap();
+            {
+                let span3 = queue.start_span("span3").unwrap();
+                queue.finish_span(span3);
+            }
+            queue.finish_span(span2);
+        }
+        queue.add_event("event", || []);
+        let now = Instant::now();
+        queue.add_spans([("span4".into(), now, now)]);
+        assert_eq!(queue.child_count(&span1), 2);
+        queue.finish_span(span1);
+    }
+
     #[test]
     fn span_add_properties() {
         let mut queue = SpanQueue::with_capacity(16);
@@ -166,6 +449,35 @@ span1 [("k1", "v1"), ("k2", "v2")]
         );
     }
 
+    #[test]
+    fn span_with_min_duration() {
+        let mut queue = SpanQueue::with_capacity(16);
+        {
+            let span1 = queue.start_span("span1").unwrap();
+            {
+                let span2 = queue.start_span("span2").unwrap();
+                {
+                    let span3 = queue.start_span("span3").unwrap();
+                    queue.finish_span(span3);
+                }
+                queue.add_event("event", || []);
+                queue.finish_span_with_min_duration(span2, Duration::from_secs(60));
+            }
+            {
+                let span4 = queue.start_span("span4").unwrap();
+                queue.finish_span_with_min_duration(span4, Duration::ZERO);
+            }
+            queue.finish_span(span1);
+        }
+        assert_eq!(
+            tree_str_from_raw_spans(queue.take_queue()),
+            r"
+span1 []
+    span4 []
+"
+        );
+    }
+
     #[test]
     fn span_not_finished() {
         let mut queue = SpanQueue::with_capacity(16);
@@ -69,3 +69,38 @@ macro_rules! file_location {
         std::concat!(file!(), ":", line!(), ":", column!())
     };
 }
+
+/// Trace a block in a [`LocalSpan`] named `name` under the local parent, and evaluate to the
+/// value of the block.
+///
+/// The block is not wrapped in a closure, so `return`, `break`, `continue` and `?` in the block
+/// behave as they would without the macro. The span ends where the macro expression ends, e.g. at
+/// the end of a `match` arm or of an `if let` body, so a span is recorded each time the block is
+/// executed.
+///
+/// # Example
+///
+/// ```
+/// use minitrace::prelude::*;
+/// use minitrace::trace_block;
+///
+/// fn parse(input: Option<&str>) -> Result<u32, std::num::ParseIntError> {
+///     match input {
+///         Some(s) => trace_block!("parse", { s.parse::<u32>() }),
+///         None => Ok(0),
+///     }
+/// }
+///
+/// let root = Span::root("root", SpanContext::random());
+/// let _g = root.set_local_parent();
+/// assert_eq!(parse(Some("1")), Ok(1));
+/// ```
+///
+/// [`LocalSpan`]: crate::local::LocalSpan
+#[macro_export]
+macro_rules! trace_block {
+    ($name:expr, $body:block) => {{
+        let _span = $crate::local::LocalSpan::enter_with_local_parent($name);
+        $body
+    }};
+}
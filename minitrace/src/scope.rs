@@ -0,0 +1,28 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+/// Where the span recorded by `#[trace]` for a non-async function can be used, given by
+/// `#[trace(scope = Scope::...)]`.
+///
+/// # Examples
+///
+/// ```
+/// use minitrace::prelude::*;
+///
+/// #[trace(scope = Scope::Threads, guard = span)]
+/// fn fan_out() {
+///     std::thread::scope(|scope| {
+///         scope.spawn(|| {
+///             let _child = Span::enter_with_parent("child", &span);
+///         });
+///     });
+/// }
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Scope {
+    /// The span is a [`LocalSpan`](crate::local::LocalSpan), only usable on the current thread.
+    #[default]
+    Local,
+    /// The span is a [`Span`](crate::Span), set as the local parent of the function body, which
+    /// can be passed to other threads.
+    Threads,
+}
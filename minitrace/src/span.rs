@@ -3,6 +3,8 @@
 use std::borrow::Cow;
 use std::cell::RefCell;
 use std::rc::Rc;
+use std::sync::mpsc;
+use std::sync::mpsc::Receiver;
 use std::sync::Arc;
 use std::time::Duration;
 
@@ -11,12 +13,16 @@ use minstant::Instant;
 use crate::collector::global_collector::reporter_ready;
 use crate::collector::CollectTokenItem;
 use crate::collector::GlobalCollect;
+use crate::collector::Sampler;
 use crate::collector::SpanContext;
 use crate::collector::SpanId;
+use crate::collector::SpanRecord;
 use crate::collector::SpanSet;
+use crate::collector::TraceId;
 use crate::local::local_collector::LocalSpansInner;
 use crate::local::local_span_stack::LocalSpanStack;
 use crate::local::local_span_stack::LOCAL_SPAN_STACK;
+use crate::local::raw_span::RawKind;
 use crate::local::raw_span::RawSpan;
 use crate::local::LocalCollector;
 use crate::local::LocalSpans;
@@ -41,12 +47,21 @@ pub(crate) struct SpanInner {
 impl Span {
     /// Create a place-holder span that never starts recording.
     ///
+    /// It can stand for the root span of a trace that is not sampled. Without a local parent,
+    /// the spans of the functions annotated with [`trace`](crate::trace) are not recorded, and
+    /// cost a thread-local lookup each without allocating.
+    ///
     /// # Examples
     ///
     /// ```
     /// use minitrace::prelude::*;
     ///
-    /// let mut root = Span::noop();
+    /// let sampled = false;
+    /// let root = if sampled {
+    ///     Span::root("root", SpanContext::random())
+    /// } else {
+    ///     Span::noop()
+    /// };
     /// ```
     #[inline]
     pub fn noop() -> Self {
@@ -98,6 +113,125 @@ impl Span {
         }
     }
 
+    /// Create a new trace and return its root span if `sampler` decides to record it, or a noop
+    /// span otherwise, which does nothing and has no cost.
+    ///
+    /// The sampler is consulted once, with the name of the root span, so that all the spans of a
+    /// trace are either recorded or not.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::RatioSampler;
+    /// use minitrace::prelude::*;
+    ///
+    /// let sampler = RatioSampler::new(0.1);
+    /// let root = Span::root_with_sampler("root", SpanContext::random(), &sampler);
+    /// ```
+    #[inline]
+    pub fn root_with_sampler(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        sampler: &impl Sampler,
+        #[cfg(test)] collect: GlobalCollect,
+    ) -> Self {
+        let name = name.into();
+        if !sampler.should_sample(&name) {
+            return Self::noop();
+        }
+
+        Self::root(
+            name,
+            parent,
+            #[cfg(test)]
+            collect,
+        )
+    }
+
+    /// Create a new trace with the trace id `trace_id` and return its root span, which has no
+    /// parent.
+    ///
+    /// This is useful to correlate a trace with an id chosen by the caller, e.g. the id of a
+    /// request, as all the spans of the trace carry the trace id into the exporters.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let request_id: u128 = 0x1234;
+    /// let root = Span::root_with_trace_id("root", request_id);
+    /// ```
+    #[inline]
+    pub fn root_with_trace_id(
+        name: impl Into<Cow<'static, str>>,
+        trace_id: u128,
+        #[cfg(test)] collect: GlobalCollect,
+    ) -> Self {
+        Self::root(
+            name,
+            SpanContext::new(TraceId(trace_id), SpanId::default()),
+            #[cfg(test)]
+            collect,
+        )
+    }
+
+    /// Create a new trace and return its root span, along with a channel receiving the span
+    /// records of the trace.
+    ///
+    /// Instead of being reported once the root span is dropped, the span records are sent to the
+    /// channel shortly after the spans complete, in the order of completion. The channel is
+    /// disconnected once the root span is dropped and all span records have been sent.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::ConsoleReporter;
+    /// use minitrace::prelude::*;
+    ///
+    /// minitrace::set_reporter(ConsoleReporter, Config::default());
+    ///
+    /// let (root, records) = Span::root_streaming("root", SpanContext::random());
+    /// drop(root);
+    ///
+    /// for record in records {
+    ///     println!("{record:?}");
+    /// }
+    /// ```
+    #[inline]
+    pub fn root_streaming(
+        name: impl Into<Cow<'static, str>>,
+        parent: SpanContext,
+        #[cfg(test)] collect: GlobalCollect,
+    ) -> (Self, Receiver<SpanRecord>) {
+        let (tx, rx) = mpsc::channel();
+
+        #[cfg(not(feature = "enable"))]
+        {
+            (Self::noop(), rx)
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            if !reporter_ready() {
+                return (Self::noop(), rx);
+            }
+
+            #[cfg(not(test))]
+            let collect = GlobalCollect;
+            let collect_id = collect.start_streaming_collect(tx);
+            let token = CollectTokenItem {
+                trace_id: parent.trace_id,
+                parent_id: parent.span_id,
+                collect_id,
+                is_root: true,
+            }
+            .into();
+            (Self::new(token, name, Some(collect_id), collect), rx)
+        }
+    }
+
     /// Create a new child span associated with the specified parent span.
     ///
     /// # Examples
@@ -206,6 +340,57 @@ impl Span {
         }
     }
 
+    /// Create a new child span of the nearest enclosing span named `ancestor` in the current
+    /// thread, rather than of the current local span.
+    ///
+    /// The enclosing spans are the unfinished [`LocalSpan`]s from the current local span up, and
+    /// then the `Span` set as the local parent if its name is static, e.g. a string literal rather
+    /// than a formatted `String`. If none of them is named `ancestor`, this function returns a
+    /// no-op span.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// let _g = root.set_local_parent();
+    ///
+    /// let _request = LocalSpan::enter_with_local_parent("request");
+    /// let _handler = LocalSpan::enter_with_local_parent("handler");
+    ///
+    /// // A child of `request` rather than `handler`.
+    /// let child = Span::enter_with_local_ancestor("child", "request");
+    /// ```
+    ///
+    /// [`LocalSpan`]: crate::local::LocalSpan
+    #[inline]
+    pub fn enter_with_local_ancestor(
+        name: impl Into<Cow<'static, str>>,
+        ancestor: &str,
+        #[cfg(test)] collect: GlobalCollect,
+    ) -> Self {
+        #[cfg(not(feature = "enable"))]
+        {
+            Self::noop()
+        }
+
+        #[cfg(feature = "enable")]
+        {
+            #[cfg(not(test))]
+            let collect = GlobalCollect;
+            LOCAL_SPAN_STACK
+                .try_with(move |stack| {
+                    let token = stack.borrow_mut().ancestor_collect_token(ancestor);
+                    match token {
+                        Some(token) => Span::new(token, name, None, collect),
+                        None => Self::noop(),
+                    }
+                })
+                .unwrap_or_default()
+        }
+    }
+
     /// Sets the current `Span` as the local parent for the current thread.
     ///
     /// This method is used to establish a `Span` as the local parent within the current scope.
@@ -288,6 +473,30 @@ impl Span {
         self
     }
 
+    /// Reserve capacity for at least `capacity` more properties on the `Span` and return the
+    /// modified `Span`.
+    ///
+    /// This avoids reallocations when the number of properties is known in advance.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::prelude::*;
+    ///
+    /// let root = Span::root("root", SpanContext::random())
+    ///     .with_properties_capacity(2)
+    ///     .with_properties(|| [("key1", "value1"), ("key2", "value2")]);
+    /// ```
+    #[inline]
+    pub fn with_properties_capacity(mut self, capacity: usize) -> Self {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = self.inner.as_mut() {
+            inner.raw_span.properties.reserve(capacity);
+        }
+
+        self
+    }
+
     /// Attach a collection of [`LocalSpan`] instances as child spans to the current span.
     ///
     /// This method allows you to associate previously collected `LocalSpan` instances with the current span.
@@ -382,6 +591,93 @@ impl Span {
             }
         }
     }
+
+    /// Returns the records of the spans of the trace completed so far, without ending the root
+    /// span. The returned records are not reported again when the root span ends.
+    ///
+    /// This is useful for long-lived root spans, e.g. one lasting for the lifetime of a server,
+    /// to inspect the completed child spans periodically. Local spans are only included once the
+    /// [`LocalParentGuard`] they are recorded under is dropped.
+    ///
+    /// # Note
+    ///
+    /// This method only returns records when called on the root span. If called on a non-root
+    /// span, it returns an empty vector.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::ConsoleReporter;
+    /// use minitrace::prelude::*;
+    ///
+    /// minitrace::set_reporter(ConsoleReporter, Config::default());
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// {
+    ///     let _g = root.set_local_parent();
+    ///     let _span = LocalSpan::enter_with_local_parent("child");
+    /// }
+    ///
+    /// let records = root.snapshot();
+    /// assert_eq!(records.len(), 1);
+    /// ```
+    pub fn snapshot(&self) -> Vec<SpanRecord> {
+        #[cfg(feature = "enable")]
+        if let Some(inner) = &self.inner {
+            if let Some(collect_id) = inner.collect_id {
+                return inner.collect.snapshot(collect_id);
+            }
+        }
+
+        Vec::new()
+    }
+
+    /// Finishes the trace and appends its span records to `records`, instead of sending them
+    /// to the reporter.
+    ///
+    /// The records are collected synchronously, so they are available once this method returns.
+    /// Spans of the trace that are still running by then, e.g. on other threads, are not
+    /// included.
+    ///
+    /// # Note
+    ///
+    /// This method only collects the trace when called on the root span. If called on a
+    /// non-root span, it will be finished as if it was dropped and `records` is left unchanged.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use minitrace::collector::Config;
+    /// use minitrace::collector::ConsoleReporter;
+    /// use minitrace::prelude::*;
+    ///
+    /// minitrace::set_reporter(ConsoleReporter, Config::default());
+    ///
+    /// let root = Span::root("root", SpanContext::random());
+    /// {
+    ///     let _g = root.set_local_parent();
+    ///     let _span = LocalSpan::enter_with_local_parent("child");
+    /// }
+    ///
+    /// let mut records = Vec::new();
+    /// root.record_into(&mut records);
+    /// assert_eq!(records.len(), 2);
+    /// ```
+    pub fn record_into(mut self, records: &mut Vec<SpanRecord>) {
+        #[cfg(feature = "enable")]
+        if let Some(mut inner) = self.inner.take() {
+            let collect_id = inner.collect_id.take();
+            let collect = inner.collect.clone();
+
+            inner.raw_span.end_with(Instant::now());
+            inner.submit_spans();
+
+            if let Some(collect_id) = collect_id {
+                collect.commit_collect_into(collect_id, records);
+            }
+        }
+    }
 }
 
 #[cfg(feature = "enable")]
@@ -395,7 +691,13 @@ impl Span {
     ) -> Self {
         let span_id = SpanId::next_id();
         let begin_instant = Instant::now();
-        let raw_span = RawSpan::begin_with(span_id, SpanId::default(), begin_instant, name, false);
+        let raw_span = RawSpan::begin_with(
+            span_id,
+            SpanId::default(),
+            begin_instant,
+            name,
+            RawKind::Span,
+        );
 
         Self {
             inner: Some(SpanInner {
@@ -447,7 +749,12 @@ impl SpanInner {
     #[inline]
     fn capture_local_spans(&self, stack: Rc<RefCell<LocalSpanStack>>) -> LocalParentGuard {
         let token = self.issue_collect_token().collect();
-        let collector = LocalCollector::new(Some(token), stack);
+        // Only a static name is recorded, which costs no allocation.
+        let parent_name = match self.raw_span.name {
+            Cow::Borrowed(name) => Some(name),
+            Cow::Owned(_) => None,
+        };
+        let collector = LocalCollector::new_with_parent_name(Some(token), parent_name, stack);
 
         LocalParentGuard::new(collector, self.collect.clone())
     }
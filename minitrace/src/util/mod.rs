@@ -7,13 +7,22 @@ pub mod spsc;
 pub mod tree;
 
 use std::borrow::Cow;
+use std::cell::Cell;
 use std::cell::RefCell;
 use std::iter::FromIterator;
+use std::panic::AssertUnwindSafe;
+use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
+use std::sync::Once;
 
 use once_cell::sync::Lazy;
 
 use crate::collector::CollectTokenItem;
+use crate::collector::SpanStatus;
+#[cfg(feature = "enable")]
+use crate::local::local_span_stack::LOCAL_SPAN_STACK;
 use crate::local::raw_span::RawSpan;
+use crate::local::LocalSpan;
 use crate::util::object_pool::Pool;
 use crate::util::object_pool::Puller;
 use crate::util::object_pool::Reusable;
@@ -24,12 +33,17 @@ static COLLECT_TOKEN_ITEMS_POOL: Lazy<Pool<Vec<CollectTokenItem>>> =
 #[allow(clippy::type_complexity)]
 static PROPERTIES_POOL: Lazy<Pool<Vec<(Cow<'static, str>, Cow<'static, str>)>>> =
     Lazy::new(|| Pool::new(Vec::new, Vec::clear));
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+static PANIC_HOOK: Once = Once::new();
 
 thread_local! {
     static RAW_SPANS_PULLER: RefCell<Puller<'static, Vec<RawSpan>>> = RefCell::new(RAW_SPANS_POOL.puller(512));
     static COLLECT_TOKEN_ITEMS_PULLER: RefCell<Puller<'static, Vec<CollectTokenItem>>>  = RefCell::new(COLLECT_TOKEN_ITEMS_POOL.puller(512));
     #[allow(clippy::type_complexity)]
     static PROPERTIES_PULLER: RefCell<Puller<'static, Vec<(Cow<'static, str>, Cow<'static, str>)>>>  = RefCell::new(PROPERTIES_POOL.puller(512));
+    static LOCAL_SAMPLER: Cell<u64> = Cell::new(rand::random::<u64>() | 1);
+    static THREAD_ID: u64 = NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed);
+    static PANIC_LOCATION: RefCell<Option<String>> = const { RefCell::new(None) };
 }
 
 pub type RawSpans = Reusable<'static, Vec<RawSpan>>;
@@ -85,3 +99,178 @@ impl From<CollectTokenItem> for CollectToken {
         new_collect_token([item])
     }
 }
+
+/// Returns the id of the current thread, starting at 1 and assigned in the order the threads first
+/// ask for it. Returns 0 while the thread is being torn down.
+#[inline]
+pub(crate) fn current_thread_id() -> u64 {
+    THREAD_ID.try_with(|id| *id).unwrap_or(0)
+}
+
+/// Records the number of child spans of a [`LocalSpan`] as its `child_count` property when
+/// dropped, which must happen while the `LocalSpan` is the local parent. Used by
+/// `#[trace(child_count = true)]`.
+pub struct ChildCountGuard<'a>(pub &'a LocalSpan);
+
+impl Drop for ChildCountGuard<'_> {
+    fn drop(&mut self) {
+        let count = self.0.child_count();
+        LocalSpan::add_property(|| ("child_count", count.to_string()));
+    }
+}
+
+/// Reports to the console unless a reporter is already set. Used by `#[minitrace::main]`, so that a
+/// reporter set up by the application, e.g. in a static initializer, is kept.
+pub fn set_default_reporter() {
+    crate::collector::global_collector::set_reporter_if_unset(
+        crate::collector::ConsoleReporter,
+        crate::collector::Config::default(),
+    );
+}
+
+/// Returns whether spans entered with a local parent are recorded on the current thread, which is
+/// not the case under a [`Span::noop()`](crate::Span::noop) root. Used by `#[trace]` to skip
+/// computing the name and the properties of a span that is not recorded.
+#[inline]
+pub fn has_local_parent() -> bool {
+    #[cfg(not(feature = "enable"))]
+    {
+        false
+    }
+
+    #[cfg(feature = "enable")]
+    {
+        LOCAL_SPAN_STACK
+            .try_with(|stack| stack.borrow().has_span_line())
+            .unwrap_or(false)
+    }
+}
+
+/// Returns `true` with the probability of `ratio`. Used by `#[trace(sample = ..)]`.
+#[inline]
+pub fn sample(ratio: f64) -> bool {
+    if ratio >= 1.0 {
+        return true;
+    }
+    if ratio <= 0.0 || ratio.is_nan() {
+        return false;
+    }
+
+    LOCAL_SAMPLER
+        .try_with(|state| {
+            // xorshift64*
+            let mut x = state.get();
+            x ^= x >> 12;
+            x ^= x << 25;
+            x ^= x >> 27;
+            state.set(x);
+
+            // Take the high 53 bits as a uniform float in [0, 1).
+            let x = x.wrapping_mul(0x2545_f491_4f6c_dd1d) >> 11;
+            (x as f64 / (1u64 << 53) as f64) < ratio
+        })
+        .unwrap_or_else(|_| rand::random::<f64>() < ratio)
+}
+
+/// Returns the id of the current tokio task, or `None` outside of a task. Used by `#[trace]`
+/// with the `tokio` feature.
+#[cfg(feature = "tokio")]
+#[inline]
+pub fn tokio_task_id() -> Option<String> {
+    tokio::task::try_id().map(|id| id.to_string())
+}
+
+/// Returns the current wall-clock time formatted as RFC 3339, e.g. `2024-01-01T00:00:00Z`.
+/// Used by `#[trace(timestamp = true)]`.
+#[cfg(feature = "timestamp")]
+pub fn rfc3339_now() -> String {
+    humantime::format_rfc3339_seconds(std::time::SystemTime::now()).to_string()
+}
+
+/// Installs a process-wide panic hook which captures the location of a panic, e.g.
+/// `src/main.rs:10:5`, so that `#[trace(catch_panic = true)]` records it as the
+/// `panic.location` property of the span. The hook calls the hook previously installed, and is
+/// installed only once however many times this function is called.
+///
+/// The panic hook is global to the process, so it is only installed when this function is
+/// called, e.g. at the start of `main`. The location is not recorded if the hook is replaced
+/// afterwards, e.g. by `std::panic::set_hook()`.
+///
+/// # Examples
+///
+/// ```
+/// minitrace::install_panic_hook();
+/// ```
+pub fn install_panic_hook() {
+    PANIC_HOOK.call_once(|| {
+        let previous = std::panic::take_hook();
+        std::panic::set_hook(Box::new(move |info| {
+            let location = info.location().map(|location| location.to_string());
+            PANIC_LOCATION
+                .try_with(|last| *last.borrow_mut() = location)
+                .ok();
+            previous(info);
+        }));
+    });
+}
+
+/// Calls `f`, and if it panics, records the panic message as the `panic.message` property of the
+/// local parent, sets its status to [`SpanStatus::Error`] and resumes the panic. Used by
+/// `#[trace(catch_panic = true)]`.
+///
+/// The location of the panic is recorded as the `panic.location` property only if the panic hook
+/// of [`install_panic_hook()`] is installed.
+pub fn catch_panic<R>(f: impl FnOnce() -> R) -> R {
+    let hook_installed = PANIC_HOOK.is_completed();
+    if hook_installed {
+        PANIC_LOCATION
+            .try_with(|last| last.borrow_mut().take())
+            .ok();
+    }
+
+    // The panic is resumed, so that no broken invariant can be observed.
+    match std::panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(ret) => ret,
+        Err(payload) => {
+            let message = payload
+                .downcast_ref::<&str>()
+                .map(|message| message.to_string())
+                .or_else(|| payload.downcast_ref::<String>().cloned())
+                .unwrap_or_else(|| "Box<dyn Any>".to_string());
+            LocalSpan::add_property(|| ("panic.message", message));
+            // Not taken, so that an enclosing `catch_panic` records it as well.
+            let location = hook_installed
+                .then(|| PANIC_LOCATION.try_with(|last| last.borrow().clone()).ok())
+                .flatten()
+                .flatten();
+            if let Some(location) = location {
+                LocalSpan::add_property(|| ("panic.location", location));
+            }
+            LocalSpan::set_status(SpanStatus::Error);
+            std::panic::resume_unwind(payload)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_ratio() {
+        assert!((0..1000).all(|_| sample(1.0)));
+        assert!((0..1000).all(|_| !sample(0.0)));
+        assert!((0..1000).all(|_| !sample(f64::NAN)));
+
+        let sampled = (0..100_000).filter(|_| sample(0.1)).count();
+        assert!((9_000..11_000).contains(&sampled), "{}", sampled);
+    }
+
+    #[test]
+    fn thread_id() {
+        let id = current_thread_id();
+        assert_ne!(id, 0);
+        assert_eq!(current_thread_id(), id);
+        assert_ne!(std::thread::spawn(current_thread_id).join().unwrap(), id);
+    }
+}
@@ -35,7 +35,7 @@ impl<T> Pool<T> {
         buffer.resize_with(n, || Reusable::new(self, (self.init)()));
     }
 
-    pub fn puller(&self, buffer_size: usize) -> Puller<T> {
+    pub fn puller(&self, buffer_size: usize) -> Puller<'_, T> {
         assert!(buffer_size > 0);
         Puller {
             pool: self,
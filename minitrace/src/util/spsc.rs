@@ -1,5 +1,7 @@
 // Copyright 2022 TiKV Project Authors. Licensed under Apache-2.0.
 
+use std::collections::VecDeque;
+
 use rtrb::Consumer;
 use rtrb::Producer;
 use rtrb::PushError;
@@ -10,7 +12,7 @@ pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
     (
         Sender {
             tx,
-            pending_messages: Vec::new(),
+            pending_messages: VecDeque::new(),
         },
         Receiver { rx },
     )
@@ -18,7 +20,7 @@ pub fn bounded<T>(capacity: usize) -> (Sender<T>, Receiver<T>) {
 
 pub struct Sender<T> {
     tx: Producer<T>,
-    pending_messages: Vec<T>,
+    pending_messages: VecDeque<T>,
 }
 
 pub struct Receiver<T> {
@@ -33,27 +35,66 @@ pub struct ChannelClosed;
 
 impl<T> Sender<T> {
     pub fn send(&mut self, value: T) -> Result<(), ChannelFull> {
-        while let Some(value) = self.pending_messages.pop() {
-            if let Err(PushError::Full(value)) = self.tx.push(value) {
-                self.pending_messages.push(value);
-                return Err(ChannelFull);
-            }
+        if !self.flush_pending() {
+            return Err(ChannelFull);
         }
 
         self.tx.push(value).map_err(|_| ChannelFull)
     }
 
     pub fn force_send(&mut self, value: T) {
-        while let Some(value) = self.pending_messages.pop() {
+        self.push_or_pend(value);
+    }
+
+    /// Sends the value, keeping it pending if the channel is full. Once more than `capacity`
+    /// messages are pending, the oldest pending messages accepted by `evictable` are removed and
+    /// returned, down to `capacity / 2` pending messages, so that the cost of an eviction is
+    /// shared by the following sends.
+    pub fn send_or_evict_oldest(
+        &mut self,
+        value: T,
+        capacity: usize,
+        evictable: impl Fn(&T) -> bool,
+    ) -> Vec<T> {
+        self.push_or_pend(value);
+
+        if self.pending_messages.len() <= capacity {
+            return Vec::new();
+        }
+
+        let mut to_evict = self.pending_messages.len() - capacity / 2;
+        let mut evicted = Vec::with_capacity(to_evict);
+        for value in std::mem::take(&mut self.pending_messages) {
+            if to_evict > 0 && evictable(&value) {
+                evicted.push(value);
+                to_evict -= 1;
+            } else {
+                self.pending_messages.push_back(value);
+            }
+        }
+        evicted
+    }
+
+    fn push_or_pend(&mut self, value: T) {
+        if self.flush_pending() {
             if let Err(PushError::Full(value)) = self.tx.push(value) {
-                self.pending_messages.push(value);
-                break;
+                self.pending_messages.push_back(value);
             }
+        } else {
+            self.pending_messages.push_back(value);
         }
+    }
 
-        if let Err(PushError::Full(value)) = self.tx.push(value) {
-            self.pending_messages.push(value);
+    /// Moves the pending messages into the channel in order. Returns `false` if the channel
+    /// becomes full before all of them are moved.
+    fn flush_pending(&mut self) -> bool {
+        while let Some(value) = self.pending_messages.pop_front() {
+            if let Err(PushError::Full(value)) = self.tx.push(value) {
+                self.pending_messages.push_front(value);
+                return false;
+            }
         }
+        true
     }
 }
 
@@ -66,3 +107,63 @@ impl<T> Receiver<T> {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn send_drops_newest() {
+        let (mut tx, mut rx) = bounded(2);
+        for i in 0..4 {
+            tx.send(i).ok();
+        }
+        assert_eq!(rx.try_recv().unwrap(), Some(0));
+        assert_eq!(rx.try_recv().unwrap(), Some(1));
+        assert_eq!(rx.try_recv().unwrap(), None);
+    }
+
+    #[test]
+    fn force_send_keeps_order() {
+        let (mut tx, mut rx) = bounded(2);
+        for i in 0..4 {
+            tx.force_send(i);
+        }
+        assert_eq!(rx.try_recv().unwrap(), Some(0));
+        assert_eq!(rx.try_recv().unwrap(), Some(1));
+        assert_eq!(rx.try_recv().unwrap(), None);
+
+        tx.force_send(4);
+        let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().unwrap()).collect();
+        assert_eq!(received, [2, 3]);
+
+        tx.force_send(5);
+        let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().unwrap()).collect();
+        assert_eq!(received, [4, 5]);
+    }
+
+    #[test]
+    fn send_or_evict_oldest() {
+        let (mut tx, mut rx) = bounded(2);
+        let evicted: Vec<_> = (0..8)
+            .map(|i| tx.send_or_evict_oldest(i, 4, |i| i % 2 == 0))
+            .collect();
+        // 0 and 1 are in the channel and 2 to 6 are pending when the capacity of 4 pending
+        // messages is exceeded. The oldest even numbers are evicted down to 2 pending messages,
+        // or as many as can be evicted.
+        assert!(evicted[..6].iter().all(Vec::is_empty));
+        assert_eq!(evicted[6], [2, 4, 6]);
+        assert!(evicted[7].is_empty());
+
+        let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().unwrap()).collect();
+        assert_eq!(received, [0, 1]);
+        tx.force_send(8);
+        let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().unwrap()).collect();
+        assert_eq!(received, [3, 5]);
+        let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().unwrap()).collect();
+        assert!(received.is_empty());
+        tx.force_send(9);
+        let received: Vec<_> = std::iter::from_fn(|| rx.try_recv().unwrap()).collect();
+        assert_eq!(received, [7, 8]);
+    }
+}
@@ -5,8 +5,10 @@ use std::time::Duration;
 use futures::executor::block_on;
 use minitrace::collector::Config;
 use minitrace::collector::ConsoleReporter;
+use minitrace::collector::SpanStatus;
 use minitrace::collector::TestReporter;
 use minitrace::local::LocalCollector;
+use minitrace::local::LocalSpanBatch;
 use minitrace::prelude::*;
 use minitrace::util::tree::tree_str_from_span_records;
 use serial_test::serial;
@@ -201,6 +203,44 @@ root []
     );
 }
 
+#[test]
+#[serial]
+fn thread_id() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _main = LocalSpan::enter_with_local_parent("main");
+
+        let cross_thread = Span::enter_with_local_parent("cross-thread");
+        std::thread::spawn(move || {
+            let _g = cross_thread.set_local_parent();
+            let _worker = LocalSpan::enter_with_local_parent("worker");
+        })
+        .join()
+        .unwrap();
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let thread_id = |name: &str| {
+        span_records
+            .iter()
+            .find(|s| s.name == name)
+            .unwrap()
+            .thread_id
+    };
+    assert_ne!(thread_id("main"), 0);
+    assert_ne!(thread_id("worker"), 0);
+    assert_ne!(thread_id("main"), thread_id("worker"));
+    assert_eq!(thread_id("root"), thread_id("main"));
+    // A span moved to another thread is recorded on the thread ending it.
+    assert_eq!(thread_id("cross-thread"), thread_id("worker"));
+}
+
 #[test]
 #[serial]
 fn multiple_threads_multiple_spans() {
@@ -322,6 +362,51 @@ root2 []
     );
 }
 
+#[test]
+#[serial]
+fn multiple_threads_concurrent_recording() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    #[trace(short_name = true)]
+    fn hot() {}
+
+    const THREADS: usize = 8;
+    const ITERS: usize = 1000;
+
+    let barrier = std::sync::Barrier::new(THREADS);
+    crossbeam::scope(|scope| {
+        for i in 0..THREADS {
+            let barrier = &barrier;
+            scope.spawn(move |_| {
+                barrier.wait();
+                let root = Span::root("root", SpanContext::new(TraceId(i as u128), SpanId(0)));
+                let _g = root.set_local_parent();
+                for _ in 0..ITERS {
+                    hot();
+                }
+            });
+        }
+    })
+    .unwrap();
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock().clone();
+    assert_eq!(spans.len(), THREADS * (ITERS + 1));
+    for i in 0..THREADS {
+        let trace_spans: Vec<_> = spans
+            .iter()
+            .filter(|span| span.trace_id == TraceId(i as u128))
+            .collect();
+        assert_eq!(trace_spans.len(), ITERS + 1);
+        assert_eq!(
+            trace_spans.iter().filter(|span| span.name == "hot").count(),
+            ITERS
+        );
+    }
+}
+
 #[test]
 #[serial]
 fn multiple_spans_without_local_spans() {
@@ -526,32 +611,127 @@ root []
     );
 }
 
+struct Foo {
+    a: u32,
+}
+
+impl Foo {
+    #[trace]
+    fn new() -> Self {
+        Foo { a: 1 }
+    }
+
+    #[trace(short_name = true)]
+    async fn new_async(a: u32) -> Self {
+        Self { a }
+    }
+}
+
 #[test]
 #[serial]
-fn multiple_local_parent() {
+fn macro_associated_fn() {
     let (reporter, collected_spans) = TestReporter::new();
     minitrace::set_reporter(reporter, Config::default());
 
     {
         let root = Span::root("root", SpanContext::random());
         let _g = root.set_local_parent();
-        let _g = LocalSpan::enter_with_local_parent("span1");
-        let span2 = Span::enter_with_local_parent("span2");
-        {
-            let _g = span2.set_local_parent();
-            let _g = LocalSpan::enter_with_local_parent("span3");
+
+        assert_eq!(Foo::new().a, 1);
+        assert_eq!(block_on(Foo::new_async(2)).a, 2);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    lib::Foo::new []
+    new_async []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_min_duration() {
+    #[trace(short_name = true, min_duration = "10ms")]
+    fn work(duration: Duration) {
+        let _g = LocalSpan::enter_with_local_parent("inner");
+        std::thread::sleep(duration);
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        work(Duration::ZERO);
+        work(Duration::from_millis(20));
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    work []
+        inner []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_name_const() {
+    trait Operation {
+        const SPAN_NAME: &'static str;
+
+        #[trace(name_const = Self::SPAN_NAME)]
+        fn run(&self) {}
+    }
+
+    struct Get;
+
+    impl Operation for Get {
+        const SPAN_NAME: &'static str = "get";
+    }
+
+    struct Put;
+
+    impl Operation for Put {
+        const SPAN_NAME: &'static str = "put";
+
+        #[trace(name_const = Self::SPAN_NAME)]
+        fn run(&self) {
+            let _g = LocalSpan::enter_with_local_parent("write");
         }
-        let _g = LocalSpan::enter_with_local_parent("span4");
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        Get.run();
+        Put.run();
     }
 
     minitrace::flush();
 
     let expected_graph = r#"
 root []
-    span1 []
-        span2 []
-            span3 []
-        span4 []
+    get []
+    put []
+        write []
 "#;
     assert_eq!(
         tree_str_from_span_records(collected_spans.lock().clone()),
@@ -561,27 +741,314 @@ root []
 
 #[test]
 #[serial]
-fn early_local_collect() {
+fn macro_name_format() {
+    #[trace(name = "handle_user({user_id})")]
+    fn handle_user(user_id: u32) {}
+
+    #[trace(name = "get({key:?}) {{cached}}")]
+    async fn get(key: &str) {}
+
+    #[trace(name = "poll({key})", enter_on_poll = true)]
+    async fn poll(key: String) {
+        drop(key);
+    }
+
+    #[trace(name = "lazy({n:>3})", lazy = true)]
+    async fn lazy(n: u32) {}
+
+    #[trace(name = "{{escaped}}")]
+    fn escaped() {}
+
     let (reporter, collected_spans) = TestReporter::new();
     minitrace::set_reporter(reporter, Config::default());
 
     {
-        let local_collector = LocalCollector::start();
-        let _g1 = LocalSpan::enter_with_local_parent("span1");
-        let _g2 = LocalSpan::enter_with_local_parent("span2");
-        drop(_g2);
-        let local_spans = local_collector.collect();
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        handle_user(42);
+        escaped();
+        block_on(get("a"));
+        block_on(
+            async {
+                poll("b".to_string()).await;
+                lazy(7).await;
+            }
+            .in_span(Span::enter_with_local_parent("task")),
+        );
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    get("a") {cached} []
+    handle_user(42) []
+    task []
+        lazy(  7) []
+        poll(b) []
+    {escaped} []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_name_from() {
+    enum State {
+        Idle,
+        Running(u32),
+    }
+
+    impl std::fmt::Display for State {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            match self {
+                State::Idle => write!(f, "idle"),
+                State::Running(n) => write!(f, "running-{n}"),
+            }
+        }
+    }
+
+    #[trace(name_from = state)]
+    fn step(state: State) -> State {
+        match state {
+            State::Idle => State::Running(1),
+            State::Running(n) => State::Running(n + 1),
+        }
+    }
+
+    #[trace(name_from = state, enter_on_poll = true)]
+    async fn step_async(state: State) -> State {
+        step(state)
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
 
+    {
         let root = Span::root("root", SpanContext::random());
-        root.push_child_spans(local_spans);
+        let _g = root.set_local_parent();
+
+        let state = step(State::Idle);
+        let state = step(state);
+        block_on(step_async(state).in_span(Span::enter_with_local_parent("task")));
     }
 
     minitrace::flush();
 
     let expected_graph = r#"
 root []
-    span1 []
-        span2 []
+    idle []
+    running-1 []
+    task []
+        running-2 []
+            running-2 []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_parent_id() {
+    #[trace(short_name = true, parent_id = 42)]
+    fn consume() {
+        let _g = LocalSpan::enter_with_local_parent("process");
+    }
+
+    #[trace(short_name = true, parent_id = header_id)]
+    async fn consume_async(header_id: u64) {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let trace_id = TraceId(7);
+    {
+        let root = Span::root("root", SpanContext::new(trace_id, SpanId::default()));
+        let _g = root.set_local_parent();
+
+        consume();
+        block_on(consume_async(43));
+    }
+    consume();
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let consume = span_records
+        .iter()
+        .filter(|s| s.name == "consume")
+        .collect::<Vec<_>>();
+    assert_eq!(consume.len(), 2);
+    assert!(consume.iter().all(|s| s.parent_id == SpanId(42)));
+
+    // Only the call inside the trace shares its trace id.
+    let inside = consume.iter().find(|s| s.trace_id == trace_id).unwrap();
+    assert!(consume.iter().any(|s| s.trace_id != trace_id));
+
+    assert!(
+        span_records.iter().any(|s| s.name == "process"
+            && s.trace_id == trace_id
+            && s.parent_id == inside.span_id)
+    );
+
+    let consume_async = span_records
+        .iter()
+        .find(|s| s.name == "consume_async")
+        .unwrap();
+    assert_eq!(consume_async.parent_id, SpanId(43));
+    assert_eq!(consume_async.trace_id, trace_id);
+}
+
+#[test]
+#[serial]
+fn root_with_sampler() {
+    use std::sync::atomic::AtomicBool;
+    use std::sync::atomic::Ordering;
+
+    use minitrace::collector::AlwaysOff;
+    use minitrace::collector::PrefixSampler;
+    use minitrace::collector::Sampler;
+
+    #[derive(Default)]
+    struct EvenInvocations(AtomicBool);
+
+    impl Sampler for EvenInvocations {
+        fn should_sample(&self, _name: &str) -> bool {
+            !self.0.fetch_xor(true, Ordering::Relaxed)
+        }
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let sampler = EvenInvocations::default();
+    for i in 0..6 {
+        let root = Span::root_with_sampler(format!("root-{i}"), SpanContext::random(), &sampler);
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+
+    drop(Span::root_with_sampler(
+        "off",
+        SpanContext::random(),
+        &AlwaysOff,
+    ));
+    let sampler = PrefixSampler::new("rpc.");
+    drop(Span::root_with_sampler(
+        "rpc.get",
+        SpanContext::random(),
+        &sampler,
+    ));
+    drop(Span::root_with_sampler(
+        "get",
+        SpanContext::random(),
+        &sampler,
+    ));
+
+    minitrace::flush();
+
+    let mut names = collected_spans
+        .lock()
+        .iter()
+        .map(|span| span.name.to_string())
+        .collect::<Vec<_>>();
+    names.sort();
+    assert_eq!(names, [
+        "child", "child", "child", "root-0", "root-2", "root-4", "rpc.get"
+    ]);
+}
+
+#[test]
+#[serial]
+fn root_with_trace_id() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let trace_id = 0x0123_4567_89ab_cdef_0123_4567_89ab_cdef;
+    {
+        let root = Span::root_with_trace_id("root", trace_id);
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+
+        let span = Span::enter_with_parent("cross-thread", &root);
+        std::thread::spawn(move || {
+            let _g = span.set_local_parent();
+            let _span = LocalSpan::enter_with_local_parent("grandchild");
+        })
+        .join()
+        .unwrap();
+    }
+    drop(Span::root("other", SpanContext::random()));
+
+    minitrace::flush();
+
+    let records = collected_spans.lock().clone();
+    assert_eq!(records.len(), 5);
+    for record in records.iter().filter(|record| record.name != "other") {
+        assert_eq!(record.trace_id, TraceId(trace_id), "{}", record.name);
+    }
+    let root = records.iter().find(|record| record.name == "root").unwrap();
+    assert_eq!(root.parent_id, SpanId::default());
+    let other = records
+        .iter()
+        .find(|record| record.name == "other")
+        .unwrap();
+    assert_ne!(other.trace_id, TraceId(trace_id));
+}
+
+#[test]
+#[serial]
+fn macro_async_trait() {
+    use std::future::Future;
+    use std::pin::Pin;
+
+    #[trace(short_name = true, async_trait = true)]
+    fn work(value: u32) -> Pin<Box<dyn Future<Output = u32> + Send>> {
+        let _g = LocalSpan::enter_with_local_parent("prepare");
+        Box::pin(async move {
+            let _g = LocalSpan::enter_with_local_parent("poll");
+            value
+        })
+    }
+
+    #[trace(short_name = true, async_trait = false)]
+    fn create(value: u32) -> Pin<Box<dyn Future<Output = u32> + Send>> {
+        Box::pin(async move {
+            let _g = LocalSpan::enter_with_local_parent("poll");
+            value
+        })
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let future = work(1);
+        assert_eq!(block_on(future), 1);
+        assert_eq!(block_on(create(2)), 2);
+    }
+
+    minitrace::flush();
+
+    // The statements before the future are kept, and the span covering the future is created
+    // under the local parent at the point the future is. The span of `create` covers the creation
+    // of the future only.
+    let expected_graph = r#"
+root []
+    create []
+    poll []
+    prepare []
+        work []
+            poll []
 "#;
     assert_eq!(
         tree_str_from_span_records(collected_spans.lock().clone()),
@@ -589,6 +1056,2134 @@ root []
     );
 }
 
+#[test]
+#[serial]
+fn macro_legacy_async_trait() {
+    use std::future::Future;
+    use std::pin::Pin;
+
+    // The shape generated by async-trait (<=0.1.43).
+    #[trace(short_name = true)]
+    fn work(value: u32) -> Pin<Box<dyn Future<Output = u32> + Send>> {
+        async fn __work(value: u32) -> u32 {
+            let _g = LocalSpan::enter_with_local_parent("poll");
+            value
+        }
+        Box::pin(__work(value))
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(block_on(work(1)), 1);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    work []
+        poll []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_conventional() {
+    #[trace(conventional = true)]
+    fn sync_work() {
+        {
+            let _g = LocalSpan::enter_with_local_parent("inside");
+        }
+        drop(span);
+        let _g = LocalSpan::enter_with_local_parent("after");
+    }
+
+    #[trace(conventional = true)]
+    async fn async_work() {
+        async {}.await;
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        sync_work();
+        block_on(async_work().in_span(Span::enter_with_local_parent("task")));
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    after []
+    sync_work []
+        inside []
+    task []
+        async_work []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_returning_impl_future() {
+    use std::future::Future;
+
+    #[trace(short_name = true)]
+    fn work(value: u32) -> impl Future<Output = u32> + Send {
+        let _g = LocalSpan::enter_with_local_parent("prepare");
+        async move {
+            let _g = LocalSpan::enter_with_local_parent("poll");
+            value
+        }
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let future = work(1);
+        assert_eq!(block_on(future), 1);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    prepare []
+        work []
+            poll []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_returning_impl_future_enter_on_poll() {
+    use std::future::Future;
+
+    async fn inner(value: u32) -> u32 {
+        let _g = LocalSpan::enter_with_local_parent("poll");
+        value
+    }
+
+    #[trace(short_name = true, enter_on_poll = true)]
+    fn work(value: u32) -> impl Future<Output = u32> {
+        if value == 0 {
+            return inner(1);
+        }
+        inner(value)
+    }
+
+    #[trace(short_name = true)]
+    fn eager(value: u32) -> impl Future<Output = u32> {
+        inner(value)
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let future = work(0);
+        assert_eq!(block_on(future), 1);
+        let future = work(2);
+        assert_eq!(block_on(future), 2);
+
+        // The span covers the call only, and the future is polled outside of it.
+        let future = eager(3);
+        assert_eq!(block_on(future), 3);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    eager []
+    poll []
+    work []
+        poll []
+    work []
+        poll []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_catch_panic() {
+    #[trace(short_name = true, catch_panic = true)]
+    fn check(a: u32) -> u32 {
+        let _g = LocalSpan::enter_with_local_parent("inner");
+        assert!(a > 0, "invalid value {a}");
+        a
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(check(1), 1);
+        let panic = std::panic::catch_unwind(|| check(0)).unwrap_err();
+        assert_eq!(panic.downcast_ref::<String>().unwrap(), "invalid value 0");
+
+        // The location is only recorded once the panic hook is installed.
+        minitrace::install_panic_hook();
+        std::panic::catch_unwind(|| check(0)).unwrap_err();
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let checks = span_records
+        .iter()
+        .filter(|span| span.name == "check")
+        .collect::<Vec<_>>();
+    assert_eq!(checks.len(), 3);
+    let ok = checks
+        .iter()
+        .find(|span| span.properties.is_empty())
+        .unwrap();
+    assert_eq!(ok.status, SpanStatus::Unset);
+
+    let panicked = checks
+        .iter()
+        .filter(|span| !span.properties.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(panicked.len(), 2);
+    for panicked in &panicked {
+        assert_eq!(panicked.status, SpanStatus::Error);
+        assert_eq!(
+            panicked.properties[0],
+            ("panic.message".into(), "invalid value 0".into())
+        );
+    }
+    assert_eq!(panicked[0].properties.len(), 1);
+    let (key, location) = &panicked[1].properties[1];
+    assert_eq!(key, "panic.location");
+    assert!(location.starts_with(concat!(file!(), ":")), "{location}");
+}
+
+#[test]
+#[serial]
+fn macro_root() {
+    #[trace(short_name = true, root = true)]
+    fn handle() {
+        let _g = LocalSpan::enter_with_local_parent("process");
+    }
+
+    #[trace(short_name = true, root = true)]
+    async fn handle_async() {
+        let _g = LocalSpan::enter_with_local_parent("process");
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    // Without a local parent, the spans are recorded nonetheless.
+    handle();
+    minitrace::flush();
+
+    let expected_graph = r#"
+handle []
+    process []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().drain(..).collect()),
+        expected_graph
+    );
+
+    block_on(handle_async());
+    minitrace::flush();
+
+    let expected_graph = r#"
+handle_async []
+    process []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().drain(..).collect()),
+        expected_graph
+    );
+
+    // With a local parent, the spans start a trace of their own.
+    let trace_id = TraceId(7);
+    {
+        let root = Span::root("root", SpanContext::new(trace_id, SpanId::default()));
+        let _g = root.set_local_parent();
+
+        handle();
+        block_on(handle_async());
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    assert_eq!(span_records.len(), 5);
+    for name in ["handle", "handle_async"] {
+        let span = span_records.iter().find(|s| s.name == name).unwrap();
+        assert_ne!(span.trace_id, trace_id);
+        assert_eq!(span.parent_id, SpanId::default());
+        assert!(span_records.iter().any(|s| s.name == "process"
+            && s.trace_id == span.trace_id
+            && s.parent_id == span.span_id));
+    }
+}
+
+#[test]
+#[serial]
+fn macro_buffer() {
+    #[trace(buffer = 2, display = [a, b])]
+    fn add(a: u32, b: u32) -> u32 {
+        a + b
+    }
+
+    #[trace(buffer = 1, display = [a])]
+    async fn double(a: u32) -> u32 {
+        a * 2
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random()).with_properties_capacity(1);
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("local")
+            .with_properties_capacity(1)
+            .with_property(|| ("k", "v"));
+
+        add(1, 2);
+        block_on(double(3));
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let add = span_records
+        .iter()
+        .find(|s| s.name.ends_with("::add"))
+        .unwrap();
+    assert_eq!(add.properties, vec![
+        ("a".into(), "1".into()),
+        ("b".into(), "2".into())
+    ]);
+    let double = span_records
+        .iter()
+        .find(|s| s.name.ends_with("::double"))
+        .unwrap();
+    assert_eq!(double.properties, vec![("a".into(), "3".into())]);
+    let local = span_records.iter().find(|s| s.name == "local").unwrap();
+    assert_eq!(local.properties, vec![("k".into(), "v".into())]);
+}
+
+#[test]
+#[serial]
+fn macro_guard() {
+    #[trace(short_name = true, guard = span)]
+    fn end_early() {
+        let inside = LocalSpan::enter_with_local_parent("inside");
+        drop(inside);
+        drop(span);
+        let _g = LocalSpan::enter_with_local_parent("after");
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        end_early();
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    after []
+    end_early []
+        inside []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_recorder() {
+    #[trace(short_name = true, recorder = myspan)]
+    fn record(value: u32) -> u32 {
+        let _myspan = myspan.with_property(|| ("value", value.to_string()));
+        value
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        record(7);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    record [("value", "7")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_recurse() {
+    #[trace(short_name = true, recurse = true)]
+    fn outer() -> u32 {
+        fn helper(a: u32) -> u32 {
+            fn leaf(a: u32) -> u32 {
+                a + 1
+            }
+
+            leaf(a) * 2
+        }
+
+        #[trace(name = "renamed")]
+        fn traced() {}
+
+        const fn constant() -> u32 {
+            1
+        }
+
+        traced();
+        helper(constant())
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(outer(), 4);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    outer []
+        helper []
+            leaf []
+        renamed []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_recurse_only() {
+    #[trace(short_name = true, recurse = true, recurse_only = traced)]
+    fn outer() -> u32 {
+        mod handlers {
+            #[traced]
+            pub fn handle(a: u32) -> u32 {
+                validate(a) + 1
+            }
+
+            pub fn validate(a: u32) -> u32 {
+                a * 2
+            }
+        }
+
+        #[traced]
+        fn helper(a: u32) -> u32 {
+            fn leaf(a: u32) -> u32 {
+                a + 1
+            }
+
+            leaf(a)
+        }
+
+        handlers::handle(helper(1))
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(outer(), 5);
+    }
+
+    minitrace::flush();
+
+    // Only the marked functions are traced.
+    let expected_graph = r#"
+root []
+    outer []
+        handle []
+        helper []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_child_count() {
+    #[trace(short_name = true)]
+    fn child() {
+        let _g = LocalSpan::enter_with_local_parent("grandchild");
+    }
+
+    #[trace(short_name = true, child_count = true)]
+    fn parent() {
+        for _ in 0..3 {
+            child();
+        }
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        parent();
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let parent = span_records.iter().find(|s| s.name == "parent").unwrap();
+    assert_eq!(parent.properties, vec![("child_count".into(), "3".into())]);
+}
+
+#[test]
+#[serial]
+fn macro_coalesce() {
+    #[trace(short_name = true, coalesce = true)]
+    fn step(i: usize) -> usize {
+        i + 1
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        for i in 0..100 {
+            step(i);
+        }
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let steps = span_records
+        .iter()
+        .filter(|s| s.name == "step")
+        .collect::<Vec<_>>();
+    assert_eq!(steps.len(), 1);
+    assert_eq!(steps[0].properties, vec![("count".into(), "100".into())]);
+}
+
+#[test]
+#[serial]
+fn macro_scope_threads() {
+    #[trace(short_name = true, scope = Scope::Threads, guard = span)]
+    fn work() {
+        let _local = LocalSpan::enter_with_local_parent("local");
+        std::thread::scope(|scope| {
+            scope.spawn(|| {
+                let _child = Span::enter_with_parent("thread", &span);
+            });
+        });
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        work();
+    }
+
+    minitrace::flush();
+
+    let graph = tree_str_from_span_records(collected_spans.lock().clone());
+    assert_eq!(
+        graph,
+        r"
+root []
+    work []
+        local []
+        thread []
+"
+    );
+}
+
+#[test]
+#[serial]
+fn macro_parent() {
+    #[trace(short_name = true, parent = "request")]
+    fn audit() {
+        let _local = LocalSpan::enter_with_local_parent("local");
+    }
+
+    #[trace(short_name = true)]
+    fn handle() {
+        let _local = LocalSpan::enter_with_local_parent("validate");
+        audit();
+    }
+
+    #[trace(short_name = true, parent = "request")]
+    async fn notify() {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    // Without a local parent, no span is recorded.
+    audit();
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        {
+            let _request = LocalSpan::enter_with_local_parent("request");
+            handle();
+        }
+        // Without an enclosing span of the name, no span is recorded.
+        audit();
+    }
+
+    {
+        let request = Span::root("request", SpanContext::random());
+        let _g = request.set_local_parent();
+        handle();
+        block_on(notify());
+    }
+
+    // Only a static name of the local parent is matched.
+    let owned_trace_id = TraceId(1501);
+    {
+        let request = Span::root(
+            String::from("request"),
+            SpanContext::new(owned_trace_id, SpanId::default()),
+        );
+        let _g = request.set_local_parent();
+        audit();
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let graph = |root: &str| {
+        let trace_id = span_records
+            .iter()
+            .find(|s| {
+                s.name == root && s.parent_id == SpanId::default() && s.trace_id != owned_trace_id
+            })
+            .unwrap()
+            .trace_id;
+        tree_str_from_span_records(
+            span_records
+                .iter()
+                .filter(|s| s.trace_id == trace_id)
+                .cloned()
+                .collect(),
+        )
+    };
+    // The span of `audit` is not recorded outside of `request`, so its local span is a child of
+    // the local parent.
+    assert_eq!(
+        graph("root"),
+        r"
+root []
+    local []
+    request []
+        audit []
+            local []
+        handle []
+            validate []
+"
+    );
+    assert_eq!(
+        graph("request"),
+        r"
+request []
+    audit []
+        local []
+    handle []
+        validate []
+    notify []
+"
+    );
+    assert_eq!(
+        tree_str_from_span_records(
+            span_records
+                .iter()
+                .filter(|s| s.trace_id == owned_trace_id)
+                .cloned()
+                .collect()
+        ),
+        r"
+request []
+    local []
+"
+    );
+}
+
+#[test]
+#[serial]
+fn macro_no_mangle() {
+    #[no_mangle]
+    #[trace(short_name = true)]
+    pub extern "C" fn minitrace_test_no_mangle(a: u32) -> u32 {
+        a + 1
+    }
+
+    // Resolved by the unmangled symbol name.
+    mod ffi {
+        extern "C" {
+            pub fn minitrace_test_no_mangle(a: u32) -> u32;
+        }
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(unsafe { ffi::minitrace_test_no_mangle(1) }, 2);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    minitrace_test_no_mangle []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_sample() {
+    #[trace(short_name = true, sample = 1.0)]
+    fn always() {}
+
+    #[trace(short_name = true, sample = 0.0)]
+    fn never() {}
+
+    #[trace(short_name = true, sample = 1.0)]
+    async fn always_async() {}
+
+    #[trace(name = "never_async", sample = 0.0)]
+    async fn never_async() {}
+
+    #[trace(err = true, sample = 0.0)]
+    fn never_err() -> Result<(), String> {
+        Err("oops".to_string())
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        for _ in 0..10 {
+            always();
+            never();
+            block_on(always_async());
+            block_on(never_async());
+            never_err().ok();
+        }
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let count = |name: &str| span_records.iter().filter(|s| s.name == name).count();
+    assert_eq!(count("always"), 10);
+    assert_eq!(count("never"), 0);
+    assert_eq!(count("always_async"), 10);
+    assert_eq!(count("never_async"), 0);
+    assert_eq!(span_records.len(), 21);
+
+    // The error of an unsampled call is not recorded on the parent span.
+    let root = span_records.iter().find(|s| s.name == "root").unwrap();
+    assert!(root.properties.is_empty());
+}
+
+#[test]
+#[serial]
+fn macro_display() {
+    struct Point(i32, i32);
+
+    impl std::fmt::Display for Point {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "({}, {})", self.0, self.1)
+        }
+    }
+
+    #[trace(short_name = true, display = [point, label])]
+    fn draw(point: Point, label: &str) -> i32 {
+        point.0 + point.1
+    }
+
+    #[trace(short_name = true, display = [point])]
+    async fn draw_async(point: Point) -> i32 {
+        point.0 + point.1
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(draw(Point(1, 2), "a"), 3);
+        assert_eq!(block_on(draw_async(Point(3, 4))), 7);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    draw [("label", "a"), ("point", "(1, 2)")]
+    draw_async [("point", "(3, 4)")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_unsampled_root() {
+    #[trace(short_name = true, display = [i])]
+    fn work(i: usize) {
+        let _g = LocalSpan::enter_with_local_parent("inner");
+    }
+
+    #[trace(short_name = true)]
+    async fn work_async() {
+        work(0);
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::noop();
+        let _g = root.set_local_parent();
+
+        for i in 0..10 {
+            work(i);
+        }
+        block_on(work_async());
+    }
+
+    minitrace::flush();
+
+    assert!(collected_spans.lock().is_empty());
+}
+
+#[test]
+#[serial]
+fn macro_variables() {
+    #[derive(Debug)]
+    struct Point(i32, i32);
+
+    #[trace(short_name = true, variables = [point, label])]
+    fn draw(point: Point, label: &str) -> i32 {
+        point.0 + point.1
+    }
+
+    #[trace(short_name = true, variables = [point])]
+    async fn draw_async(point: Point) -> i32 {
+        point.0 + point.1
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(draw(Point(1, 2), "a"), 3);
+        assert_eq!(block_on(draw_async(Point(3, 4))), 7);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    draw [("label", "\"a\""), ("point", "Point(1, 2)")]
+    draw_async [("point", "Point(3, 4)")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_redact() {
+    fn mask_email(email: &str) -> String {
+        match email.split_once('@') {
+            Some((_, domain)) => format!("***@{domain}"),
+            None => "***".to_string(),
+        }
+    }
+
+    #[trace(
+        short_name = true,
+        display = [user, email],
+        variables = [password],
+        redact = [password, email = mask_email]
+    )]
+    fn login(user: &str, email: &str, password: &str) -> bool {
+        !user.is_empty() && !email.is_empty() && !password.is_empty()
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert!(login("alice", "alice@example.com", "hunter2"));
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    login [("email", "***@example.com"), ("password", "***"), ("user", "alice")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_type_params() {
+    #[trace(short_name = true, type_params = true)]
+    fn process<T: Default, U>() -> T {
+        T::default()
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(process::<u32, ()>(), 0);
+        assert_eq!(process::<String, &str>(), "");
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    process [("T", "alloc::string::String"), ("U", "&str")]
+    process [("T", "u32"), ("U", "()")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_detached_task() {
+    use futures::channel::oneshot;
+
+    #[trace(short_name = true)]
+    async fn spawn_detached(rx: oneshot::Receiver<()>, done: std::sync::mpsc::Sender<()>) {
+        let span = Span::root("detached", SpanContext::current_local_parent().unwrap());
+        tokio::spawn(async move {
+            async {
+                rx.await.ok();
+            }
+            .in_span(span)
+            .await;
+            done.send(()).unwrap();
+        });
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+
+        let runtime = Builder::new_multi_thread()
+            .worker_threads(4)
+            .enable_all()
+            .build()
+            .unwrap();
+
+        let (tx, rx) = oneshot::channel();
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        block_on(runtime.spawn(spawn_detached(rx, done_tx).in_span(root))).unwrap();
+
+        // The detached task outlives the traced function.
+        std::thread::sleep(Duration::from_millis(10));
+        tx.send(()).unwrap();
+        done_rx.recv().unwrap();
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let spawner = span_records
+        .iter()
+        .find(|span| span.name == "spawn_detached")
+        .unwrap();
+    let detached = span_records
+        .iter()
+        .find(|span| span.name == "detached")
+        .unwrap();
+    assert_eq!(detached.trace_id, spawner.trace_id);
+    assert_eq!(detached.parent_id, spawner.span_id);
+    assert!(
+        detached.begin_time_unix_ns + detached.duration_ns
+            > spawner.begin_time_unix_ns + spawner.duration_ns
+    );
+    assert!(spawner.duration_ns < 10_000_000);
+
+    let expected_graph = r#"
+root []
+    spawn_detached []
+        detached []
+"#;
+    assert_eq!(tree_str_from_span_records(span_records), expected_graph);
+}
+
+#[test]
+#[serial]
+fn macro_argument_order() {
+    #[trace(name = "name-first", enter_on_poll = true)]
+    async fn f() {}
+
+    #[trace(enter_on_poll = true, name = "name-last")]
+    async fn g() {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        block_on(f());
+        block_on(g());
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    name-first []
+    name-last []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_lazy() {
+    use async_trait::async_trait;
+
+    #[async_trait]
+    trait Foo {
+        async fn eager(&self);
+        async fn lazy(&self);
+    }
+
+    struct Bar;
+
+    #[async_trait]
+    impl Foo for Bar {
+        #[trace(short_name = true)]
+        async fn eager(&self) {}
+
+        #[trace(short_name = true, lazy = true)]
+        async fn lazy(&self) {}
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        // Neither future is polled, but only the eager one has already created its span.
+        drop(Bar.eager());
+        drop(Bar.lazy());
+
+        block_on(Bar.lazy());
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    eager []
+    lazy []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_err() {
+    fn check(a: u32) -> Result<u32, String> {
+        if a > 0 {
+            Ok(a)
+        } else {
+            Err(format!("invalid value {a}"))
+        }
+    }
+
+    #[trace(short_name = true, err = true)]
+    fn sync_check(a: u32) -> Result<u32, String> {
+        let a = check(a)?;
+        Ok(a)
+    }
+
+    #[trace(short_name = true, err = true)]
+    async fn async_check(a: u32) -> Result<u32, String> {
+        let a = check(a)?;
+        // Spans created after `?` would have short-circuited must not be recorded.
+        let _g = LocalSpan::enter_with_local_parent("after-check");
+        Ok(a)
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(sync_check(1), Ok(1));
+        assert!(sync_check(0).is_err());
+        assert_eq!(block_on(async_check(2)), Ok(2));
+        assert!(block_on(async_check(0)).is_err());
+
+        LocalSpan::set_status(SpanStatus::Ok);
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    for span in &span_records {
+        let expected_status = if span.name == "root" {
+            SpanStatus::Ok
+        } else if span.properties.iter().any(|(k, _)| k == "error") {
+            SpanStatus::Error
+        } else {
+            SpanStatus::Unset
+        };
+        assert_eq!(span.status, expected_status, "{}", span.name);
+    }
+
+    let expected_graph = r#"
+root []
+    async_check [("error", "invalid value 0")]
+    async_check []
+        after-check []
+    sync_check []
+    sync_check [("error", "invalid value 0")]
+"#;
+    assert_eq!(tree_str_from_span_records(span_records), expected_graph);
+}
+
+#[test]
+#[serial]
+fn macro_status_code() {
+    fn check(a: u32) -> Result<u32, String> {
+        if a > 0 {
+            Ok(a)
+        } else {
+            Err(format!("invalid value {a}"))
+        }
+    }
+
+    #[trace(ok_code = 0, err_code = 2)]
+    fn sync_check(a: u32) -> Result<u32, String> {
+        let a = check(a)?;
+        Ok(a)
+    }
+
+    #[trace(ok_code = "OK", err_code = "INVALID_ARGUMENT")]
+    async fn async_check(a: u32) -> Result<u32, String> {
+        let a = check(a)?;
+        Ok(a)
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(sync_check(1), Ok(1));
+        assert!(sync_check(0).is_err());
+        assert_eq!(block_on(async_check(2)), Ok(2));
+        assert!(block_on(async_check(0)).is_err());
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    lib::macro_status_code::{{closure}}::async_check [("status_code", "INVALID_ARGUMENT")]
+    lib::macro_status_code::{{closure}}::async_check [("status_code", "OK")]
+    lib::macro_status_code::{{closure}}::sync_check [("status_code", "0")]
+    lib::macro_status_code::{{closure}}::sync_check [("status_code", "2")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn trace_block() {
+    use minitrace::trace_block;
+
+    fn classify(n: i32) -> Option<&'static str> {
+        let kind = match n {
+            0 => trace_block!("zero", { "zero" }),
+            n if n < 0 => trace_block!("negative", {
+                if n == i32::MIN {
+                    return None;
+                }
+                "negative"
+            }),
+            _ => "positive",
+        };
+        if let Some(kind) = kind.strip_suffix("tive") {
+            trace_block!("strip", {
+                LocalSpan::add_property(|| ("kind", kind));
+            });
+        }
+        Some(kind)
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(classify(0), Some("zero"));
+        assert_eq!(classify(-1), Some("negative"));
+        assert_eq!(classify(i32::MIN), None);
+        assert_eq!(classify(1), Some("positive"));
+        // The span of the block ends with the arm, so this one is a sibling of the blocks.
+        let _span = LocalSpan::enter_with_local_parent("after");
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    after []
+    negative []
+    negative []
+    strip [("kind", "nega")]
+    strip [("kind", "posi")]
+    zero []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_passthrough() {
+    use std::cell::Cell;
+    use std::future::Future;
+    use std::pin::Pin;
+    use std::task::Context;
+    use std::task::Poll;
+
+    /// Ready after being polled `remaining + 1` times, counting the polls.
+    struct Yield<'a> {
+        remaining: usize,
+        polls: &'a Cell<usize>,
+    }
+
+    impl Future for Yield<'_> {
+        type Output = usize;
+
+        fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<usize> {
+            self.polls.set(self.polls.get() + 1);
+            if self.remaining == 0 {
+                return Poll::Ready(self.polls.get());
+            }
+            self.remaining -= 1;
+            cx.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+
+    #[trace(short_name = true)]
+    async fn wrapped(polls: &Cell<usize>) -> usize {
+        Yield {
+            remaining: 3,
+            polls,
+        }
+        .await
+    }
+
+    #[trace(short_name = true, passthrough = true)]
+    async fn passthrough(polls: &Cell<usize>) -> usize {
+        Yield {
+            remaining: 3,
+            polls,
+        }
+        .await
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let polls = Cell::new(0);
+        assert_eq!(block_on(wrapped(&polls)), 4);
+        let polls = Cell::new(0);
+        assert_eq!(block_on(passthrough(&polls)), 4);
+
+        // The future of the body is not wrapped in another async block.
+        let polls = Cell::new(0);
+        assert!(
+            std::mem::size_of_val(&passthrough(&polls)) < std::mem::size_of_val(&wrapped(&polls))
+        );
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    passthrough []
+    wrapped []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_record_return() {
+    #[derive(Debug)]
+    struct Point {
+        x: i32,
+        y: i32,
+    }
+
+    #[trace(short_name = true, record_return = true)]
+    fn sync_point(x: i32) -> Point {
+        if x < 0 {
+            return Point { x: 0, y: 0 };
+        }
+        Point { x, y: x * 2 }
+    }
+
+    #[trace(short_name = true, record_return = true)]
+    async fn async_parse(s: &str) -> Result<u32, std::num::ParseIntError> {
+        let n = s.parse::<u32>()?;
+        Ok(n + 1)
+    }
+
+    // Recording `()` is skipped.
+    #[trace(short_name = true, record_return = true)]
+    #[allow(clippy::unused_unit)]
+    fn unit() -> () {}
+
+    #[trace(short_name = true, record_return = true)]
+    async fn async_unit() {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        let point = sync_point(1);
+        assert_eq!((point.x, point.y), (1, 2));
+        let point = sync_point(-1);
+        assert_eq!((point.x, point.y), (0, 0));
+        assert_eq!(block_on(async_parse("41")), Ok(42));
+        assert!(block_on(async_parse("x")).is_err());
+        unit();
+        block_on(async_unit());
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    async_parse [("return", "Err(ParseIntError { kind: InvalidDigit })")]
+    async_parse [("return", "Ok(42)")]
+    async_unit []
+    sync_point [("return", "Point { x: 0, y: 0 }")]
+    sync_point [("return", "Point { x: 1, y: 2 }")]
+    unit []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn macro_many_arguments() {
+    #[trace(short_name = true, display = [a], err = true, ok_code = 0, err_code = 2)]
+    fn check(a: u32) -> Result<u32, String> {
+        if a > 0 {
+            Ok(a)
+        } else {
+            Err("invalid value".to_string())
+        }
+    }
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        assert_eq!(check(1), Ok(1));
+        assert!(check(0).is_err());
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    check [("a", "0"), ("error", "invalid value"), ("status_code", "2")]
+    check [("a", "1"), ("status_code", "0")]
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn properties_order() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random())
+            .with_property(|| ("c", "1"))
+            .with_properties(|| [("a", "2"), ("b", "3")]);
+        let _g = root.set_local_parent();
+
+        let _span = LocalSpan::enter_with_local_parent("span")
+            .with_property(|| ("z", "1"))
+            .with_property(|| ("x", "2"));
+        LocalSpan::add_property(|| ("y", "3"));
+        LocalSpan::add_properties(|| [("b", "4"), ("a", "5")]);
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let properties = |name: &str| {
+        span_records
+            .iter()
+            .find(|span| span.name == name)
+            .unwrap()
+            .properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+    };
+    let expected = |properties: &[(&str, &str)]| {
+        properties
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        properties("root"),
+        expected(&[("c", "1"), ("a", "2"), ("b", "3")])
+    );
+    assert_eq!(
+        properties("span"),
+        expected(&[("z", "1"), ("x", "2"), ("y", "3"), ("b", "4"), ("a", "5")])
+    );
+}
+
+#[test]
+#[serial]
+fn local_span_batch() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let per_span_trace_id = TraceId(1);
+    let batch_trace_id = TraceId(2);
+
+    {
+        let root = Span::root("root", SpanContext::new(per_span_trace_id, SpanId(0)));
+        let _g = root.set_local_parent();
+
+        for i in 0..10 {
+            let _g = LocalSpan::enter_with_local_parent(format!("span{}", i));
+        }
+    }
+
+    {
+        let root = Span::root("root", SpanContext::new(batch_trace_id, SpanId(0)));
+        let _g = root.set_local_parent();
+
+        let mut batch = LocalSpanBatch::with_capacity(10);
+        for i in 0..10 {
+            let _g = batch.enter(format!("span{}", i));
+        }
+    }
+
+    minitrace::flush();
+
+    let span_records = collected_spans.lock().clone();
+    let trace = |trace_id: TraceId| {
+        span_records
+            .iter()
+            .filter(|span| span.trace_id == trace_id)
+            .cloned()
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(
+        tree_str_from_span_records(trace(per_span_trace_id)),
+        tree_str_from_span_records(trace(batch_trace_id))
+    );
+
+    let batch = trace(batch_trace_id);
+    let root = batch.iter().find(|span| span.name == "root").unwrap();
+    let mut spans = batch
+        .iter()
+        .filter(|span| span.name != "root")
+        .collect::<Vec<_>>();
+    spans.sort_by_key(|span| span.begin_time_unix_ns);
+    assert_eq!(spans.len(), 10);
+    for (i, span) in spans.iter().enumerate() {
+        assert_eq!(span.name, format!("span{}", i));
+        assert_eq!(span.parent_id, root.span_id);
+        assert!(span.begin_time_unix_ns >= root.begin_time_unix_ns);
+        assert!(
+            span.begin_time_unix_ns + span.duration_ns
+                <= root.begin_time_unix_ns + root.duration_ns
+        );
+    }
+}
+
+#[test]
+#[serial]
+fn multiple_local_parent() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _g = LocalSpan::enter_with_local_parent("span1");
+        let span2 = Span::enter_with_local_parent("span2");
+        {
+            let _g = span2.set_local_parent();
+            let _g = LocalSpan::enter_with_local_parent("span3");
+        }
+        let _g = LocalSpan::enter_with_local_parent("span4");
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    span1 []
+        span2 []
+            span3 []
+        span4 []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn early_local_collect() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let local_collector = LocalCollector::start();
+        let _g1 = LocalSpan::enter_with_local_parent("span1");
+        let _g2 = LocalSpan::enter_with_local_parent("span2");
+        drop(_g2);
+        let local_spans = local_collector.collect();
+
+        let root = Span::root("root", SpanContext::random());
+        root.push_child_spans(local_spans);
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    span1 []
+        span2 []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn record_into() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let mut records = Vec::new();
+    {
+        let root = Span::root("root", SpanContext::random());
+        {
+            let _g = root.set_local_parent();
+            four_spans();
+        }
+        root.record_into(&mut records);
+    }
+
+    {
+        let root = Span::root("reported", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+
+    minitrace::flush();
+
+    let expected_graph = r#"
+root []
+    iter-span-0 [("tmp_property", "tmp_value")]
+    iter-span-1 [("tmp_property", "tmp_value")]
+    rec-span []
+        rec-span []
+"#;
+    assert_eq!(tree_str_from_span_records(records), expected_graph);
+
+    let expected_graph = r#"
+reported []
+    child []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn snapshot() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let names = |records: Vec<SpanRecord>| {
+        let mut names = records
+            .into_iter()
+            .map(|record| record.name.to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        names
+    };
+
+    let root = Span::root("root", SpanContext::random());
+    {
+        let _g = root.set_local_parent();
+        LocalSpan::add_property(|| ("k", "v"));
+        let _span = LocalSpan::enter_with_local_parent("a");
+    }
+    assert_eq!(names(root.snapshot()), vec!["a"]);
+
+    {
+        let _g = root.set_local_parent();
+        let _b = LocalSpan::enter_with_local_parent("b");
+        let _c = LocalSpan::enter_with_local_parent("c");
+    }
+    let _d = Span::enter_with_parent("d", &root);
+    assert_eq!(names(root.snapshot()), vec!["b", "c"]);
+    assert!(root.snapshot().is_empty());
+
+    drop(_d);
+    drop(root);
+    minitrace::flush();
+
+    let expected_graph = r#"
+root [("k", "v")]
+    d []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().clone()),
+        expected_graph
+    );
+}
+
+#[test]
+#[serial]
+fn root_streaming() {
+    #[trace(short_name = true)]
+    fn outer() {
+        inner();
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    #[trace(short_name = true)]
+    fn inner() {}
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let (root, records) = Span::root_streaming("root", SpanContext::random());
+    {
+        let _g = root.set_local_parent();
+        outer();
+    }
+
+    // The completed spans are received while the root span is still running.
+    let timeout = Duration::from_secs(5);
+    assert_eq!(records.recv_timeout(timeout).unwrap().name, "inner");
+    assert_eq!(records.recv_timeout(timeout).unwrap().name, "outer");
+    assert!(records.try_recv().is_err());
+
+    {
+        let _g = root.set_local_parent();
+        Event::add_to_local_parent("event", || []);
+        LocalSpan::add_property(|| ("k", "v"));
+    }
+    drop(root);
+
+    let record = records.recv_timeout(timeout).unwrap();
+    assert_eq!(record.name, "root");
+    assert_eq!(record.events.len(), 1);
+    assert_eq!(record.properties, vec![("k".into(), "v".into())]);
+    assert!(records.recv_timeout(timeout).is_err());
+
+    minitrace::flush();
+    assert!(collected_spans.lock().is_empty());
+}
+
+#[test]
+#[serial]
+fn diff() {
+    fn run(rename: bool) -> Vec<SpanRecord> {
+        #[trace(short_name = true)]
+        fn get() {}
+
+        #[trace(short_name = true)]
+        fn put() {
+            let _g = LocalSpan::enter_with_local_parent("write");
+        }
+
+        #[trace(short_name = true)]
+        fn store() {
+            let _g = LocalSpan::enter_with_local_parent("write");
+        }
+
+        let (reporter, collected_spans) = TestReporter::new();
+        minitrace::set_reporter(reporter, Config::default());
+
+        {
+            let root = Span::root("root", SpanContext::random());
+            let _g = root.set_local_parent();
+
+            get();
+            if rename {
+                store();
+            } else {
+                put();
+            }
+        }
+
+        minitrace::flush();
+        let records = collected_spans.lock().clone();
+        records
+    }
+
+    let expected = run(false);
+    assert!(minitrace::collector::diff(&expected, &run(false)).is_empty());
+
+    assert_eq!(
+        minitrace::collector::diff(&expected, &run(true)),
+        minitrace::collector::TraceDiff {
+            renamed: vec![("root/put".to_string(), "root/store".to_string())],
+            ..Default::default()
+        }
+    );
+}
+
+#[test]
+#[serial]
+fn latency_report() {
+    let (reporter, _) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default().latency_report(true));
+
+    {
+        let root = Span::root("latency-root", SpanContext::random());
+        let _g = root.set_local_parent();
+        for _ in 0..100 {
+            let _span = LocalSpan::enter_with_local_parent("latency-op");
+        }
+    }
+
+    minitrace::flush();
+
+    let report = minitrace::collector::latency_report();
+    let op = report.iter().find(|p| p.name == "latency-op").unwrap();
+    assert_eq!(op.count, 100);
+    assert!(op.p50 <= op.p90 && op.p90 <= op.p99);
+    let root = report.iter().find(|p| p.name == "latency-root").unwrap();
+    assert_eq!(root.count, 1);
+
+    assert_eq!(minitrace::collector::take_latency_report(), report);
+    assert!(minitrace::collector::latency_report().is_empty());
+}
+
+#[test]
+#[serial]
+fn to_csv() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random()).with_property(|| ("k", "a,b"));
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+
+    minitrace::flush();
+
+    let csv = minitrace::collector::to_csv(&collected_spans.lock());
+    let mut lines = csv.lines();
+    assert_eq!(
+        lines.next(),
+        Some("id,parent_id,event,begin_ns,duration_ns,properties")
+    );
+    assert_eq!(lines.count(), 2);
+    assert!(csv.contains(",root,") && csv.contains(",\"k=a,b\"\n"));
+}
+
+#[test]
+#[serial]
+fn to_chrome_trace() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random()).with_property(|| ("k", "\"v\""));
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+    }
+
+    minitrace::flush();
+
+    let trace = minitrace::collector::to_chrome_trace(&collected_spans.lock());
+    let events: serde_json::Value = serde_json::from_str(&trace).unwrap();
+    let events = events.as_array().unwrap();
+    assert_eq!(events.len(), 2);
+    for event in events {
+        for field in ["name", "ph", "ts", "dur", "pid", "tid"] {
+            assert!(event.get(field).is_some(), "{field} missing in {event}");
+        }
+        assert_eq!(event["ph"], "X");
+        assert!(event["ts"].as_f64().unwrap() > 0.0);
+    }
+    let root = events.iter().find(|event| event["name"] == "root").unwrap();
+    assert_eq!(root["args"]["k"], "\"v\"");
+}
+
+#[test]
+#[serial]
+fn to_flamegraph_svg() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        let _span = LocalSpan::enter_with_local_parent("child");
+        std::thread::sleep(Duration::from_millis(1));
+    }
+
+    minitrace::flush();
+
+    let svg = minitrace::collector::to_flamegraph_svg(&collected_spans.lock());
+    let svg = &svg[svg.find("<svg").unwrap()..];
+    assert!(svg.starts_with("<svg"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+    assert!(svg.contains(">root (") && svg.contains(">child ("));
+}
+
+#[test]
+#[serial]
+fn resource() {
+    use std::borrow::Cow;
+    use std::sync::Arc;
+    use std::sync::Mutex;
+
+    use futures::future::BoxFuture;
+    use minitrace_opentelemetry::OpenTelemetryReporter;
+    use opentelemetry::sdk::export::trace::ExportResult;
+    use opentelemetry::sdk::export::trace::SpanData;
+    use opentelemetry::sdk::export::trace::SpanExporter;
+    use opentelemetry::sdk::Resource;
+    use opentelemetry::trace::SpanKind;
+    use opentelemetry::InstrumentationLibrary;
+    use opentelemetry::Key;
+    use opentelemetry::KeyValue;
+    use opentelemetry::Value;
+
+    #[derive(Clone, Debug, Default)]
+    struct CaptureExporter(Arc<Mutex<Vec<SpanData>>>);
+
+    impl SpanExporter for CaptureExporter {
+        fn export(&mut self, batch: Vec<SpanData>) -> BoxFuture<'static, ExportResult> {
+            self.0.lock().unwrap().extend(batch);
+            Box::pin(async { Ok(()) })
+        }
+    }
+
+    minitrace::set_resource([
+        ("service.name", "minitrace-test"),
+        ("service.version", env!("CARGO_PKG_VERSION")),
+    ]);
+
+    let exporter = CaptureExporter::default();
+    let reporter = OpenTelemetryReporter::new(
+        exporter.clone(),
+        SpanKind::Server,
+        Cow::Owned(Resource::new([KeyValue::new("host.name", "localhost")])),
+        InstrumentationLibrary::new("minitrace-test", None::<&str>, None::<&str>, None),
+    );
+    minitrace::set_reporter(reporter, Config::default());
+
+    drop(Span::root("root", SpanContext::random()));
+    minitrace::flush();
+
+    let spans = exporter.0.lock().unwrap();
+    assert_eq!(spans.len(), 1);
+    let resource = &spans[0].resource;
+    assert_eq!(
+        resource.get(Key::from_static_str("service.name")),
+        Some(Value::from("minitrace-test"))
+    );
+    assert_eq!(
+        resource.get(Key::from_static_str("service.version")),
+        Some(Value::from(env!("CARGO_PKG_VERSION")))
+    );
+    assert_eq!(
+        resource.get(Key::from_static_str("host.name")),
+        Some(Value::from("localhost"))
+    );
+}
+
+/// A writer into a buffer shared with its clones, to read what a reporter has written.
+#[derive(Clone, Default)]
+struct SharedBuf(std::sync::Arc<std::sync::Mutex<Vec<u8>>>);
+
+impl SharedBuf {
+    fn take_string(&self) -> String {
+        String::from_utf8(std::mem::take(&mut *self.0.lock().unwrap())).unwrap()
+    }
+}
+
+impl std::io::Write for SharedBuf {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.lock().unwrap().write(buf)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[test]
+#[serial]
+fn tree_reporter() {
+    use minitrace::collector::TreeReporter;
+
+    let buf = SharedBuf::default();
+    minitrace::set_reporter(TreeReporter::with_writer(buf.clone()), Config::default());
+
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+        {
+            let _span = LocalSpan::enter_with_local_parent("a").with_property(|| ("k", "v"));
+            let _span = LocalSpan::enter_with_local_parent("b");
+        }
+        let _span = LocalSpan::enter_with_local_parent("c");
+    }
+
+    minitrace::flush();
+
+    let output = buf.take_string();
+    let lines = output
+        .lines()
+        .map(|line| {
+            let (span, properties) = line.split_once(" (").unwrap();
+            let properties = properties.split_once(") ").map(|(_, p)| p);
+            (span.to_string(), properties)
+        })
+        .collect::<Vec<_>>();
+    assert_eq!(lines, vec![
+        ("root".to_string(), None),
+        ("    a".to_string(), Some("k=v")),
+        ("        b".to_string(), None),
+        ("    c".to_string(), None),
+    ]);
+}
+
+#[test]
+#[serial]
+fn tree_reporter_bytes() {
+    use minitrace::collector::TreeReporter;
+
+    let root = || {
+        Span::root("root", SpanContext::random())
+            .with_properties(|| [("read.bytes", "1572864"), ("name_bytes", "n/a")])
+    };
+
+    let properties = |output: String| output.trim_end().split_once(") ").unwrap().1.to_string();
+
+    // The sizes are only formatted if asked for.
+    let buf = SharedBuf::default();
+    minitrace::set_reporter(TreeReporter::with_writer(buf.clone()), Config::default());
+    drop(root());
+    minitrace::flush();
+    assert_eq!(
+        properties(buf.take_string()),
+        "read.bytes=1572864 name_bytes=n/a"
+    );
+
+    minitrace::set_reporter(
+        TreeReporter::with_writer(buf.clone()).format_bytes(true),
+        Config::default(),
+    );
+    drop(root());
+    minitrace::flush();
+    assert_eq!(
+        properties(buf.take_string()),
+        "read.bytes=1.5 MiB name_bytes=n/a"
+    );
+
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+    drop(root());
+    minitrace::flush();
+
+    assert_eq!(collected_spans.lock()[0].properties, vec![
+        ("read.bytes".into(), "1572864".into()),
+        ("name_bytes".into(), "n/a".into()),
+    ]);
+}
+
 #[test]
 #[serial]
 fn max_spans_per_trace() {
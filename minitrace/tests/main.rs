@@ -0,0 +1,16 @@
+// Copyright 2023 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::prelude::*;
+
+#[trace(short_name = true)]
+fn child() -> Option<SpanContext> {
+    SpanContext::current_local_parent()
+}
+
+#[minitrace::main]
+fn main() {
+    let root = SpanContext::current_local_parent().expect("`main` should be in a root span");
+    let child = child().unwrap();
+    assert_eq!(child.trace_id, root.trace_id);
+    assert_ne!(child.span_id, root.span_id);
+}
@@ -0,0 +1,42 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::sync::Mutex;
+
+use minitrace::collector::Reporter;
+use minitrace::collector::SpanRecord;
+use minitrace::prelude::*;
+
+static NAMES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+
+struct NameReporter;
+
+impl Reporter for NameReporter {
+    fn report(&mut self, spans: &[SpanRecord]) {
+        let mut names = NAMES.lock().unwrap();
+        names.extend(spans.iter().map(|span| span.name.to_string()));
+        names.sort();
+    }
+}
+
+#[trace(short_name = true)]
+fn child() {}
+
+#[minitrace::main(reporter = NameReporter)]
+fn with_reporter() {
+    child();
+}
+
+#[minitrace::main]
+fn with_default_reporter() {
+    child();
+}
+
+fn main() {
+    with_reporter();
+    assert_eq!(*NAMES.lock().unwrap(), ["child", "with_reporter"]);
+
+    // The reporter already set is kept rather than replaced by the console reporter.
+    NAMES.lock().unwrap().clear();
+    with_default_reporter();
+    assert_eq!(*NAMES.lock().unwrap(), ["child", "with_default_reporter"]);
+}
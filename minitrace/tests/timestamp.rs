@@ -0,0 +1,48 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use std::time::Duration;
+use std::time::SystemTime;
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+
+#[trace(short_name = true, timestamp = true)]
+fn work() {}
+
+#[trace(short_name = true, timestamp = true)]
+async fn work_async() {}
+
+#[test]
+fn timestamp() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let before = SystemTime::now() - Duration::from_secs(1);
+    {
+        let root = Span::root("root", SpanContext::random());
+        let _g = root.set_local_parent();
+
+        work();
+        futures::executor::block_on(work_async());
+    }
+    let after = SystemTime::now() + Duration::from_secs(1);
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock();
+    for name in ["work", "work_async"] {
+        let span = spans.iter().find(|s| s.name == name).unwrap();
+        let (_, started_at) = span
+            .properties
+            .iter()
+            .find(|(k, _)| k == "started_at")
+            .unwrap();
+
+        // e.g. `2024-01-01T00:00:00Z`
+        assert_eq!(started_at.len(), 20, "{started_at}");
+        assert!(started_at.ends_with('Z'), "{started_at}");
+        let started_at = humantime::parse_rfc3339(started_at).unwrap();
+        assert!(before <= started_at && started_at <= after);
+    }
+}
@@ -0,0 +1,55 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+
+#[trace]
+async fn work_async() {}
+
+#[trace]
+fn work_sync() {}
+
+#[tokio::test]
+async fn tokio_task_id() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    let root = Span::root("root", SpanContext::random());
+    let task_id = tokio::spawn(
+        async {
+            work_async().await;
+            work_sync();
+            tokio::task::id()
+        }
+        .in_span(root),
+    )
+    .await
+    .unwrap();
+
+    // The test body runs in `block_on`, outside of a task.
+    {
+        let root = Span::root("outside", SpanContext::random());
+        let _g = root.set_local_parent();
+        work_sync();
+    }
+
+    minitrace::flush();
+
+    let spans = collected_spans.lock();
+    let task_ids = |parent: &str| {
+        let parent = spans.iter().find(|s| s.name == parent).unwrap();
+        spans
+            .iter()
+            .filter(|s| s.parent_id == parent.span_id)
+            .map(|s| {
+                s.properties
+                    .iter()
+                    .find(|(k, _)| k == "tokio.task_id")
+                    .map(|(_, v)| v.to_string())
+            })
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(task_ids("root"), vec![Some(task_id.to_string()); 2]);
+    assert_eq!(task_ids("outside"), vec![None]);
+}
@@ -0,0 +1,60 @@
+// Copyright 2024 TiKV Project Authors. Licensed under Apache-2.0.
+
+//! Test functions instrumented with `#[trace]`. They are in their own test binary, since the root
+//! span of an instrumented test is reported to the global collector shared by the tests of a
+//! binary.
+
+use futures::executor::block_on;
+use minitrace::collector::Config;
+use minitrace::collector::TestReporter;
+use minitrace::prelude::*;
+use minitrace::util::tree::tree_str_from_span_records;
+use serial_test::serial;
+
+#[trace(short_name = true)]
+fn check() {}
+
+// `#[test]` implies `test = true`, so the test runs in a root span. It is also called by
+// `test_attribute` below, once a reporter is set.
+#[trace(short_name = true)]
+#[test]
+#[serial]
+fn instrumented_test() {
+    check();
+}
+
+#[trace(short_name = true, test = true)]
+async fn instrumented_async_test() {
+    check();
+}
+
+#[test]
+#[serial]
+fn test_attribute() {
+    let (reporter, collected_spans) = TestReporter::new();
+    minitrace::set_reporter(reporter, Config::default());
+
+    instrumented_test();
+    minitrace::flush();
+
+    let expected_graph = r#"
+instrumented_test []
+    check []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().drain(..).collect()),
+        expected_graph
+    );
+
+    block_on(instrumented_async_test());
+    minitrace::flush();
+
+    let expected_graph = r#"
+instrumented_async_test []
+    check []
+"#;
+    assert_eq!(
+        tree_str_from_span_records(collected_spans.lock().drain(..).collect()),
+        expected_graph
+    );
+}